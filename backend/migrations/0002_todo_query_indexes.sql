@@ -0,0 +1,4 @@
+-- indexes backing GET /todos filtering and sorting
+CREATE INDEX IF NOT EXISTS idx_todos_archived_due_date ON todos(is_archived, due_date);
+CREATE INDEX IF NOT EXISTS idx_todos_archived_updated_at ON todos(is_archived, updated_at);
+CREATE INDEX IF NOT EXISTS idx_todos_course_due_date ON todos(course_id, due_date);
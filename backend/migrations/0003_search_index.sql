@@ -0,0 +1,83 @@
+-- full-text search over todos and courses
+-- trigram tokenizer so that Japanese titles (no word boundaries) are searchable by substring
+CREATE VIRTUAL TABLE IF NOT EXISTS todos_fts USING fts5(title, body, tokenize = 'trigram');
+CREATE VIRTUAL TABLE IF NOT EXISTS courses_fts USING fts5(title, body, tokenize = 'trigram');
+
+-- the fts rowid of a todo / course. The implicit rowid of todos / courses is
+-- not stable for TEXT primary keys (VACUUM may renumber it); an INTEGER
+-- PRIMARY KEY is, so the triggers look the rowid up here and delete by it.
+CREATE TABLE IF NOT EXISTS search_ids (
+    id INTEGER PRIMARY KEY,
+    entity TEXT NOT NULL CHECK (entity IN ('course', 'todo')),
+    entity_id TEXT NOT NULL,
+    UNIQUE (entity, entity_id)
+);
+
+-- archived rows are not indexed. The insert triggers delete first as well:
+-- INSERT OR REPLACE keeps the search_ids row of the replaced one.
+-- NOT EXISTS rather than INSERT OR IGNORE: the conflict policy of the outer
+-- statement would override it
+
+CREATE TRIGGER IF NOT EXISTS todos_fts_ai AFTER INSERT ON todos
+BEGIN
+    INSERT INTO search_ids (entity, entity_id)
+    SELECT 'todo', NEW.id WHERE NOT EXISTS (SELECT 1 FROM search_ids WHERE entity = 'todo' AND entity_id = NEW.id);
+    DELETE FROM todos_fts WHERE rowid = (SELECT id FROM search_ids WHERE entity = 'todo' AND entity_id = NEW.id);
+    INSERT INTO todos_fts(rowid, title, body)
+    SELECT id, NEW.title, '' FROM search_ids
+    WHERE entity = 'todo' AND entity_id = NEW.id AND NEW.is_archived = 0;
+END;
+
+CREATE TRIGGER IF NOT EXISTS todos_fts_au AFTER UPDATE ON todos
+BEGIN
+    DELETE FROM todos_fts WHERE rowid = (SELECT id FROM search_ids WHERE entity = 'todo' AND entity_id = OLD.id);
+    INSERT INTO todos_fts(rowid, title, body)
+    SELECT id, NEW.title, '' FROM search_ids
+    WHERE entity = 'todo' AND entity_id = NEW.id AND NEW.is_archived = 0;
+END;
+
+CREATE TRIGGER IF NOT EXISTS todos_fts_ad AFTER DELETE ON todos
+BEGIN
+    DELETE FROM todos_fts WHERE rowid = (SELECT id FROM search_ids WHERE entity = 'todo' AND entity_id = OLD.id);
+    DELETE FROM search_ids WHERE entity = 'todo' AND entity_id = OLD.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS courses_fts_ai AFTER INSERT ON courses
+BEGIN
+    INSERT INTO search_ids (entity, entity_id)
+    SELECT 'course', NEW.id WHERE NOT EXISTS (SELECT 1 FROM search_ids WHERE entity = 'course' AND entity_id = NEW.id);
+    DELETE FROM courses_fts WHERE rowid = (SELECT id FROM search_ids WHERE entity = 'course' AND entity_id = NEW.id);
+    INSERT INTO courses_fts(rowid, title, body)
+    SELECT id, NEW.title, trim(coalesce(NEW.instructor, '') || ' ' || coalesce(NEW.room, '')) FROM search_ids
+    WHERE entity = 'course' AND entity_id = NEW.id AND NEW.is_archived = 0;
+END;
+
+CREATE TRIGGER IF NOT EXISTS courses_fts_au AFTER UPDATE ON courses
+BEGIN
+    DELETE FROM courses_fts WHERE rowid = (SELECT id FROM search_ids WHERE entity = 'course' AND entity_id = OLD.id);
+    INSERT INTO courses_fts(rowid, title, body)
+    SELECT id, NEW.title, trim(coalesce(NEW.instructor, '') || ' ' || coalesce(NEW.room, '')) FROM search_ids
+    WHERE entity = 'course' AND entity_id = NEW.id AND NEW.is_archived = 0;
+END;
+
+CREATE TRIGGER IF NOT EXISTS courses_fts_ad AFTER DELETE ON courses
+BEGIN
+    DELETE FROM courses_fts WHERE rowid = (SELECT id FROM search_ids WHERE entity = 'course' AND entity_id = OLD.id);
+    DELETE FROM search_ids WHERE entity = 'course' AND entity_id = OLD.id;
+END;
+
+-- backfill existing rows
+INSERT INTO search_ids (entity, entity_id)
+SELECT 'todo', id FROM todos
+UNION ALL
+SELECT 'course', id FROM courses;
+
+INSERT INTO todos_fts(rowid, title, body)
+SELECT s.id, t.title, '' FROM todos t
+JOIN search_ids s ON s.entity = 'todo' AND s.entity_id = t.id
+WHERE t.is_archived = 0;
+
+INSERT INTO courses_fts(rowid, title, body)
+SELECT s.id, c.title, trim(coalesce(c.instructor, '') || ' ' || coalesce(c.room, '')) FROM courses c
+JOIN search_ids s ON s.entity = 'course' AND s.entity_id = c.id
+WHERE c.is_archived = 0;
@@ -0,0 +1,9 @@
+-- todos.status now stores TodoStatus keys instead of Notion option names;
+-- options other than the default representatives are kept as custom statuses
+UPDATE todos
+SET status = CASE status
+    WHEN '未着手' THEN 'not_started'
+    WHEN '進行中' THEN 'in_progress'
+    WHEN '完了' THEN 'done'
+    ELSE status
+END;
@@ -0,0 +1,57 @@
+-- timestamps are stored as RFC 3339 in UTC with milliseconds
+-- ("2026-10-18T12:00:00.000+00:00"), so text comparison follows time order;
+-- due dates as "YYYY-MM-DD" (all-day) or "YYYY-MM-DDTHH:MM:SSZ" (timed, UTC).
+--
+-- values SQLite cannot parse would fail to decode and break every listing
+-- and the sync. They are moved out of the way and kept in quarantined_values
+-- (logged at startup) so they can be fixed by hand: a due_date becomes the
+-- date of updated_at (today if that is unreadable too), an updated_at the
+-- Unix epoch (so Notion wins on the next pull), nullable columns NULL.
+CREATE TABLE IF NOT EXISTS quarantined_values (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity TEXT NOT NULL CHECK (entity IN ('course', 'todo')),
+    entity_id TEXT NOT NULL,
+    field TEXT NOT NULL,
+    value TEXT NOT NULL,
+    quarantined_at TEXT NOT NULL
+);
+
+-- due dates: "YYYY-MM-DD" (an existing day) or a date-time
+INSERT INTO quarantined_values (entity, entity_id, field, value, quarantined_at)
+SELECT 'todo', id, 'due_date', due_date, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')
+FROM todos
+WHERE NOT (length(due_date) = 10 AND date(due_date) IS due_date)
+  AND NOT (length(due_date) > 10 AND due_date GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9][T ]*'
+           AND strftime('%Y-%m-%dT%H:%M:%SZ', due_date) IS NOT NULL);
+
+-- timestamps
+INSERT INTO quarantined_values (entity, entity_id, field, value, quarantined_at)
+SELECT 'course', id, 'updated_at', updated_at, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')
+FROM courses WHERE strftime('%Y-%m-%dT%H:%M:%f+00:00', updated_at) IS NULL
+UNION ALL
+SELECT 'course', id, 'last_synced_at', last_synced_at, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')
+FROM courses WHERE last_synced_at IS NOT NULL AND strftime('%Y-%m-%dT%H:%M:%f+00:00', last_synced_at) IS NULL
+UNION ALL
+SELECT 'todo', id, 'updated_at', updated_at, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')
+FROM todos WHERE strftime('%Y-%m-%dT%H:%M:%f+00:00', updated_at) IS NULL
+UNION ALL
+SELECT 'todo', id, 'last_synced_at', last_synced_at, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')
+FROM todos WHERE last_synced_at IS NOT NULL AND strftime('%Y-%m-%dT%H:%M:%f+00:00', last_synced_at) IS NULL
+UNION ALL
+SELECT 'todo', id, 'completed_at', completed_at, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now')
+FROM todos WHERE completed_at IS NOT NULL AND strftime('%Y-%m-%dT%H:%M:%f+00:00', completed_at) IS NULL;
+
+UPDATE courses
+SET updated_at = coalesce(strftime('%Y-%m-%dT%H:%M:%f+00:00', updated_at), '1970-01-01T00:00:00.000+00:00'),
+    last_synced_at = strftime('%Y-%m-%dT%H:%M:%f+00:00', last_synced_at);
+
+UPDATE todos
+SET updated_at = coalesce(strftime('%Y-%m-%dT%H:%M:%f+00:00', updated_at), '1970-01-01T00:00:00.000+00:00'),
+    last_synced_at = strftime('%Y-%m-%dT%H:%M:%f+00:00', last_synced_at),
+    completed_at = strftime('%Y-%m-%dT%H:%M:%f+00:00', completed_at),
+    due_date = CASE
+        WHEN length(due_date) = 10 AND date(due_date) IS due_date THEN due_date
+        WHEN length(due_date) > 10 AND due_date GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9][T ]*'
+            THEN coalesce(strftime('%Y-%m-%dT%H:%M:%SZ', due_date), coalesce(date(updated_at), date('now')))
+        ELSE coalesce(date(updated_at), date('now'))
+    END;
@@ -0,0 +1,4 @@
+-- due-date ranges: due_date is the start, due_end the optional end (same text format).
+-- due_timezone keeps Notion's IANA time_zone so it can be sent back on push.
+ALTER TABLE todos ADD COLUMN due_end TEXT;
+ALTER TABLE todos ADD COLUMN due_timezone TEXT;
@@ -0,0 +1,41 @@
+-- semesters become rows of their own; courses link to them through course_semesters
+-- instead of the ", "-joined courses.semester text.
+CREATE TABLE IF NOT EXISTS semesters (
+    name TEXT PRIMARY KEY,
+    start_date TEXT,
+    end_date TEXT,
+    is_current INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS course_semesters (
+    course_id TEXT NOT NULL REFERENCES courses(id) ON DELETE CASCADE,
+    semester TEXT NOT NULL REFERENCES semesters(name) ON UPDATE CASCADE ON DELETE CASCADE,
+    -- order of the Notion multi-select
+    position INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (course_id, semester)
+);
+
+CREATE INDEX IF NOT EXISTS idx_course_semesters_semester ON course_semesters(semester);
+
+-- split the old column on ", "
+CREATE TEMP TABLE split_semesters AS
+WITH RECURSIVE split(course_id, position, name, rest) AS (
+    SELECT id, 0, NULL, semester || ', ' FROM courses
+    UNION ALL
+    SELECT course_id, position + 1,
+           trim(substr(rest, 1, instr(rest, ', ') - 1)),
+           substr(rest, instr(rest, ', ') + 2)
+    FROM split
+    WHERE rest <> ''
+)
+SELECT course_id, position, name FROM split WHERE name IS NOT NULL AND name <> '';
+
+INSERT OR IGNORE INTO semesters (name)
+SELECT DISTINCT name FROM split_semesters;
+
+INSERT OR IGNORE INTO course_semesters (course_id, semester, position)
+SELECT course_id, name, position FROM split_semesters;
+
+DROP TABLE split_semesters;
+
+ALTER TABLE courses DROP COLUMN semester;
@@ -0,0 +1,25 @@
+-- a course can meet several times a week: one row per slot instead of the
+-- single courses.day_of_week / courses.period pair.
+-- A slot is either a period of the timetable or a start/end time ("HH:MM").
+CREATE TABLE IF NOT EXISTS course_meetings (
+    course_id TEXT NOT NULL REFERENCES courses(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    day_of_week TEXT NOT NULL CHECK (day_of_week IN ('Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat', 'Sun')),
+    period INTEGER,
+    start_time TEXT,
+    end_time TEXT,
+    -- overrides courses.room for this slot
+    room TEXT,
+    PRIMARY KEY (course_id, position),
+    CHECK (period IS NOT NULL OR (start_time IS NOT NULL AND end_time IS NOT NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_course_meetings_day ON course_meetings(day_of_week, period);
+
+INSERT OR IGNORE INTO course_meetings (course_id, position, day_of_week, period)
+SELECT id, 0, day_of_week, period
+FROM courses
+WHERE day_of_week IN ('Mon', 'Tue', 'Wed', 'Thu', 'Fri', 'Sat', 'Sun') AND period > 0;
+
+ALTER TABLE courses DROP COLUMN day_of_week;
+ALTER TABLE courses DROP COLUMN period;
@@ -0,0 +1,82 @@
+-- instructors become a list: one row per name instead of the ", "-joined
+-- courses.instructor text.
+CREATE TABLE IF NOT EXISTS course_instructors (
+    course_id TEXT NOT NULL REFERENCES courses(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    -- order of the Notion multi-select
+    position INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (course_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_course_instructors_name ON course_instructors(name);
+
+WITH RECURSIVE split(course_id, position, name, rest) AS (
+    SELECT id, -1, NULL, instructor || ', ' FROM courses WHERE instructor IS NOT NULL
+    UNION ALL
+    SELECT course_id, position + 1,
+           trim(substr(rest, 1, instr(rest, ', ') - 1)),
+           substr(rest, instr(rest, ', ') + 2)
+    FROM split
+    WHERE rest <> ''
+)
+INSERT OR IGNORE INTO course_instructors (course_id, name, position)
+SELECT course_id, name, position FROM split WHERE name IS NOT NULL AND name <> '';
+
+-- the course search body ("instructors room") now reads course_instructors,
+-- so the triggers are recreated and the link table refreshes the row as well
+DROP TRIGGER IF EXISTS courses_fts_ai;
+DROP TRIGGER IF EXISTS courses_fts_au;
+
+CREATE TRIGGER IF NOT EXISTS courses_fts_ai AFTER INSERT ON courses
+BEGIN
+    INSERT INTO search_ids (entity, entity_id)
+    SELECT 'course', NEW.id WHERE NOT EXISTS (SELECT 1 FROM search_ids WHERE entity = 'course' AND entity_id = NEW.id);
+    DELETE FROM courses_fts WHERE rowid = (SELECT id FROM search_ids WHERE entity = 'course' AND entity_id = NEW.id);
+    INSERT INTO courses_fts(rowid, title, body)
+    SELECT id, NEW.title, trim(coalesce(NEW.room, '')) FROM search_ids
+    WHERE entity = 'course' AND entity_id = NEW.id AND NEW.is_archived = 0;
+END;
+
+CREATE TRIGGER IF NOT EXISTS courses_fts_au AFTER UPDATE ON courses
+BEGIN
+    DELETE FROM courses_fts WHERE rowid = (SELECT id FROM search_ids WHERE entity = 'course' AND entity_id = OLD.id);
+    INSERT INTO courses_fts(rowid, title, body)
+    SELECT id, NEW.title,
+        trim(coalesce((SELECT group_concat(name, ' ' ORDER BY position) FROM course_instructors
+                       WHERE course_id = NEW.id), '') || ' ' || coalesce(NEW.room, ''))
+    FROM search_ids
+    WHERE entity = 'course' AND entity_id = NEW.id AND NEW.is_archived = 0;
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_instructors_fts_ai AFTER INSERT ON course_instructors
+BEGIN
+    DELETE FROM courses_fts WHERE rowid = (SELECT id FROM search_ids WHERE entity = 'course' AND entity_id = NEW.course_id);
+    INSERT INTO courses_fts(rowid, title, body)
+    SELECT s.id, c.title,
+        trim(coalesce((SELECT group_concat(name, ' ' ORDER BY position) FROM course_instructors
+                       WHERE course_id = c.id), '') || ' ' || coalesce(c.room, ''))
+    FROM courses c JOIN search_ids s ON s.entity = 'course' AND s.entity_id = c.id
+    WHERE c.id = NEW.course_id AND c.is_archived = 0;
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_instructors_fts_ad AFTER DELETE ON course_instructors
+BEGIN
+    DELETE FROM courses_fts WHERE rowid = (SELECT id FROM search_ids WHERE entity = 'course' AND entity_id = OLD.course_id);
+    INSERT INTO courses_fts(rowid, title, body)
+    SELECT s.id, c.title,
+        trim(coalesce((SELECT group_concat(name, ' ' ORDER BY position) FROM course_instructors
+                       WHERE course_id = c.id), '') || ' ' || coalesce(c.room, ''))
+    FROM courses c JOIN search_ids s ON s.entity = 'course' AND s.entity_id = c.id
+    WHERE c.id = OLD.course_id AND c.is_archived = 0;
+END;
+
+ALTER TABLE courses DROP COLUMN instructor;
+
+-- re-index with the space-separated names
+DELETE FROM courses_fts;
+INSERT INTO courses_fts(rowid, title, body)
+SELECT s.id, c.title,
+    trim(coalesce((SELECT group_concat(name, ' ' ORDER BY position) FROM course_instructors
+                   WHERE course_id = c.id), '') || ' ' || coalesce(c.room, ''))
+FROM courses c JOIN search_ids s ON s.entity = 'course' AND s.entity_id = c.id
+WHERE c.is_archived = 0;
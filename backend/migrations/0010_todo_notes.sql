@@ -0,0 +1,23 @@
+-- todo notes: the Notion page body as Markdown. It becomes the search body of the todo.
+ALTER TABLE todos ADD COLUMN notes TEXT NOT NULL DEFAULT '';
+
+DROP TRIGGER IF EXISTS todos_fts_ai;
+DROP TRIGGER IF EXISTS todos_fts_au;
+
+CREATE TRIGGER IF NOT EXISTS todos_fts_ai AFTER INSERT ON todos
+BEGIN
+    INSERT INTO search_ids (entity, entity_id)
+    SELECT 'todo', NEW.id WHERE NOT EXISTS (SELECT 1 FROM search_ids WHERE entity = 'todo' AND entity_id = NEW.id);
+    DELETE FROM todos_fts WHERE rowid = (SELECT id FROM search_ids WHERE entity = 'todo' AND entity_id = NEW.id);
+    INSERT INTO todos_fts(rowid, title, body)
+    SELECT id, NEW.title, NEW.notes FROM search_ids
+    WHERE entity = 'todo' AND entity_id = NEW.id AND NEW.is_archived = 0;
+END;
+
+CREATE TRIGGER IF NOT EXISTS todos_fts_au AFTER UPDATE ON todos
+BEGIN
+    DELETE FROM todos_fts WHERE rowid = (SELECT id FROM search_ids WHERE entity = 'todo' AND entity_id = OLD.id);
+    INSERT INTO todos_fts(rowid, title, body)
+    SELECT id, NEW.title, NEW.notes FROM search_ids
+    WHERE entity = 'todo' AND entity_id = NEW.id AND NEW.is_archived = 0;
+END;
@@ -0,0 +1,12 @@
+-- checklist items of a todo; synced with the top-level to-do blocks of the
+-- Notion page (in page order).
+CREATE TABLE IF NOT EXISTS subtasks (
+    id TEXT PRIMARY KEY,
+    todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    done INTEGER NOT NULL DEFAULT 0,
+    position INTEGER NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_subtasks_todo_id ON subtasks(todo_id, position);
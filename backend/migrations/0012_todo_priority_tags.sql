@@ -0,0 +1,13 @@
+-- priority is the Notion "Priority" select (high / medium / low)
+ALTER TABLE todos ADD COLUMN priority TEXT CHECK (priority IN ('high', 'medium', 'low'));
+
+-- tags are the Notion "Tags" multi-select, one row per name
+CREATE TABLE IF NOT EXISTS todo_tags (
+    todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    -- order of the Notion multi-select
+    position INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (todo_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_todo_tags_name ON todo_tags(name);
@@ -0,0 +1,21 @@
+-- recurring todos: a rule attached to a template todo; upcoming instances are
+-- materialized as ordinary todos linked back through todos.series_id
+CREATE TABLE IF NOT EXISTS todo_series (
+    id TEXT PRIMARY KEY,
+    template_todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+    -- RRULE subset, e.g. FREQ=WEEKLY;BYDAY=MO,TH;UNTIL=20270131
+    rule TEXT NOT NULL,
+    start_date TEXT NOT NULL,
+    -- instances up to this date have been created
+    generated_until TEXT,
+    is_active INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+ALTER TABLE todos ADD COLUMN series_id TEXT REFERENCES todo_series(id) ON DELETE SET NULL;
+-- local date of the occurrence the todo was created for
+ALTER TABLE todos ADD COLUMN series_date TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_todos_series_date ON todos(series_id, series_date)
+WHERE series_id IS NOT NULL;
@@ -0,0 +1,23 @@
+-- reminder offsets in minutes before the deadline (JSON array, largest first).
+-- a course's offsets are the default for its todos; a todo's own offsets,
+-- even an empty list, override them (NULL = use the course's)
+ALTER TABLE courses ADD COLUMN reminder_offsets TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE todos ADD COLUMN reminder_offsets TEXT;
+
+-- reminders planned from the offsets; acknowledging one marks it delivered
+-- for every device
+CREATE TABLE IF NOT EXISTS reminders (
+    id TEXT PRIMARY KEY,
+    todo_id TEXT NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+    offset_minutes INTEGER NOT NULL,
+    remind_at TEXT NOT NULL,
+    due_at TEXT NOT NULL,
+    -- set once the reminder has been broadcast on the stream
+    notified_at TEXT,
+    acknowledged_at TEXT,
+    created_at TEXT NOT NULL,
+    UNIQUE (todo_id, offset_minutes, remind_at)
+);
+
+CREATE INDEX IF NOT EXISTS idx_reminders_pending ON reminders(remind_at)
+WHERE acknowledged_at IS NULL;
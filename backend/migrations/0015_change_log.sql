@@ -0,0 +1,178 @@
+-- delta sync: one row per course / todo holding the sequence number of its
+-- latest change. every change (API, sync pull, archive, delete, and changes to
+-- the rows aggregated into the entity) moves the row to a new, higher seq.
+-- AUTOINCREMENT keeps seq monotonic even after the newest row is replaced.
+CREATE TABLE IF NOT EXISTS change_log (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity TEXT NOT NULL CHECK (entity IN ('course', 'todo')),
+    entity_id TEXT NOT NULL,
+    changed_at TEXT NOT NULL,
+    UNIQUE (entity, entity_id)
+);
+
+-- existing rows count as changed once, so since=0 returns everything
+INSERT INTO change_log (entity, entity_id, changed_at)
+SELECT 'course', id, updated_at FROM courses ORDER BY updated_at;
+INSERT INTO change_log (entity, entity_id, changed_at)
+SELECT 'todo', id, updated_at FROM todos ORDER BY updated_at;
+
+-- DELETE + INSERT rather than INSERT OR REPLACE: the conflict policy of the
+-- outer statement (e.g. INSERT OR IGNORE INTO todos) would override it.
+-- updates are logged only when the content changes: sync bookkeeping
+-- (updated_at, sync_state, last_synced_at) and rewriting the same values are
+-- not changes, so a sync that pulls nothing new leaves the log alone
+
+CREATE TRIGGER IF NOT EXISTS courses_change_ai AFTER INSERT ON courses
+BEGIN
+    DELETE FROM change_log WHERE entity = 'course' AND entity_id = NEW.id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('course', NEW.id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS courses_change_au AFTER UPDATE ON courses
+WHEN NEW.title IS NOT OLD.title
+    OR NEW.room IS NOT OLD.room
+    OR NEW.is_archived IS NOT OLD.is_archived
+    OR NEW.reminder_offsets IS NOT OLD.reminder_offsets
+BEGIN
+    DELETE FROM change_log WHERE entity = 'course' AND entity_id = NEW.id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('course', NEW.id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS courses_change_ad AFTER DELETE ON courses
+BEGIN
+    DELETE FROM change_log WHERE entity = 'course' AND entity_id = OLD.id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('course', OLD.id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS todos_change_ai AFTER INSERT ON todos
+BEGIN
+    DELETE FROM change_log WHERE entity = 'todo' AND entity_id = NEW.id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('todo', NEW.id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS todos_change_au AFTER UPDATE ON todos
+WHEN NEW.course_id IS NOT OLD.course_id
+    OR NEW.title IS NOT OLD.title
+    OR NEW.due_date IS NOT OLD.due_date
+    OR NEW.due_end IS NOT OLD.due_end
+    OR NEW.due_timezone IS NOT OLD.due_timezone
+    OR NEW.status IS NOT OLD.status
+    OR NEW.priority IS NOT OLD.priority
+    OR NEW.notes IS NOT OLD.notes
+    OR NEW.completed_at IS NOT OLD.completed_at
+    OR NEW.is_archived IS NOT OLD.is_archived
+    OR NEW.series_id IS NOT OLD.series_id
+    OR NEW.series_date IS NOT OLD.series_date
+    OR NEW.reminder_offsets IS NOT OLD.reminder_offsets
+BEGIN
+    DELETE FROM change_log WHERE entity = 'todo' AND entity_id = NEW.id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('todo', NEW.id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS todos_change_ad AFTER DELETE ON todos
+BEGIN
+    DELETE FROM change_log WHERE entity = 'todo' AND entity_id = OLD.id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('todo', OLD.id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+-- rows aggregated into Course / Todo (semesters, meetings, instructors, tags, subtasks)
+
+CREATE TRIGGER IF NOT EXISTS course_semesters_change_ai AFTER INSERT ON course_semesters
+BEGIN
+    DELETE FROM change_log WHERE entity = 'course' AND entity_id = NEW.course_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('course', NEW.course_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_semesters_change_au AFTER UPDATE ON course_semesters
+WHEN NEW.semester IS NOT OLD.semester OR NEW.position IS NOT OLD.position
+BEGIN
+    DELETE FROM change_log WHERE entity = 'course' AND entity_id = NEW.course_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('course', NEW.course_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_semesters_change_ad AFTER DELETE ON course_semesters
+BEGIN
+    DELETE FROM change_log WHERE entity = 'course' AND entity_id = OLD.course_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('course', OLD.course_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_meetings_change_ai AFTER INSERT ON course_meetings
+BEGIN
+    DELETE FROM change_log WHERE entity = 'course' AND entity_id = NEW.course_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('course', NEW.course_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_meetings_change_au AFTER UPDATE ON course_meetings
+WHEN NEW.position IS NOT OLD.position
+    OR NEW.day_of_week IS NOT OLD.day_of_week
+    OR NEW.period IS NOT OLD.period
+    OR NEW.start_time IS NOT OLD.start_time
+    OR NEW.end_time IS NOT OLD.end_time
+    OR NEW.room IS NOT OLD.room
+BEGIN
+    DELETE FROM change_log WHERE entity = 'course' AND entity_id = NEW.course_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('course', NEW.course_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_meetings_change_ad AFTER DELETE ON course_meetings
+BEGIN
+    DELETE FROM change_log WHERE entity = 'course' AND entity_id = OLD.course_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('course', OLD.course_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_instructors_change_ai AFTER INSERT ON course_instructors
+BEGIN
+    DELETE FROM change_log WHERE entity = 'course' AND entity_id = NEW.course_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('course', NEW.course_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_instructors_change_au AFTER UPDATE ON course_instructors
+WHEN NEW.name IS NOT OLD.name OR NEW.position IS NOT OLD.position
+BEGIN
+    DELETE FROM change_log WHERE entity = 'course' AND entity_id = NEW.course_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('course', NEW.course_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_instructors_change_ad AFTER DELETE ON course_instructors
+BEGIN
+    DELETE FROM change_log WHERE entity = 'course' AND entity_id = OLD.course_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('course', OLD.course_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS todo_tags_change_ai AFTER INSERT ON todo_tags
+BEGIN
+    DELETE FROM change_log WHERE entity = 'todo' AND entity_id = NEW.todo_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('todo', NEW.todo_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS todo_tags_change_au AFTER UPDATE ON todo_tags
+WHEN NEW.name IS NOT OLD.name OR NEW.position IS NOT OLD.position
+BEGIN
+    DELETE FROM change_log WHERE entity = 'todo' AND entity_id = NEW.todo_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('todo', NEW.todo_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS todo_tags_change_ad AFTER DELETE ON todo_tags
+BEGIN
+    DELETE FROM change_log WHERE entity = 'todo' AND entity_id = OLD.todo_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('todo', OLD.todo_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS subtasks_change_ai AFTER INSERT ON subtasks
+BEGIN
+    DELETE FROM change_log WHERE entity = 'todo' AND entity_id = NEW.todo_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('todo', NEW.todo_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS subtasks_change_au AFTER UPDATE ON subtasks
+WHEN NEW.title IS NOT OLD.title OR NEW.done IS NOT OLD.done OR NEW.position IS NOT OLD.position
+BEGIN
+    DELETE FROM change_log WHERE entity = 'todo' AND entity_id = NEW.todo_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('todo', NEW.todo_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
+
+CREATE TRIGGER IF NOT EXISTS subtasks_change_ad AFTER DELETE ON subtasks
+BEGIN
+    DELETE FROM change_log WHERE entity = 'todo' AND entity_id = OLD.todo_id;
+    INSERT INTO change_log (entity, entity_id, changed_at) VALUES ('todo', OLD.todo_id, strftime('%Y-%m-%dT%H:%M:%f+00:00', 'now'));
+END;
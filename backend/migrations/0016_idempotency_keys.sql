@@ -0,0 +1,21 @@
+-- responses of POST / PATCH requests sent with an Idempotency-Key header,
+-- replayed when the same request is retried within the window. Keys are
+-- scoped per client (X-Client-Id, 'default' without the header), so two
+-- devices that happen to pick the same key don't collide.
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    client_id TEXT NOT NULL DEFAULT 'default',
+    key TEXT NOT NULL,
+    method TEXT NOT NULL,
+    path TEXT NOT NULL,
+    request_body BLOB NOT NULL,
+    -- NULL while the first request is still being handled
+    status INTEGER,
+    content_type TEXT,
+    -- the ETag header of the first response, replayed with it
+    etag TEXT,
+    response_body BLOB,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (client_id, key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_created_at ON idempotency_keys(created_at);
@@ -0,0 +1,126 @@
+-- optimistic concurrency: a per-row version for courses and todos, exposed as
+-- a weak ETag. every change to the content of the row, or of the rows
+-- aggregated into it, increments it (a single API write may move it by more
+-- than one). sync bookkeeping (updated_at, sync_state, last_synced_at) is not
+-- versioned, and rewriting the same values keeps the version.
+ALTER TABLE courses ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE todos ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+-- NEW.version = OLD.version skips the bump's own update (and updates that
+-- already bumped it); recursive triggers are off, so the trigger never re-enters
+
+CREATE TRIGGER IF NOT EXISTS courses_version_au AFTER UPDATE ON courses
+WHEN NEW.version = OLD.version
+    AND (NEW.title IS NOT OLD.title
+        OR NEW.room IS NOT OLD.room
+        OR NEW.is_archived IS NOT OLD.is_archived
+        OR NEW.reminder_offsets IS NOT OLD.reminder_offsets)
+BEGIN
+    UPDATE courses SET version = OLD.version + 1 WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS todos_version_au AFTER UPDATE ON todos
+WHEN NEW.version = OLD.version
+    AND (NEW.course_id IS NOT OLD.course_id
+        OR NEW.title IS NOT OLD.title
+        OR NEW.due_date IS NOT OLD.due_date
+        OR NEW.due_end IS NOT OLD.due_end
+        OR NEW.due_timezone IS NOT OLD.due_timezone
+        OR NEW.status IS NOT OLD.status
+        OR NEW.priority IS NOT OLD.priority
+        OR NEW.notes IS NOT OLD.notes
+        OR NEW.completed_at IS NOT OLD.completed_at
+        OR NEW.is_archived IS NOT OLD.is_archived
+        OR NEW.series_id IS NOT OLD.series_id
+        OR NEW.series_date IS NOT OLD.series_date
+        OR NEW.reminder_offsets IS NOT OLD.reminder_offsets)
+BEGIN
+    UPDATE todos SET version = OLD.version + 1 WHERE id = NEW.id;
+END;
+
+-- rows aggregated into Course / Todo (semesters, meetings, instructors, tags, subtasks)
+
+CREATE TRIGGER IF NOT EXISTS course_semesters_version_ai AFTER INSERT ON course_semesters
+BEGIN
+    UPDATE courses SET version = version + 1 WHERE id = NEW.course_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_semesters_version_au AFTER UPDATE ON course_semesters
+WHEN NEW.semester IS NOT OLD.semester OR NEW.position IS NOT OLD.position
+BEGIN
+    UPDATE courses SET version = version + 1 WHERE id = NEW.course_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_semesters_version_ad AFTER DELETE ON course_semesters
+BEGIN
+    UPDATE courses SET version = version + 1 WHERE id = OLD.course_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_meetings_version_ai AFTER INSERT ON course_meetings
+BEGIN
+    UPDATE courses SET version = version + 1 WHERE id = NEW.course_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_meetings_version_au AFTER UPDATE ON course_meetings
+WHEN NEW.position IS NOT OLD.position
+    OR NEW.day_of_week IS NOT OLD.day_of_week
+    OR NEW.period IS NOT OLD.period
+    OR NEW.start_time IS NOT OLD.start_time
+    OR NEW.end_time IS NOT OLD.end_time
+    OR NEW.room IS NOT OLD.room
+BEGIN
+    UPDATE courses SET version = version + 1 WHERE id = NEW.course_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_meetings_version_ad AFTER DELETE ON course_meetings
+BEGIN
+    UPDATE courses SET version = version + 1 WHERE id = OLD.course_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_instructors_version_ai AFTER INSERT ON course_instructors
+BEGIN
+    UPDATE courses SET version = version + 1 WHERE id = NEW.course_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_instructors_version_au AFTER UPDATE ON course_instructors
+WHEN NEW.name IS NOT OLD.name OR NEW.position IS NOT OLD.position
+BEGIN
+    UPDATE courses SET version = version + 1 WHERE id = NEW.course_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS course_instructors_version_ad AFTER DELETE ON course_instructors
+BEGIN
+    UPDATE courses SET version = version + 1 WHERE id = OLD.course_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS todo_tags_version_ai AFTER INSERT ON todo_tags
+BEGIN
+    UPDATE todos SET version = version + 1 WHERE id = NEW.todo_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS todo_tags_version_au AFTER UPDATE ON todo_tags
+WHEN NEW.name IS NOT OLD.name OR NEW.position IS NOT OLD.position
+BEGIN
+    UPDATE todos SET version = version + 1 WHERE id = NEW.todo_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS todo_tags_version_ad AFTER DELETE ON todo_tags
+BEGIN
+    UPDATE todos SET version = version + 1 WHERE id = OLD.todo_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS subtasks_version_ai AFTER INSERT ON subtasks
+BEGIN
+    UPDATE todos SET version = version + 1 WHERE id = NEW.todo_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS subtasks_version_au AFTER UPDATE ON subtasks
+WHEN NEW.title IS NOT OLD.title OR NEW.done IS NOT OLD.done OR NEW.position IS NOT OLD.position
+BEGIN
+    UPDATE todos SET version = version + 1 WHERE id = NEW.todo_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS subtasks_version_ad AFTER DELETE ON subtasks
+BEGIN
+    UPDATE todos SET version = version + 1 WHERE id = OLD.todo_id;
+END;
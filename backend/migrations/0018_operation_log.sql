@@ -0,0 +1,23 @@
+-- undo / redo: local mutations made through the API, per client (X-Client-Id),
+-- with the entity's state before and after as JSON snapshots (a semester's
+-- entity_id is its name). sync pulls are not recorded. undone_at marks entries
+-- on the client's redo stack; a new mutation by the same client discards them.
+CREATE TABLE IF NOT EXISTS operation_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    client_id TEXT NOT NULL,
+    action TEXT NOT NULL,
+    entity TEXT NOT NULL CHECK (entity IN ('course', 'todo', 'semester')),
+    entity_id TEXT NOT NULL,
+    -- NULL when the entity did not exist before (created) / after (deleted)
+    before TEXT,
+    after TEXT,
+    created_at TEXT NOT NULL,
+    undone_at TEXT,
+    -- operations written by one request share a group and are undone / redone
+    -- together (a series change touches the template and each of its instances).
+    -- NULL: the operation is a group of its own.
+    group_id INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_operation_log_client ON operation_log(client_id, id);
+CREATE INDEX IF NOT EXISTS idx_operation_log_group ON operation_log(group_id);
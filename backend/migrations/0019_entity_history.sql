@@ -0,0 +1,16 @@
+-- audit trail: every change to a course / todo / semester with where it came from.
+-- changes holds the changed fields as {"field": {"from": .., "to": ..}}
+-- (subtasks as a whole under "subtasks"); version and updated_at are left out.
+CREATE TABLE IF NOT EXISTS entity_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity TEXT NOT NULL CHECK (entity IN ('course', 'todo', 'semester')),
+    entity_id TEXT NOT NULL,
+    source TEXT NOT NULL CHECK (source IN ('api', 'sync_pull', 'sync_push', 'conflict_resolution', 'recurrence')),
+    -- X-Client-Id for api changes, NULL otherwise
+    client_id TEXT,
+    action TEXT NOT NULL,
+    changes TEXT NOT NULL,
+    changed_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_entity_history_entity ON entity_history(entity, entity_id, id);
@@ -0,0 +1,8 @@
+-- courses / todos created locally keep their own id; the Notion page made for
+-- them is recorded here and carries the id back in its "course_id" / "todo_id"
+-- property. NULL: the id is the page id (rows that came from Notion).
+ALTER TABLE courses ADD COLUMN notion_page_id TEXT;
+ALTER TABLE todos ADD COLUMN notion_page_id TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_courses_notion_page_id ON courses(notion_page_id);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_todos_notion_page_id ON todos(notion_page_id);
@@ -0,0 +1,93 @@
+use axum::http::{HeaderMap, HeaderValue, header};
+
+use crate::error::AppError;
+
+/// コース・todo 1 件の ETag: `W/"<version>"`
+///
+/// 版は内容の変更でだけ増え、同期の記録 (`updated_at`・`sync_state`・`last_synced_at`) は含まないので弱い ETag。
+pub fn etag(version: i64) -> String {
+    format!("W/\"{}\"", version)
+}
+
+/// 一覧の ETag: 返す行の id と版の並びのハッシュ (FNV-1a)
+///
+/// 行の追加・削除・並び替え・変更のどれでも変わる。
+pub fn list_etag<'a>(items: impl IntoIterator<Item = (&'a str, i64)>) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for (id, version) in items {
+        for byte in id.bytes().chain(version.to_le_bytes()).chain([0xff]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    format!("W/\"{:016x}\"", hash)
+}
+
+/// `ETag` ヘッダー
+pub fn etag_headers(etag: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        headers.insert(header::ETAG, value);
+    }
+    headers
+}
+
+/// `If-None-Match` が `etag` を含むか (弱い比較)。含めば `304 Not Modified` を返す
+pub fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    header_tags(headers, header::IF_NONE_MATCH)
+        .any(|tag| tag == "*" || opaque(tag) == opaque(etag))
+}
+
+/// `If-Match` があれば現在の版と比べる (ETag は弱いので弱い比較)。違えば `412 Precondition Failed`
+pub fn check_if_match(headers: &HeaderMap, version: i64) -> Result<(), AppError> {
+    if !headers.contains_key(header::IF_MATCH) {
+        return Ok(());
+    }
+    let current = etag(version);
+    if header_tags(headers, header::IF_MATCH).any(|tag| tag == "*" || opaque(tag) == opaque(&current)) {
+        Ok(())
+    } else {
+        Err(AppError::PreconditionFailed(format!(
+            "If-Match does not match the current version ({})",
+            current
+        )))
+    }
+}
+
+/// `W/` を除いた ETag の値
+fn opaque(tag: &str) -> &str {
+    tag.trim_start_matches("W/")
+}
+
+/// カンマ区切りで複数のヘッダーに分かれた ETag の一覧
+fn header_tags(headers: &HeaderMap, name: header::HeaderName) -> impl Iterator<Item = &str> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conditional_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_MATCH, HeaderValue::from_static("\"3\", W/\"4\""));
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("W/\"7\""));
+
+        assert!(check_if_match(&headers, 4).is_ok());
+        assert!(matches!(check_if_match(&headers, 5), Err(AppError::PreconditionFailed(_))));
+        assert!(check_if_match(&HeaderMap::new(), 5).is_ok());
+        assert!(if_none_match(&headers, &etag(7)));
+        assert!(!if_none_match(&headers, &etag(8)));
+
+        assert_eq!(list_etag([("a", 1), ("b", 2)]), list_etag([("a", 1), ("b", 2)]));
+        assert_ne!(list_etag([("a", 1), ("b", 2)]), list_etag([("b", 2), ("a", 1)]));
+        assert_ne!(list_etag([("a", 1)]), list_etag([("a", 2)]));
+    }
+}
@@ -0,0 +1,136 @@
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{FromRequest, FromRequestParts, Query, Request};
+use axum::http::request::Parts;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+
+use crate::error::{AppError, FieldError};
+use crate::models::operation::{DEFAULT_CLIENT_ID, MAX_CLIENT_ID_LEN};
+use crate::models::Validate;
+
+/// `Json<T>` を取り出して `Validate` を通す extractor
+///
+/// 型が合わないなどのデシリアライズエラーも `422` の `details` 形式で返す。
+pub struct ValidJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let CheckedJson { value, errors } = CheckedJson::<T>::from_request(req, state).await?;
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors).into_response());
+        }
+        Ok(Self(value))
+    }
+}
+
+/// `ValidJson` と同じく `Validate` を通すが、検証エラーはハンドラーに渡す
+///
+/// DB を参照する検証 (course_id の存在確認など) のエラーと合わせて、1 回の `422` で返すときに使う。
+pub struct CheckedJson<T> {
+    pub value: T,
+    pub errors: Vec<FieldError>,
+}
+
+impl<T, S> FromRequest<S> for CheckedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(mut value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| match rejection {
+                JsonRejection::JsonDataError(e) => {
+                    AppError::Validation(vec![FieldError::new("body", e.body_text())]).into_response()
+                }
+                other => other.into_response(),
+            })?;
+
+        value.normalize();
+        let errors = value.validate();
+        Ok(Self { value, errors })
+    }
+}
+
+/// `Query<T>` を取り出して `Validate` を通す extractor
+pub struct ValidQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ValidQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(mut value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection: QueryRejection| {
+                AppError::Validation(vec![FieldError::new("query", rejection.body_text())]).into_response()
+            })?;
+
+        value.normalize();
+        let errors = value.validate();
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors).into_response());
+        }
+        Ok(Self(value))
+    }
+}
+
+/// 操作ログをまとめる端末 id (`X-Client-Id` ヘッダー、無ければ `default`)
+pub struct ClientId(pub String);
+
+impl<S> FromRequestParts<S> for ClientId
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get("x-client-id") else {
+            return Ok(Self(DEFAULT_CLIENT_ID.to_string()));
+        };
+        match value.to_str().map(str::trim) {
+            Ok(id) if !id.is_empty() && id.len() <= MAX_CLIENT_ID_LEN => Ok(Self(id.to_string())),
+            _ => Err(AppError::BadRequest(format!(
+                "X-Client-Id must be 1 to {} visible ASCII characters",
+                MAX_CLIENT_ID_LEN
+            ))
+            .into_response()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::header;
+    use crate::models::NewTodoRequest;
+
+    #[tokio::test]
+    async fn test_checked_json_hands_errors_to_the_handler() {
+        let body = r#"{ "course_id": "missing", "title": " ", "due_date": "tomorrow" }"#;
+        let request = Request::builder()
+            .method("POST")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap();
+
+        let Ok(CheckedJson { value, errors }) = CheckedJson::<NewTodoRequest>::from_request(request, &()).await else {
+            panic!("field errors should not reject the request");
+        };
+        assert_eq!(value.course_id, "missing");
+        assert_eq!(errors.iter().map(|e| e.field.as_str()).collect::<Vec<_>>(), vec!["title", "due_date"]);
+    }
+}
@@ -0,0 +1,121 @@
+use axum::body::{Body, to_bytes};
+use axum::extract::{FromRequestParts, Request, State};
+use axum::http::{HeaderValue, Method, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use tracing::warn;
+
+use crate::db::repository;
+use crate::error::AppError;
+use crate::models::idempotency::MAX_IDEMPOTENCY_KEY_LEN;
+use crate::models::{IdempotencyState, StoredResponse};
+use crate::state::AppState;
+
+use super::extract::ClientId;
+
+/// 再送を安全にするためのリクエストヘッダー
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// 保存したレスポンスを返したときに付けるヘッダー
+pub const IDEMPOTENT_REPLAYED: &str = "idempotent-replayed";
+
+/// 保存するリクエスト・レスポンスの本文の上限 (axum の `Json` の上限と同じ)
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// `Idempotency-Key` 付きの POST / PATCH の最初のレスポンスを保存し、再送にはそれを返す
+///
+/// キーは端末 (`X-Client-Id`) ごと。同じキーでメソッド・パス・本文が違えば `409`。
+/// 最初のリクエストの処理中に届いた再送も `409`。`5xx` は保存しない (同じキーで再試行できる)。
+pub async fn idempotent(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    if !matches!(*req.method(), Method::POST | Method::PATCH) {
+        return next.run(req).await;
+    }
+    let Some(key) = req.headers().get(IDEMPOTENCY_KEY) else {
+        return next.run(req).await;
+    };
+    let key = match key.to_str() {
+        Ok(key) if !key.trim().is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN => key.trim().to_string(),
+        _ => {
+            return AppError::BadRequest(format!(
+                "Idempotency-Key must be 1 to {} visible ASCII characters",
+                MAX_IDEMPOTENCY_KEY_LEN
+            ))
+            .into_response();
+        }
+    };
+
+    let (mut parts, body) = req.into_parts();
+    let client = match ClientId::from_request_parts(&mut parts, &()).await {
+        Ok(ClientId(client)) => client,
+        Err(rejection) => return rejection,
+    };
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => return AppError::BadRequest("Request body is too large".to_string()).into_response(),
+    };
+    let method = parts.method.as_str().to_string();
+    let path = parts.uri.path_and_query().map_or_else(|| parts.uri.path(), |p| p.as_str()).to_string();
+
+    let begun = repository::begin_idempotent_request(
+        &state.db,
+        &client,
+        &key,
+        &method,
+        &path,
+        &body,
+        state.idempotency_ttl_secs,
+    )
+    .await;
+    match begun {
+        Ok(IdempotencyState::New) => {}
+        Ok(IdempotencyState::Replay(stored)) => return replay(stored),
+        Ok(IdempotencyState::InProgress) => {
+            return AppError::Conflict("A request with this Idempotency-Key is still being processed".to_string())
+                .into_response();
+        }
+        Ok(IdempotencyState::Mismatch) => {
+            return AppError::Conflict("Idempotency-Key was already used for a different request".to_string())
+                .into_response();
+        }
+        Err(e) => return AppError::from(e).into_response(),
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if response.status().is_server_error() {
+        let _ = repository::abandon_idempotent_request(&state.db, &client, &key).await;
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => {
+            let _ = repository::abandon_idempotent_request(&state.db, &client, &key).await;
+            return AppError::InternalServerError.into_response();
+        }
+    };
+    let stored = StoredResponse {
+        status: parts.status.as_u16(),
+        content_type: parts.headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(str::to_string),
+        etag: parts.headers.get(header::ETAG).and_then(|v| v.to_str().ok()).map(str::to_string),
+        body: body.to_vec(),
+    };
+    if let Err(e) = repository::complete_idempotent_request(&state.db, &client, &key, &stored).await {
+        // レスポンスは返す。次の再送は処理中扱いになり、古くなれば処理し直される
+        warn!("Failed to store idempotent response: {}", e);
+    }
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let mut response = Response::new(Body::from(stored.body));
+    *response.status_mut() = StatusCode::from_u16(stored.status).unwrap_or_default();
+    if let Some(content_type) = stored.content_type.and_then(|v| HeaderValue::from_str(&v).ok()) {
+        response.headers_mut().insert(header::CONTENT_TYPE, content_type);
+    }
+    if let Some(etag) = stored.etag.and_then(|v| HeaderValue::from_str(&v).ok()) {
+        response.headers_mut().insert(header::ETAG, etag);
+    }
+    response.headers_mut().insert(IDEMPOTENT_REPLAYED, HeaderValue::from_static("true"));
+    response
+}
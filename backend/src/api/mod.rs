@@ -1,81 +1,914 @@
+mod etag;
+mod extract;
+mod idempotency;
+
 use axum::Json;
-use axum::extract::Path;
+use chrono::{Duration, NaiveDate, Utc};
+use axum::extract::{Path, Query};
+use axum::http::{HeaderMap, HeaderValue};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::middleware;
 use axum::routing::{patch, post};
 use axum::{Router, extract::State, http::StatusCode, routing::get};
 
-use crate::error::AppError;
+use crate::error::{AppError, FieldError};
+use crate::models::agenda::local_midnight;
+use crate::models::change::{DEFAULT_CHANGES_LIMIT, MAX_CHANGES_LIMIT};
+use crate::models::history::DEFAULT_HISTORY_LIMIT;
 use crate::state::AppState;
-use crate::services::{SyncService, SyncStats};
+use crate::services::{pending_then_live, AppEvent, BatchService, EventEntity, RecurrenceService, ReminderService, SyncService, SyncStats, UndoService};
 use crate::models::*;
 use crate::db::repository;
+use extract::{CheckedJson, ClientId, ValidJson, ValidQuery};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::{Stream, StreamExt};
+use uuid::Uuid;
+
+/// `GET /todos` の 1 ページあたりの最大件数
+const MAX_PAGE_SIZE: u32 = 200;
+
+/// `GET /search` のデフォルト件数
+const DEFAULT_SEARCH_LIMIT: u32 = 20;
 
 pub fn router(state: AppState) -> Router {
     Router::new()
         .route("/health", get(health))
         .route("/courses", get(list_courses).post(create_course))
+        .route("/courses/{id}", get(get_course).patch(update_course).delete(delete_course))
+        .route("/courses/{id}/archive", patch(archive_course))
+        .route("/courses/{id}/history", get(course_history))
+        .route("/courses/{id}/reminders", get(get_course_reminders).put(update_course_reminders))
         .route("/todos", get(list_todos).post(create_todo))
-        .route("/todos/{id}", patch(update_todo))
+        .route("/todos/{id}", get(get_todo).patch(update_todo).delete(delete_todo))
         .route("/todos/{id}/archive", patch(archive_todo))
+        .route("/todos/{id}/complete", post(complete_todo))
+        .route("/todos/{id}/uncomplete", post(uncomplete_todo))
+        .route("/todos/{id}/history", get(todo_history))
+        .route("/todos/{id}/reminders", get(get_todo_reminders).put(update_todo_reminders))
+        .route("/todos/{id}/series", post(create_series))
+        .route("/todos/{id}/subtasks", get(list_subtasks).post(create_subtask))
+        .route("/todos/{id}/subtasks/{subtask_id}", patch(update_subtask).delete(delete_subtask))
+        .route("/series", get(list_series))
+        .route("/series/{id}", get(get_series).patch(update_series).delete(stop_series))
+        .route("/reminders/due", get(due_reminders))
+        .route("/reminders/stream", get(reminder_stream))
+        .route("/reminders/{id}/ack", post(acknowledge_reminder))
+        .route("/agenda", get(agenda))
+        .route("/instructors", get(list_instructors))
+        .route("/tags", get(list_tags))
+        .route("/semesters", get(list_semesters))
+        .route("/semesters/{name}", patch(update_semester))
+        .route("/semesters/{name}/history", get(semester_history))
+        .route("/timetable", get(timetable))
+        .route("/statuses", get(list_statuses))
+        .route("/search", get(search))
+        .route("/batch", post(batch))
+        .route("/undo", post(undo))
+        .route("/redo", post(redo))
         .route("/sync", post(sync_now))
+        .route("/changes", get(changes))
+        .route("/events", get(events))
+        .layer(middleware::from_fn_with_state(state.clone(), idempotency::idempotent))
         .with_state(state)
 }
 
+/// `ETag` を付けて返す。`If-None-Match` が一致すれば本文なしの `304 Not Modified`
+fn conditional(headers: &HeaderMap, tag: &str, body: impl IntoResponse) -> Response {
+    let etag_headers = etag::etag_headers(tag);
+    if etag::if_none_match(headers, tag) {
+        return (StatusCode::NOT_MODIFIED, etag_headers).into_response();
+    }
+    (etag_headers, body).into_response()
+}
+
 async fn health(State(state): State<AppState>) -> Result<StatusCode, AppError> {
     sqlx::query("select 1").execute(&state.db).await?;
     Ok(StatusCode::OK)
 }
 
-async fn list_courses(State(state): State<AppState>) -> Result<Json<Vec<Course>>, AppError> {
-    let courses = repository::fetch_courses(&state.db).await?;
-    Ok(Json(courses))
+async fn list_courses(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<CourseListQuery>,
+) -> Result<Response, AppError> {
+    let courses = repository::query_courses(&state.db, &query).await?;
+    let tag = etag::list_etag(courses.iter().map(|c| (c.id.as_str(), c.version)));
+    Ok(conditional(&headers, &tag, Json(courses)))
 }
 
 async fn create_course(
     State(state): State<AppState>,
-    Json(req): Json<NewCourseRequest>
-) -> Result<Json<Course>, AppError> {
-    let course = repository::insert_course(&state.db, req).await?;
-    Ok(Json(course))
+    client: ClientId,
+    ValidJson(req): ValidJson<NewCourseRequest>
+) -> Result<(HeaderMap, Json<Course>), AppError> {
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let course = repository::insert_course_in(&mut tx, Uuid::new_v4().to_string(), req).await?;
+    repository::record_operation_in(&mut tx, &client.0, "create_course", ChangeEntity::Course, &course.id, None).await?;
+    tx.commit().await?;
+    state.events.publish(AppEvent::CourseChanged { course: course.clone() });
+    Ok((etag::etag_headers(&etag::etag(course.version)), Json(course)))
+}
+
+async fn get_course(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let course = repository::find_course_by_id(&state.db, &id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    Ok(conditional(&headers, &etag::etag(course.version), Json(course)))
+}
+
+/// `If-Match` があれば、版の確認から書き込みまでを 1 つの書き込みトランザクションで行う
+async fn update_course(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    client: ClientId,
+    ValidJson(req): ValidJson<UpdateCourseRequest>
+) -> Result<(HeaderMap, Json<Course>), AppError> {
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let current = repository::find_course_in(&mut tx, &id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    etag::check_if_match(&headers, current.version)?;
+    let before = repository::snapshot_in(&mut tx, ChangeEntity::Course, &id).await?;
+    let course = repository::update_course_in(&mut tx, &id, req)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    repository::record_operation_in(&mut tx, &client.0, "update_course", ChangeEntity::Course, &id, before).await?;
+    tx.commit().await?;
+    state.events.publish(AppEvent::CourseChanged { course: course.clone() });
+    Ok((etag::etag_headers(&etag::etag(course.version)), Json(course)))
+}
+
+async fn archive_course(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    client: ClientId,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let before = repository::snapshot_in(&mut tx, ChangeEntity::Course, &id).await?;
+    if !repository::archive_course_in(&mut tx, &id).await? {
+        return Err(AppError::NotFound);
+    }
+    repository::record_operation_in(&mut tx, &client.0, "archive_course", ChangeEntity::Course, &id, before).await?;
+    let course = repository::find_course_in(&mut tx, &id).await?;
+    tx.commit().await?;
+    if let Some(course) = course {
+        state.events.publish(AppEvent::CourseChanged { course });
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_course(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    client: ClientId,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let before = repository::snapshot_in(&mut tx, ChangeEntity::Course, &id).await?;
+    if repository::delete_course_draft_in(&mut tx, &id).await? {
+        repository::record_operation_in(&mut tx, &client.0, "delete_course", ChangeEntity::Course, &id, before).await?;
+        tx.commit().await?;
+        state.events.publish(AppEvent::CourseDeleted { id });
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    match before {
+        Some(_) => Err(AppError::Conflict(
+            "Course has been synced to Notion; archive it instead".to_string(),
+        )),
+        None => Err(AppError::NotFound),
+    }
 }
 
-async fn list_todos(State(state): State<AppState>) -> Result<Json<Vec<Todo>>, AppError> {
-    let todos = repository::fetch_todos(&state.db).await?;
-    Ok(Json(todos))
+async fn list_todos(
+    State(state): State<AppState>,
+    request_headers: HeaderMap,
+    ValidQuery(mut query): ValidQuery<TodoListQuery>,
+) -> Result<Response, AppError> {
+    // the filter accepts Notion option names as well as keys
+    if query.status.is_some() {
+        let statuses: Vec<String> = query
+            .statuses()
+            .iter()
+            .map(|s| state.statuses.normalize(TodoStatus::parse(s)).to_string())
+            .collect();
+        query.status = Some(statuses.join(","));
+    }
+    if let Some(limit) = query.limit
+        && !(1..=MAX_PAGE_SIZE).contains(&limit)
+    {
+        return Err(AppError::BadRequest(format!("limit must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+
+    // A cursor that no longer resolves would silently yield an empty page
+    if let Some(cursor) = &query.cursor
+        && repository::find_todo_by_id(&state.db, cursor).await?.is_none()
+    {
+        return Err(AppError::BadRequest(format!("Unknown cursor: {}", cursor)));
+    }
+
+    // Fetch one extra row to know whether another page exists
+    let mut todos = repository::query_todos(&state.db, &query, query.limit.map(|l| l + 1), &state.timezone).await?;
+
+    let mut headers = HeaderMap::new();
+    if let Some(limit) = query.limit
+        && todos.len() > limit as usize
+    {
+        todos.truncate(limit as usize);
+        if let Some(last) = todos.last()
+            && let Ok(cursor) = HeaderValue::from_str(&last.id)
+        {
+            headers.insert("x-next-cursor", cursor);
+        }
+    }
+
+    let tag = etag::list_etag(todos.iter().map(|t| (t.id.as_str(), t.version)));
+    headers.extend(etag::etag_headers(&tag));
+    if etag::if_none_match(&request_headers, &tag) {
+        return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+    }
+    Ok((headers, Json(todos)).into_response())
 }
 
 async fn create_todo(
     State(state): State<AppState>,
-    Json(req): Json<NewTodoRequest>
-) -> Result<Json<Todo>, AppError> {
-    let todo = repository::insert_todo(&state.db, req).await?;
-    Ok(Json(todo))
+    client: ClientId,
+    CheckedJson { value: mut req, errors }: CheckedJson<NewTodoRequest>
+) -> Result<(HeaderMap, Json<Todo>), AppError> {
+    check_todo_references(&state, errors, Some(&req.course_id), Some(&req.status)).await?;
+    req.status = state.statuses.normalize(req.status);
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let todo = repository::insert_todo_in(&mut tx, Uuid::new_v4().to_string(), req).await?;
+    repository::record_operation_in(&mut tx, &client.0, "create_todo", ChangeEntity::Todo, &todo.id, None).await?;
+    tx.commit().await?;
+    state.events.publish(AppEvent::TodoCreated { todo: todo.clone() });
+    Ok((etag::etag_headers(&etag::etag(todo.version)), Json(todo)))
+}
+
+async fn get_todo(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let todo = repository::find_todo_by_id(&state.db, &id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    Ok(conditional(&headers, &etag::etag(todo.version), Json(todo)))
 }
 
+/// `If-Match` があれば、版の確認から書き込みまでを 1 つの書き込みトランザクションで行う
 async fn update_todo(
     State(state): State<AppState>,
     Path(id): Path<String>,
-    Json(req): Json<UpdateTodoRequest>
-) -> Result<Json<Todo>, AppError> {
-    let todo = repository::update_todo(&state.db, &id, req)
+    headers: HeaderMap,
+    client: ClientId,
+    CheckedJson { value: req, errors }: CheckedJson<UpdateTodoRequest>
+) -> Result<(HeaderMap, Json<Todo>), AppError> {
+    check_todo_references(&state, errors, None, req.status.as_ref()).await?;
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let current = repository::find_todo_in(&mut tx, &id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    etag::check_if_match(&headers, current.version)?;
+    if req.due_date.is_some() || req.due_end.as_deref().is_some_and(|end| !end.is_empty()) {
+        let errors = req.check_merged_due_range(&current);
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors));
+        }
+    }
+    let before = repository::snapshot_in(&mut tx, ChangeEntity::Todo, &id).await?;
+    let todo = repository::update_todo_in(&mut tx, &id, req, &state.statuses)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    repository::record_operation_in(&mut tx, &client.0, "update_todo", ChangeEntity::Todo, &id, before).await?;
+    tx.commit().await?;
+    state.events.publish(AppEvent::TodoUpdated { todo: todo.clone() });
+    Ok((etag::etag_headers(&etag::etag(todo.version)), Json(todo)))
+}
+
+async fn complete_todo(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    client: ClientId,
+) -> Result<(HeaderMap, Json<Todo>), AppError> {
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let before = repository::snapshot_in(&mut tx, ChangeEntity::Todo, &id).await?;
+    let todo = repository::set_todo_completed_in(&mut tx, &id, true, &state.statuses)
         .await?
         .ok_or(AppError::NotFound)?;
-    Ok(Json(todo))
+    repository::record_operation_in(&mut tx, &client.0, "complete_todo", ChangeEntity::Todo, &id, before).await?;
+    tx.commit().await?;
+    state.events.publish(AppEvent::TodoUpdated { todo: todo.clone() });
+    Ok((etag::etag_headers(&etag::etag(todo.version)), Json(todo)))
+}
+
+async fn uncomplete_todo(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    client: ClientId,
+) -> Result<(HeaderMap, Json<Todo>), AppError> {
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let before = repository::snapshot_in(&mut tx, ChangeEntity::Todo, &id).await?;
+    let todo = repository::set_todo_completed_in(&mut tx, &id, false, &state.statuses)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    repository::record_operation_in(&mut tx, &client.0, "uncomplete_todo", ChangeEntity::Todo, &id, before).await?;
+    tx.commit().await?;
+    state.events.publish(AppEvent::TodoUpdated { todo: todo.clone() });
+    Ok((etag::etag_headers(&etag::etag(todo.version)), Json(todo)))
 }
 
 async fn archive_todo(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    client: ClientId,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let before = repository::snapshot_in(&mut tx, ChangeEntity::Todo, &id).await?;
+    if !repository::archive_todo_in(&mut tx, &id).await? {
+        return Err(AppError::NotFound);
+    }
+    repository::record_operation_in(&mut tx, &client.0, "archive_todo", ChangeEntity::Todo, &id, before).await?;
+    tx.commit().await?;
+    state.events.publish(AppEvent::TodoArchived { id });
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_todo(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    client: ClientId,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let before = repository::snapshot_in(&mut tx, ChangeEntity::Todo, &id).await?;
+    if repository::delete_todo_draft_in(&mut tx, &id).await? {
+        repository::record_operation_in(&mut tx, &client.0, "delete_todo", ChangeEntity::Todo, &id, before).await?;
+        tx.commit().await?;
+        state.events.publish(AppEvent::TodoDeleted { id });
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    match before {
+        Some(_) => Err(AppError::Conflict(
+            "Todo has been synced to Notion; archive it instead".to_string(),
+        )),
+        None => Err(AppError::NotFound),
+    }
+}
+
+async fn list_subtasks(
+    State(state): State<AppState>,
+    Path(id): Path<String>
+) -> Result<Json<Vec<Subtask>>, AppError> {
+    repository::find_todo_by_id(&state.db, &id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let subtasks = repository::fetch_subtasks(&state.db, &id).await?;
+    Ok(Json(subtasks))
+}
+
+async fn create_subtask(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    client: ClientId,
+    ValidJson(req): ValidJson<NewSubtaskRequest>
+) -> Result<Json<Subtask>, AppError> {
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let before = repository::snapshot_in(&mut tx, ChangeEntity::Todo, &id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let subtask = repository::insert_subtask_in(&mut tx, &id, req).await?;
+    repository::record_operation_in(&mut tx, &client.0, "create_subtask", ChangeEntity::Todo, &id, Some(before)).await?;
+    tx.commit().await?;
+    publish_todo_updated(&state, &id).await?;
+    Ok(Json(subtask))
+}
+
+async fn update_subtask(
+    State(state): State<AppState>,
+    Path((id, subtask_id)): Path<(String, String)>,
+    client: ClientId,
+    ValidJson(req): ValidJson<UpdateSubtaskRequest>
+) -> Result<Json<Subtask>, AppError> {
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let before = repository::snapshot_in(&mut tx, ChangeEntity::Todo, &id).await?;
+    let subtask = repository::update_subtask_in(&mut tx, &id, &subtask_id, req)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    repository::record_operation_in(&mut tx, &client.0, "update_subtask", ChangeEntity::Todo, &id, before).await?;
+    tx.commit().await?;
+    publish_todo_updated(&state, &id).await?;
+    Ok(Json(subtask))
+}
+
+async fn delete_subtask(
+    State(state): State<AppState>,
+    Path((id, subtask_id)): Path<(String, String)>,
+    client: ClientId,
+) -> Result<StatusCode, AppError> {
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let before = repository::snapshot_in(&mut tx, ChangeEntity::Todo, &id).await?;
+    if !repository::delete_subtask_in(&mut tx, &id, &subtask_id).await? {
+        return Err(AppError::NotFound);
+    }
+    repository::record_operation_in(&mut tx, &client.0, "delete_subtask", ChangeEntity::Todo, &id, before).await?;
+    tx.commit().await?;
+    publish_todo_updated(&state, &id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// サブタスクの変更で親の todo の progress と sync_state が変わったことを流す
+async fn publish_todo_updated(state: &AppState, id: &str) -> Result<(), AppError> {
+    if let Some(todo) = repository::find_todo_by_id(&state.db, id).await? {
+        state.events.publish(AppEvent::TodoUpdated { todo });
+    }
+    Ok(())
+}
+
+fn recurrence(state: &AppState) -> RecurrenceService {
+    RecurrenceService::new(state.db.clone(), state.timezone, state.recurrence_horizon_days)
+        .with_events(state.events.clone())
+}
+
+async fn create_series(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    client: ClientId,
+    ValidJson(req): ValidJson<NewSeriesRequest>
+) -> Result<Json<TodoSeries>, AppError> {
+    let rule = req.rule.parse::<RecurrenceRule>().map_err(AppError::BadRequest)?;
+    let recurrence = recurrence(&state);
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let before = repository::snapshot_in(&mut tx, ChangeEntity::Todo, &id).await?;
+    let template = repository::find_todo_in(&mut tx, &id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    if template.series_id.is_some() {
+        return Err(AppError::Conflict("Todo already belongs to a series".to_string()));
+    }
+    let start_date = template.due_date.local_date(&state.timezone);
+    let series = repository::insert_series_in(&mut tx, &template, &rule.to_string(), start_date).await?;
+    let created = recurrence.materialize_in(&mut tx, &series, recurrence.today()).await?;
+    let mut entries = vec![(ChangeEntity::Todo, template.id.clone(), before)];
+    entries.extend(created.iter().map(|todo| (ChangeEntity::Todo, todo.id.clone(), None)));
+    repository::record_operation_group_in(&mut tx, &client.0, "create_series", entries).await?;
+    let series = repository::find_series_in(&mut tx, &series.id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    tx.commit().await?;
+
+    publish_todo_updated(&state, &template.id).await?;
+    for todo in created {
+        state.events.publish(AppEvent::TodoCreated { todo });
+    }
+    Ok(Json(series))
+}
+
+async fn list_series(State(state): State<AppState>) -> Result<Json<Vec<TodoSeries>>, AppError> {
+    let series = repository::fetch_series(&state.db).await?;
+    Ok(Json(series))
+}
+
+async fn get_series(
+    State(state): State<AppState>,
+    Path(id): Path<String>
+) -> Result<Json<TodoSeries>, AppError> {
+    let series = repository::find_series(&state.db, &id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    Ok(Json(series))
+}
+
+/// ルールを置き換え、明日以降の回を新しいルールで作り直す
+async fn update_series(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    client: ClientId,
+    ValidJson(req): ValidJson<UpdateSeriesRequest>
+) -> Result<Json<TodoSeries>, AppError> {
+    let rule = req.rule.parse::<RecurrenceRule>().map_err(AppError::BadRequest)?;
+    let recurrence = recurrence(&state);
+    let today = recurrence.today();
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let series = repository::find_series_in(&mut tx, &id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    if !series.is_active {
+        return Err(AppError::Conflict("Series has been stopped".to_string()));
+    }
+    let template_id = series.template_todo_id.clone();
+    let before = repository::snapshot_in(&mut tx, ChangeEntity::Todo, &template_id).await?;
+
+    let removed = repository::remove_future_series_instances_in(&mut tx, &id, today).await?;
+    let yesterday = today.pred_opt().unwrap_or(today);
+    let series = repository::update_series_rule_in(&mut tx, &id, &rule.to_string(), yesterday.max(series.start_date))
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let created = recurrence.materialize_in(&mut tx, &series, today).await?;
+    let entries = series_entries(&template_id, before, &removed, &created);
+    repository::record_operation_group_in(&mut tx, &client.0, "update_series", entries).await?;
+    let series = repository::find_series_in(&mut tx, &id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    tx.commit().await?;
+
+    publish_todo_updated(&state, &template_id).await?;
+    publish_removed_instances(&state, removed);
+    for todo in created {
+        state.events.publish(AppEvent::TodoCreated { todo });
+    }
+    Ok(Json(series))
+}
+
+/// 系列を止める。明日以降の回は削除 (同期済みはアーカイブ) し、今日までの回は残す
+async fn stop_series(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    client: ClientId,
+) -> Result<StatusCode, AppError> {
+    let today = recurrence(&state).today();
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let series = repository::find_series_in(&mut tx, &id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let template_id = series.template_todo_id;
+    let before = repository::snapshot_in(&mut tx, ChangeEntity::Todo, &template_id).await?;
+    if !repository::stop_series_in(&mut tx, &id).await? {
+        return Err(AppError::Conflict("Series has already been stopped".to_string()));
+    }
+    let removed = repository::remove_future_series_instances_in(&mut tx, &id, today).await?;
+    let entries = series_entries(&template_id, before, &removed, &[]);
+    repository::record_operation_group_in(&mut tx, &client.0, "stop_series", entries).await?;
+    tx.commit().await?;
+
+    publish_todo_updated(&state, &template_id).await?;
+    publish_removed_instances(&state, removed);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 系列の変更を 1 回の取り消しにまとめる: テンプレート、外した回、作った回の順
+fn series_entries(
+    template_id: &str,
+    before: Option<Snapshot>,
+    removed: &[repository::RemovedInstance],
+    created: &[Todo],
+) -> Vec<(ChangeEntity, String, Option<Snapshot>)> {
+    let mut entries = vec![(ChangeEntity::Todo, template_id.to_string(), before)];
+    entries.extend(removed.iter().map(|(id, before, _)| (ChangeEntity::Todo, id.clone(), before.clone())));
+    entries.extend(created.iter().map(|todo| (ChangeEntity::Todo, todo.id.clone(), None)));
+    entries
+}
+
+/// 削除した未同期の回と、アーカイブした同期済みの回
+fn publish_removed_instances(state: &AppState, removed: Vec<repository::RemovedInstance>) {
+    for (id, _, archived) in removed {
+        state.events.publish(if archived { AppEvent::TodoArchived { id } } else { AppEvent::TodoDeleted { id } });
+    }
+}
+
+fn reminders(state: &AppState) -> ReminderService {
+    ReminderService::new(state.db.clone(), state.statuses.clone(), state.timezone)
+}
+
+async fn get_course_reminders(
+    State(state): State<AppState>,
+    Path(id): Path<String>
+) -> Result<Json<ReminderSettings>, AppError> {
+    let settings = repository::fetch_course_reminder_settings(&state.db, &id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    Ok(Json(settings))
+}
+
+async fn update_course_reminders(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    client: ClientId,
+    ValidJson(req): ValidJson<UpdateReminderSettingsRequest>
+) -> Result<Json<ReminderSettings>, AppError> {
+    let before = repository::fetch_course_reminder_settings(&state.db, &id).await?;
+    let offsets = req.offsets.unwrap_or_default();
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let snapshot = repository::snapshot_in(&mut tx, ChangeEntity::Course, &id).await?;
+    if !repository::set_course_reminder_offsets_in(&mut tx, &id, &offsets).await? {
+        return Err(AppError::NotFound);
+    }
+    repository::record_operation_in(&mut tx, &client.0, "update_course_reminders", ChangeEntity::Course, &id, snapshot).await?;
+    tx.commit().await?;
+    reminders(&state).refresh(Utc::now()).await?;
+    let Json(settings) = get_course_reminders(State(state.clone()), Path(id.clone())).await?;
+    if before.as_ref() != Some(&settings) {
+        state.events.publish(AppEvent::RemindersChanged { entity: EventEntity::Course, id, settings: settings.clone() });
+    }
+    Ok(Json(settings))
+}
+
+async fn get_todo_reminders(
+    State(state): State<AppState>,
+    Path(id): Path<String>
+) -> Result<Json<ReminderSettings>, AppError> {
+    let settings = repository::fetch_todo_reminder_settings(&state.db, &id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    Ok(Json(settings))
+}
+
+async fn update_todo_reminders(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    client: ClientId,
+    ValidJson(req): ValidJson<UpdateReminderSettingsRequest>
+) -> Result<Json<ReminderSettings>, AppError> {
+    let before = repository::fetch_todo_reminder_settings(&state.db, &id).await?;
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let snapshot = repository::snapshot_in(&mut tx, ChangeEntity::Todo, &id).await?;
+    if !repository::set_todo_reminder_offsets_in(&mut tx, &id, req.offsets.as_deref()).await? {
+        return Err(AppError::NotFound);
+    }
+    repository::record_operation_in(&mut tx, &client.0, "update_todo_reminders", ChangeEntity::Todo, &id, snapshot).await?;
+    tx.commit().await?;
+    reminders(&state).refresh(Utc::now()).await?;
+    let Json(settings) = get_todo_reminders(State(state.clone()), Path(id.clone())).await?;
+    if before.as_ref() != Some(&settings) {
+        state.events.publish(AppEvent::RemindersChanged { entity: EventEntity::Todo, id, settings: settings.clone() });
+    }
+    Ok(Json(settings))
+}
+
+/// 時刻になった、どの端末もまだ確認していないリマインダー
+async fn due_reminders(State(state): State<AppState>) -> Result<Json<Vec<Reminder>>, AppError> {
+    let reminders = reminders(&state).due(Utc::now()).await?;
+    Ok(Json(reminders))
+}
+
+/// 接続時に未確認のリマインダーを送り、以降は時刻になったものと他の端末の確認を流す
+async fn reminder_stream(
+    State(state): State<AppState>
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, AppError> {
+    // 取得の前に購読して、その間に配信されたものを取りこぼさない (重複は id で除く)
+    let live = BroadcastStream::new(state.reminder_events.subscribe()).filter_map(Result::ok);
+    let pending = reminders(&state).due(Utc::now()).await?;
+    let stream = pending_then_live(pending, live)
+        .map(|event| Event::default().event(event.name()).json_data(&event));
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// リマインダーを表示する端末が先に呼ぶ。最初の 1 台だけが 204、以降は 409
+async fn acknowledge_reminder(
     State(state): State<AppState>,
     Path(id): Path<String>
 ) -> Result<StatusCode, AppError> {
-    let ok = repository::archive_todo(&state.db, &id).await?;
-    if ok {
-        Ok(StatusCode::NO_CONTENT)
+    if !repository::acknowledge_reminder(&state.db, &id).await? {
+        return match repository::find_reminder(&state.db, &id).await? {
+            Some(_) => Err(AppError::Conflict("Reminder has already been acknowledged".to_string())),
+            None => Err(AppError::NotFound),
+        };
+    }
+    let _ = state.reminder_events.send(ReminderEvent::Acknowledged { id });
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_statuses(State(state): State<AppState>) -> Json<Vec<StatusOption>> {
+    Json(state.statuses.list())
+}
+
+/// DB や Notion の設定に依存する検証 (course_id の存在、Status オプションの存在)
+///
+/// 入力の検証エラー `errors` とまとめて、1 件でもあれば 1 回の `422` で返す。
+async fn check_todo_references(
+    state: &AppState,
+    mut errors: Vec<FieldError>,
+    course_id: Option<&String>,
+    status: Option<&TodoStatus>,
+) -> Result<(), AppError> {
+    if let Some(course_id) = course_id.filter(|id| !id.trim().is_empty())
+        && repository::find_course_by_id(&state.db, course_id).await?.is_none()
+    {
+        errors.push(FieldError::new("course_id", format!("course '{}' does not exist", course_id)));
+    }
+    if let Some(status) = status
+        && !state.statuses.is_known(status)
+    {
+        errors.push(FieldError::new("status", format!("unknown status '{}'", status)));
+    }
+
+    if errors.is_empty() {
+        Ok(())
     } else {
-        Err(AppError::NotFound)
+        Err(AppError::Validation(errors))
     }
 }
 
+async fn agenda(
+    State(state): State<AppState>,
+    ValidQuery(query): ValidQuery<AgendaQuery>,
+) -> Result<Json<Agenda>, AppError> {
+    let tz = state.timezone;
+    let now = Utc::now();
+    let today = now.with_timezone(&tz).date_naive();
+    let (first, last) = query.days(today);
+    let window = (local_midnight(&tz, first), local_midnight(&tz, last + Duration::days(1)));
+
+    let done = state.statuses.done_statuses();
+    let course_id = query.course_id.as_deref();
+    let overdue = repository::fetch_overdue_todos(&state.db, &done, course_id, now, today).await?;
+    let todos = repository::fetch_agenda_todos(&state.db, &done, course_id, window, now, today, &tz).await?;
+
+    Ok(Json(Agenda::build(tz, today, (first, last), overdue, todos)))
+}
+
+async fn list_instructors(
+    State(state): State<AppState>,
+    Query(query): Query<InstructorQuery>,
+) -> Result<Json<Vec<Instructor>>, AppError> {
+    let instructors = repository::fetch_instructors(&state.db, &query).await?;
+    Ok(Json(instructors))
+}
+
+async fn list_tags(State(state): State<AppState>) -> Result<Json<Vec<Tag>>, AppError> {
+    let tags = repository::fetch_tags(&state.db).await?;
+    Ok(Json(tags))
+}
+
+async fn list_semesters(State(state): State<AppState>) -> Result<Json<Vec<Semester>>, AppError> {
+    let semesters = repository::fetch_semesters(&state.db).await?;
+    Ok(Json(semesters))
+}
+
+async fn update_semester(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    client: ClientId,
+    ValidJson(req): ValidJson<UpdateSemesterRequest>,
+) -> Result<Json<Semester>, AppError> {
+    let mut tx = state.db.begin_with("BEGIN IMMEDIATE").await?;
+    let current = repository::find_semester_in(&mut tx, &name)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    // 片方だけの変更も既存の値と合わせて確認する
+    let date = |value: &Option<String>, existing: Option<NaiveDate>| match value.as_deref() {
+        Some(value) => NaiveDate::parse_from_str(value, "%Y-%m-%d").ok(),
+        None => existing,
+    };
+    if let (Some(start), Some(end)) = (date(&req.start_date, current.start_date), date(&req.end_date, current.end_date))
+        && end < start
+    {
+        return Err(AppError::Validation(vec![FieldError::new("end_date", "must not be before start_date")]));
+    }
+
+    // current を付け替えると、それまでの current の学期も変わる (まとめて 1 回で戻す)
+    let mut names = vec![name.clone()];
+    if req.is_current == Some(true) {
+        names.extend(repository::fetch_current_semester_names_in(&mut tx).await?.into_iter().filter(|n| *n != name));
+    }
+    let mut entries = Vec::with_capacity(names.len());
+    for name in names {
+        let before = repository::snapshot_in(&mut tx, ChangeEntity::Semester, &name).await?;
+        entries.push((ChangeEntity::Semester, name, before));
+    }
+    let semester = repository::update_semester_in(&mut tx, &name, req)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let mut changed = Vec::new();
+    for (_, name, before) in &entries {
+        let after = repository::snapshot_in(&mut tx, ChangeEntity::Semester, name).await?;
+        if let Some(Snapshot::Semester { semester }) = after.as_ref()
+            && !repository::same_state(before.as_ref(), after.as_ref())
+        {
+            changed.push(semester.clone());
+        }
+    }
+    repository::record_operation_group_in(&mut tx, &client.0, "update_semester", entries).await?;
+    tx.commit().await?;
+    for semester in changed {
+        state.events.publish(AppEvent::SemesterChanged { semester });
+    }
+    Ok(Json(semester))
+}
+
+async fn timetable(
+    State(state): State<AppState>,
+    Query(query): Query<TimetableQuery>,
+) -> Result<Json<Timetable>, AppError> {
+    let semester = query.semester.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    let done = state.statuses.done_statuses();
+    let entries = repository::fetch_timetable_entries(&state.db, semester.as_deref(), &done).await?;
+    Ok(Json(Timetable::build(&state.periods, semester, entries)))
+}
+
+async fn search(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchHit>>, AppError> {
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Err(AppError::BadRequest("q must not be empty".to_string()));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(AppError::BadRequest(format!("limit must be between 1 and {}", MAX_PAGE_SIZE)));
+    }
+
+    let hits = repository::search(&state.db, q, limit).await?;
+    Ok(Json(hits))
+}
+
 async fn sync_now(State(state): State<AppState>) -> Result<Json<SyncStats>, AppError> {
-    let service = SyncService::new(state.db.clone(), state.notion.clone());
+    let service = SyncService::new(state.db.clone(), state.notion.clone()).with_events(state.events.clone());
     let stats = service.sync_all().await?;
     Ok(Json(stats))
 }
+
+/// 溜まった操作をまとめて適用する。失敗した操作があればすべて取り消して `applied: false` を返す
+async fn batch(
+    State(state): State<AppState>,
+    client: ClientId,
+    ValidJson(req): ValidJson<BatchRequest>
+) -> Result<Json<BatchResponse>, AppError> {
+    let service = BatchService::new(state.db.clone(), state.statuses.clone(), state.events.clone());
+    let response = service.apply(&client.0, req.operations).await?;
+    Ok(Json(response))
+}
+
+/// この端末 (`X-Client-Id`) の最後の操作を取り消す
+async fn undo(State(state): State<AppState>, client: ClientId) -> Result<Json<UndoResponse>, AppError> {
+    let response = UndoService::new(state.db.clone(), state.events.clone()).undo(&client.0).await?;
+    Ok(Json(response))
+}
+
+/// この端末で最後に取り消した操作をやり直す
+async fn redo(State(state): State<AppState>, client: ClientId) -> Result<Json<UndoResponse>, AppError> {
+    let response = UndoService::new(state.db.clone(), state.events.clone()).redo(&client.0).await?;
+    Ok(Json(response))
+}
+
+/// オフラインのクライアント向けの差分。`since` は前回の `cursor`
+async fn changes(
+    State(state): State<AppState>,
+    Query(query): Query<ChangesQuery>,
+) -> Result<Json<Changes>, AppError> {
+    if query.since < 0 {
+        return Err(AppError::BadRequest("since must not be negative".to_string()));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_CHANGES_LIMIT);
+    if !(1..=MAX_CHANGES_LIMIT).contains(&limit) {
+        return Err(AppError::BadRequest(format!("limit must be between 1 and {}", MAX_CHANGES_LIMIT)));
+    }
+    let changes = repository::fetch_changes(&state.db, query.since, limit).await?;
+    Ok(Json(changes))
+}
+
+async fn course_history(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ValidQuery(query): ValidQuery<HistoryQuery>,
+) -> Result<Json<Vec<HistoryEntry>>, AppError> {
+    history(&state, ChangeEntity::Course, &id, query).await.map(Json)
+}
+
+async fn todo_history(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    ValidQuery(query): ValidQuery<HistoryQuery>,
+) -> Result<Json<Vec<HistoryEntry>>, AppError> {
+    history(&state, ChangeEntity::Todo, &id, query).await.map(Json)
+}
+
+async fn semester_history(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    ValidQuery(query): ValidQuery<HistoryQuery>,
+) -> Result<Json<Vec<HistoryEntry>>, AppError> {
+    history(&state, ChangeEntity::Semester, &name, query).await.map(Json)
+}
+
+/// 変更履歴 (新しい順)。削除済みでも履歴があれば返す
+async fn history(
+    state: &AppState,
+    entity: ChangeEntity,
+    id: &str,
+    query: HistoryQuery,
+) -> Result<Vec<HistoryEntry>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    let entries = repository::fetch_history(&state.db, entity, id, limit).await?;
+    if entries.is_empty() && repository::snapshot(&state.db, entity, id).await?.is_none() {
+        return Err(AppError::NotFound);
+    }
+    Ok(entries)
+}
+
+/// データ変更のイベント。購読が追いつかずにイベントを落とした場合は `lagged` を送るので、クライアントは取得し直す
+async fn events(State(state): State<AppState>) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let stream = BroadcastStream::new(state.events.subscribe()).map(|event| match event {
+        Ok(event) => Event::default().event(event.name()).json_data(&event),
+        Err(BroadcastStreamRecvError::Lagged(missed)) => {
+            Event::default().event("lagged").json_data(serde_json::json!({ "type": "lagged", "missed": missed }))
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
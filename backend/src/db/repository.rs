@@ -1,331 +1,2693 @@
-use chrono::Utc;
-use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Duration, NaiveDate, SecondsFormat, Utc};
+use chrono_tz::Tz;
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
 use uuid::Uuid;
 
-use crate::models::{Course, NewCourseRequest, NewTodoRequest, Todo, UpdateTodoRequest};
+use crate::models::agenda::local_midnight;
+use crate::models::history::diff_snapshots;
+use crate::models::operation::MAX_UNDO_DEPTH;
+use crate::models::{
+    ChangeEntity, Changes, Course, HistoryContext, HistoryEntry, HistorySource, IdempotencyState, StoredResponse, Operation, Snapshot, CourseListQuery, CourseMeeting, DueDate, Instructor, InstructorQuery, Semester, UpdateSemesterRequest, CURRENT_SEMESTER, NewCourseRequest, NewSubtaskRequest, NewTodoRequest, PlannedReminder, RemovalReason, RemovedEntity, Priority, Reminder, ReminderSettings, SearchHit, TodoSeries, Weekday, SortOrder, StatusMapping, Subtask, SubtaskItem, Tag, TimetableEntry, Todo,
+    TodoListQuery, TodoStatus,
+    UpdateCourseRequest, UpdateSubtaskRequest, UpdateTodoRequest,
+};
+
+/// Course columns; `semesters`, `meetings` and `instructors` are aggregated
+/// from `course_semesters` / `course_meetings` / `course_instructors` as JSON arrays.
+const COURSE_COLUMNS: &str = "id, title, \
+    (SELECT json_group_array(semester ORDER BY position) FROM course_semesters WHERE course_id = courses.id) AS semesters, \
+    (SELECT json_group_array(json_object('day_of_week', m.day_of_week, 'period', m.period, \
+        'start_time', m.start_time, 'end_time', m.end_time, 'room', m.room) ORDER BY m.position) \
+        FROM course_meetings m WHERE m.course_id = courses.id) AS meetings, \
+    room, \
+    (SELECT json_group_array(name ORDER BY position) FROM course_instructors WHERE course_id = courses.id) AS instructors, \
+    version, is_archived, updated_at, sync_state, last_synced_at, notion_page_id";
+
+/// Todo columns; `tags` is aggregated from `todo_tags` as a JSON array and
+/// `progress` counts the todo's `subtasks` as a JSON object.
+const TODO_COLUMNS: &str = "id, course_id, title, due_date, due_end, due_timezone, status, priority, \
+    (SELECT json_group_array(name ORDER BY position) FROM todo_tags WHERE todo_id = todos.id) AS tags, \
+    notes, \
+    (SELECT json_object('done', coalesce(sum(done), 0), 'total', count(*)) FROM subtasks WHERE todo_id = todos.id) AS progress, \
+    series_id, version, completed_at, is_archived, updated_at, sync_state, last_synced_at, notion_page_id";
+
+/// Stored form of the `courses` / `todos` timestamps: RFC 3339 in UTC with a
+/// fixed three-digit fraction ("2026-10-18T12:00:00.000+00:00"), so that text
+/// comparison (`ORDER BY updated_at`, the keyset cursor) follows time order.
+pub fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, false)
+}
 
 pub async fn fetch_courses(db: &SqlitePool) -> Result<Vec<Course>, sqlx::Error> {
-    sqlx::query_as!(
-        Course,
-        r#"
-        SELECT
-            id as "id!",
-            title as "title!",
-            semester as "semester!",
-            day_of_week as "day_of_week!",
-            period as "period: i32",
-            room as "room?",
-            instructor as "instructor?",
-            is_archived as "is_archived: bool",
-            updated_at as "updated_at!",
-            sync_state as "sync_state!",
-            last_synced_at as "last_synced_at?"
-        FROM courses
-        WHERE is_archived = 0
-        ORDER BY updated_at DESC
-        "#
-    )
+    sqlx::query_as::<_, Course>(&format!(
+        "SELECT {} FROM courses WHERE is_archived = 0 ORDER BY updated_at DESC",
+        COURSE_COLUMNS
+    ))
     .fetch_all(db)
     .await
 }
 
+/// Non-archived courses for `GET /courses`, optionally limited to a semester
+/// and/or an instructor.
+pub async fn query_courses(db: &SqlitePool, query: &CourseListQuery) -> Result<Vec<Course>, sqlx::Error> {
+    let mut qb = QueryBuilder::<Sqlite>::new(format!("SELECT {} FROM courses WHERE is_archived = 0", COURSE_COLUMNS));
+    if let Some(semester) = &query.semester {
+        push_semester_filter(&mut qb, "id", semester);
+    }
+    if let Some(instructor) = &query.instructor {
+        qb.push(" AND id IN (SELECT course_id FROM course_instructors WHERE name = ")
+            .push_bind(instructor.clone())
+            .push(")");
+    }
+    qb.push(" ORDER BY updated_at DESC");
+    qb.build_query_as::<Course>().fetch_all(db).await
+}
+
+/// Courses with local changes waiting to be pushed, archived ones included.
+pub async fn fetch_pending_courses(db: &SqlitePool) -> Result<Vec<Course>, sqlx::Error> {
+    sqlx::query_as::<_, Course>(&format!("SELECT {} FROM courses WHERE sync_state != 'synced'", COURSE_COLUMNS))
+        .fetch_all(db)
+        .await
+}
+
 pub async fn insert_course(
     db: &SqlitePool,
     req: NewCourseRequest,
 ) -> Result<Course, sqlx::Error> {
-    let id = Uuid::new_v4().to_string();
-    let now = Utc::now().to_rfc3339();
+    let mut tx = db.begin().await?;
+    let course = insert_course_in(&mut tx, Uuid::new_v4().to_string(), req).await?;
+    tx.commit().await?;
+    Ok(course)
+}
+
+/// `insert_course` within the caller's transaction, with a given id
+/// (client-generated ids from `POST /batch`).
+pub async fn insert_course_in(
+    conn: &mut SqliteConnection,
+    id: String,
+    req: NewCourseRequest,
+) -> Result<Course, sqlx::Error> {
+    let now = Utc::now();
     let sync_state = "pending".to_string();
 
+    let stamp = timestamp(now);
     sqlx::query!(
         r#"
         INSERT INTO courses
-            (id, title, semester, day_of_week, period, room, instructor,
+            (id, title, room,
             is_archived, updated_at, sync_state, last_synced_at)
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, ?8, ?9, NULL)
+        VALUES (?1, ?2, ?3, 0, ?4, ?5, NULL)
         "#,
         id,
         req.title,
-        req.semester,
-        req.day_of_week,
-        req.period,
         req.room,
-        req.instructor,
-        now,
+        stamp,
         sync_state,
     )
-    .execute(db)
+    .execute(&mut *conn)
     .await?;
+    set_course_semesters(conn, &id, &req.semesters).await?;
+    set_course_meetings(conn, &id, &req.meetings).await?;
+    set_course_instructors(conn, &id, &req.instructors).await?;
 
-    Ok(Course {
-        id,
-        title: req.title,
-        semester: req.semester,
-        day_of_week: req.day_of_week,
-        period: req.period,
-        room: req.room,
-        instructor: req.instructor,
-        is_archived: false,
-        updated_at: now,
-        sync_state,
-        last_synced_at: None,
-    })
+    // re-read for the version the triggers assigned
+    find_course_in(conn, &id).await?.ok_or(sqlx::Error::RowNotFound)
 }
 
-pub async fn fetch_todos(db: &SqlitePool) -> Result<Vec<Todo>, sqlx::Error> {
+/// Replaces the semesters a course belongs to, creating unknown semesters.
+/// Nothing is written when they are unchanged, so the course version stays.
+async fn set_course_semesters(
+    conn: &mut SqliteConnection,
+    course_id: &str,
+    semesters: &[String],
+) -> Result<(), sqlx::Error> {
+    let current: Vec<String> =
+        sqlx::query_scalar("SELECT semester FROM course_semesters WHERE course_id = ? ORDER BY position, semester")
+            .bind(course_id)
+            .fetch_all(&mut *conn)
+            .await?;
+    if current == semesters {
+        return Ok(());
+    }
+    sqlx::query!("DELETE FROM course_semesters WHERE course_id = ?1", course_id)
+        .execute(&mut *conn)
+        .await?;
+    for (position, name) in semesters.iter().enumerate() {
+        let position = position as i64;
+        sqlx::query!("INSERT OR IGNORE INTO semesters (name) VALUES (?1)", name)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query!(
+            "INSERT OR IGNORE INTO course_semesters (course_id, semester, position) VALUES (?1, ?2, ?3)",
+            course_id,
+            name,
+            position,
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// day_of_week, period, start_time, end_time, room
+type MeetingRow = (String, Option<i32>, Option<String>, Option<String>, Option<String>);
+
+/// Replaces the meeting slots of a course, keeping their order (no write when unchanged).
+async fn set_course_meetings(
+    conn: &mut SqliteConnection,
+    course_id: &str,
+    meetings: &[CourseMeeting],
+) -> Result<(), sqlx::Error> {
+    let current: Vec<MeetingRow> = sqlx::query_as(
+        "SELECT day_of_week, period, start_time, end_time, room FROM course_meetings WHERE course_id = ? ORDER BY position",
+    )
+    .bind(course_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let slot = |m: &CourseMeeting| {
+        (
+            m.day_of_week.as_str().to_string(),
+            m.period,
+            m.start_time.map(|t| t.format("%H:%M").to_string()),
+            m.end_time.map(|t| t.format("%H:%M").to_string()),
+            m.room.clone(),
+        )
+    };
+    if current.iter().cloned().eq(meetings.iter().map(slot)) {
+        return Ok(());
+    }
+    sqlx::query!("DELETE FROM course_meetings WHERE course_id = ?1", course_id)
+        .execute(&mut *conn)
+        .await?;
+    for (position, meeting) in meetings.iter().enumerate() {
+        let position = position as i64;
+        let day_of_week = meeting.day_of_week.as_str();
+        let start_time = meeting.start_time.map(|t| t.format("%H:%M").to_string());
+        let end_time = meeting.end_time.map(|t| t.format("%H:%M").to_string());
+        sqlx::query!(
+            r#"
+            INSERT INTO course_meetings (course_id, position, day_of_week, period, start_time, end_time, room)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            "#,
+            course_id,
+            position,
+            day_of_week,
+            meeting.period,
+            start_time,
+            end_time,
+            meeting.room,
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Replaces the instructors of a course, keeping their order (no write when unchanged).
+async fn set_course_instructors(
+    conn: &mut SqliteConnection,
+    course_id: &str,
+    instructors: &[String],
+) -> Result<(), sqlx::Error> {
+    let current: Vec<String> =
+        sqlx::query_scalar("SELECT name FROM course_instructors WHERE course_id = ? ORDER BY position, name")
+            .bind(course_id)
+            .fetch_all(&mut *conn)
+            .await?;
+    if current == instructors {
+        return Ok(());
+    }
+    sqlx::query!("DELETE FROM course_instructors WHERE course_id = ?1", course_id)
+        .execute(&mut *conn)
+        .await?;
+    for (position, name) in instructors.iter().enumerate() {
+        let position = position as i64;
+        sqlx::query!(
+            "INSERT OR IGNORE INTO course_instructors (course_id, name, position) VALUES (?1, ?2, ?3)",
+            course_id,
+            name,
+            position,
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Replaces the tags of a todo, keeping their order (no write when unchanged).
+async fn set_todo_tags(conn: &mut SqliteConnection, todo_id: &str, tags: &[String]) -> Result<(), sqlx::Error> {
+    let current: Vec<String> = sqlx::query_scalar("SELECT name FROM todo_tags WHERE todo_id = ? ORDER BY position, name")
+        .bind(todo_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    if current == tags {
+        return Ok(());
+    }
+    sqlx::query!("DELETE FROM todo_tags WHERE todo_id = ?1", todo_id)
+        .execute(&mut *conn)
+        .await?;
+    for (position, name) in tags.iter().enumerate() {
+        let position = position as i64;
+        sqlx::query!(
+            "INSERT OR IGNORE INTO todo_tags (todo_id, name, position) VALUES (?1, ?2, ?3)",
+            todo_id,
+            name,
+            position,
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Tags in use with their number of non-archived todos, by name.
+pub async fn fetch_tags(db: &SqlitePool) -> Result<Vec<Tag>, sqlx::Error> {
+    sqlx::query_as::<_, Tag>(
+        "SELECT tg.name AS name, count(t.id) AS todo_count \
+         FROM todo_tags tg JOIN todos t ON t.id = tg.todo_id \
+         WHERE t.is_archived = 0 \
+         GROUP BY tg.name ORDER BY tg.name",
+    )
+    .fetch_all(db)
+    .await
+}
+
+/// Instructors of non-archived courses with the courses they teach, by name.
+pub async fn fetch_instructors(db: &SqlitePool, query: &InstructorQuery) -> Result<Vec<Instructor>, sqlx::Error> {
+    let mut qb = QueryBuilder::<Sqlite>::new(
+        "SELECT i.name AS name, \
+            json_group_array(json_object('id', c.id, 'title', c.title) ORDER BY c.title, c.id) AS courses \
+         FROM course_instructors i JOIN courses c ON c.id = i.course_id \
+         WHERE c.is_archived = 0",
+    );
+    if let Some(semester) = &query.semester {
+        push_semester_filter(&mut qb, "c.id", semester);
+    }
+    qb.push(" GROUP BY i.name ORDER BY i.name");
+    qb.build_query_as::<Instructor>().fetch_all(db).await
+}
+
+/// Restricts `course_id_column` to courses linked to `semester`; `current`
+/// means the semester(s) flagged `is_current`.
+fn push_semester_filter(qb: &mut QueryBuilder<'_, Sqlite>, course_id_column: &str, semester: &str) {
+    qb.push(format!(" AND {course_id_column} IN (SELECT course_id FROM course_semesters WHERE semester "));
+    if semester == CURRENT_SEMESTER {
+        qb.push("IN (SELECT name FROM semesters WHERE is_current = 1))");
+    } else {
+        qb.push("= ").push_bind(semester.to_string()).push(")");
+    }
+}
+
+/// All semesters with their number of non-archived courses, newest first
+/// (undated semesters last, then by name).
+pub async fn fetch_semesters(db: &SqlitePool) -> Result<Vec<Semester>, sqlx::Error> {
     sqlx::query_as!(
-        Todo,
+        Semester,
         r#"
         SELECT
-            id as "id!",
-            course_id as "course_id!",
-            title as "title!",
-            due_date as "due_date!",
-            status as "status!",
-            completed_at as "completed_at?",
-            is_archived as "is_archived: bool",
-            updated_at as "updated_at!",
-            sync_state as "sync_state!",
-            last_synced_at as "last_synced_at?"
-        FROM todos
-        WHERE is_archived = 0
-        ORDER BY updated_at DESC
+            s.name as "name!",
+            s.start_date as "start_date?: NaiveDate",
+            s.end_date as "end_date?: NaiveDate",
+            s.is_current as "is_current: bool",
+            (SELECT COUNT(*) FROM course_semesters cs JOIN courses c ON c.id = cs.course_id
+             WHERE cs.semester = s.name AND c.is_archived = 0) as "course_count!: i64"
+        FROM semesters s
+        ORDER BY s.start_date IS NULL, s.start_date DESC, s.name ASC
         "#
     )
     .fetch_all(db)
     .await
 }
 
-pub async fn insert_todo(
-    db: &SqlitePool,
-    req: NewTodoRequest,
-) -> Result<Todo, sqlx::Error> {
-    let id = Uuid::new_v4().to_string();
-    let now = Utc::now().to_rfc3339();
-    let sync_state = "pending".to_string();
+pub async fn find_semester(db: &SqlitePool, name: &str) -> Result<Option<Semester>, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    find_semester_in(&mut conn, name).await
+}
 
-    sqlx::query!(
+pub async fn find_semester_in(conn: &mut SqliteConnection, name: &str) -> Result<Option<Semester>, sqlx::Error> {
+    sqlx::query_as!(
+        Semester,
         r#"
-        INSERT INTO todos
-            (id, course_id, title, due_date, status,
-            is_archived, updated_at, sync_state, last_synced_at)
-        VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6, ?7, NULL)
+        SELECT
+            s.name as "name!",
+            s.start_date as "start_date?: NaiveDate",
+            s.end_date as "end_date?: NaiveDate",
+            s.is_current as "is_current: bool",
+            (SELECT COUNT(*) FROM course_semesters cs JOIN courses c ON c.id = cs.course_id
+             WHERE cs.semester = s.name AND c.is_archived = 0) as "course_count!: i64"
+        FROM semesters s
+        WHERE s.name = ?1
         "#,
-        id,
-        req.course_id,
-        req.title,
-        req.due_date,
-        req.status,
-        now,
-        sync_state,
+        name
     )
-    .execute(db)
-    .await?;
+    .fetch_optional(&mut *conn)
+    .await
+}
 
-    Ok(Todo {
-        id,
-        course_id: req.course_id,
-        title: req.title,
-        due_date: req.due_date,
-        status: req.status,
-        completed_at: None,
-        is_archived: false,
-        updated_at: now,
-        sync_state,
-        last_synced_at: None,
-    })
+/// Names of the semesters currently marked current.
+pub async fn fetch_current_semester_names_in(conn: &mut SqliteConnection) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar!(r#"SELECT name AS "name!" FROM semesters WHERE is_current = 1 ORDER BY name"#)
+        .fetch_all(&mut *conn)
+        .await
 }
 
-pub async fn update_todo(
+/// Sets a semester's dates and current flag. Marking one semester current
+/// clears the flag on every other semester.
+pub async fn update_semester(
+    db: &SqlitePool,
+    name: &str,
+    req: UpdateSemesterRequest,
+) -> Result<Option<Semester>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let semester = update_semester_in(&mut tx, name, req).await?;
+    tx.commit().await?;
+    Ok(semester)
+}
+
+pub async fn update_semester_in(
+    conn: &mut SqliteConnection,
+    name: &str,
+    req: UpdateSemesterRequest,
+) -> Result<Option<Semester>, sqlx::Error> {
+    let exists = sqlx::query_scalar!("SELECT COUNT(*) FROM semesters WHERE name = ?1", name)
+        .fetch_one(&mut *conn)
+        .await?
+        > 0;
+    if !exists {
+        return Ok(None);
+    }
+
+    // An empty string clears the date
+    let date = |value: &str| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok();
+    if let Some(start_date) = &req.start_date {
+        let start_date = date(start_date);
+        sqlx::query!("UPDATE semesters SET start_date = ?1 WHERE name = ?2", start_date, name)
+            .execute(&mut *conn)
+            .await?;
+    }
+    if let Some(end_date) = &req.end_date {
+        let end_date = date(end_date);
+        sqlx::query!("UPDATE semesters SET end_date = ?1 WHERE name = ?2", end_date, name)
+            .execute(&mut *conn)
+            .await?;
+    }
+    match req.is_current {
+        Some(true) => {
+            sqlx::query!("UPDATE semesters SET is_current = (name = ?1)", name)
+                .execute(&mut *conn)
+                .await?;
+        }
+        Some(false) => {
+            sqlx::query!("UPDATE semesters SET is_current = 0 WHERE name = ?1", name)
+                .execute(&mut *conn)
+                .await?;
+        }
+        None => {}
+    }
+
+    find_semester_in(conn, name).await
+}
+
+pub async fn update_course(
     db: &SqlitePool,
     id: &str,
-    req: UpdateTodoRequest,
-) -> Result<Option<Todo>, sqlx::Error> {
-    let mut current = match sqlx::query_as!(
-        Todo,
-        r#"
-        SELECT
-            id as "id!",
-            course_id as "course_id!",
-            title as "title!",
-            due_date as "due_date!",
-            status as "status!",
-            completed_at as "completed_at?",
-            is_archived as "is_archived: bool",
-            updated_at as "updated_at!",
-            sync_state as "sync_state!",
-            last_synced_at as "last_synced_at?"
-        FROM todos
-        WHERE id = ?1
-        "#,
-        id
-    )
-    .fetch_optional(db)
-    .await? {
-        Some(t) => t,
+    req: UpdateCourseRequest,
+) -> Result<Option<Course>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let course = update_course_in(&mut tx, id, req).await?;
+    tx.commit().await?;
+    Ok(course)
+}
+
+pub async fn update_course_in(
+    conn: &mut SqliteConnection,
+    id: &str,
+    req: UpdateCourseRequest,
+) -> Result<Option<Course>, sqlx::Error> {
+    let mut current = match find_course_in(conn, id).await? {
+        Some(c) => c,
         None => return Ok(None),
     };
 
     if let Some(title) = req.title {
         current.title = title;
     }
-    if let Some(due_date) = req.due_date {
-        current.due_date = due_date;
+    if let Some(semesters) = req.semesters {
+        current.semesters = semesters;
     }
-    if let Some(status) = req.status {
-        current.status = status;
+    if let Some(meetings) = req.meetings {
+        current.meetings = meetings;
+    }
+    if let Some(room) = req.room {
+        current.room = Some(room);
+    }
+    if let Some(instructors) = req.instructors {
+        current.instructors = instructors;
     }
-    let now = Utc::now().to_rfc3339();
-    current.updated_at = now.clone();
+    let now = Utc::now();
+    current.updated_at = now;
     current.sync_state = "pending".to_string();
 
+    let stamp = timestamp(now);
     sqlx::query!(
         r#"
-        UPDATE todos
+        UPDATE courses
         SET title = ?1,
-            due_date = ?2,
-            status = ?3,
-            updated_at = ?4,
-            sync_state = ?5
-        WHERE id = ?6
+            room = ?2,
+            updated_at = ?3,
+            sync_state = ?4
+        WHERE id = ?5
         "#,
         current.title,
-        current.due_date,
-        current.status,
-        now,
+        current.room,
+        stamp,
         current.sync_state,
         id
     )
-    .execute(db)
+    .execute(&mut *conn)
     .await?;
+    set_course_semesters(conn, id, &current.semesters).await?;
+    set_course_meetings(conn, id, &current.meetings).await?;
+    set_course_instructors(conn, id, &current.instructors).await?;
 
-    Ok(Some(current))
+    find_course_in(conn, id).await
 }
 
-pub async fn archive_todo(db: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
-    let now = Utc::now().to_rfc3339();
+pub async fn archive_course(db: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    archive_course_in(&mut conn, id).await
+}
+
+pub async fn archive_course_in(conn: &mut SqliteConnection, id: &str) -> Result<bool, sqlx::Error> {
+    let now = Utc::now();
+    let stamp = timestamp(now);
     let result = sqlx::query!(
         r#"
-        UPDATE todos
+        UPDATE courses
         SET is_archived = 1,
             updated_at = ?2,
             sync_state = 'pending'
         WHERE id = ?1
         "#,
         id,
-        now,
+        stamp,
     )
-    .execute(db)
+    .execute(&mut *conn)
     .await?
     .rows_affected();
 
     Ok(result > 0)
 }
 
-pub async fn find_course_by_id(db: &SqlitePool, id: &str) -> Result<Option<Course>, sqlx::Error> {
-    sqlx::query_as::<_, Course>(
-        "SELECT id, title, semester, day_of_week, period, room, instructor, is_archived, updated_at, sync_state, last_synced_at FROM courses WHERE id = ?"
+/// Hard-deletes a course that has never been synced to Notion.
+///
+/// Returns `false` when the course has been synced, or when deleting it would
+/// cascade to todos that have been synced.
+pub async fn delete_course_draft(db: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    delete_course_draft_in(&mut conn, id).await
+}
+
+pub async fn delete_course_draft_in(conn: &mut SqliteConnection, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        r#"
+        DELETE FROM courses
+        WHERE id = ?1
+            AND last_synced_at IS NULL
+            AND NOT EXISTS (
+                SELECT 1 FROM todos WHERE course_id = ?1 AND last_synced_at IS NOT NULL
+            )
+        "#,
+        id,
     )
-    .bind(id)
-    .fetch_optional(db)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    Ok(result > 0)
+}
+
+pub async fn fetch_todos(db: &SqlitePool) -> Result<Vec<Todo>, sqlx::Error> {
+    sqlx::query_as::<_, Todo>(&format!(
+        "SELECT {} FROM todos WHERE is_archived = 0 ORDER BY updated_at DESC",
+        TODO_COLUMNS
+    ))
+    .fetch_all(db)
     .await
 }
 
-pub async fn upsert_course(db: &SqlitePool, course: &Course) -> Result<Course, sqlx::Error> {
-    match find_course_by_id(db, &course.id).await? {
-        Some(_) => {
-            // Update
-            sqlx::query(
-                "UPDATE courses SET title = ?, semester = ?, day_of_week = ?, period = ?, room = ?, instructor = ?, is_archived = ?, updated_at = ?, sync_state = ?, last_synced_at = ? WHERE id = ?"
-            )
-            .bind(&course.title)
-            .bind(&course.semester)
-            .bind(&course.day_of_week)
-            .bind(course.period)
-            .bind(&course.room)
-            .bind(&course.instructor)
-            .bind(course.is_archived)
-            .bind(&course.updated_at)
-            .bind(&course.sync_state)
-            .bind(&course.last_synced_at)
-            .bind(&course.id)
-            .execute(db)
-            .await?;
+/// Filtered, sorted and keyset-paginated todo listing for `GET /todos`.
+///
+/// The cursor is the id of the last row of the previous page; rows are
+/// ordered by `(sort column, id)` so the position is stable across pages.
+/// When `limit` is set, up to `limit` rows are returned. All-day due dates
+/// are compared with a timed `due_from` on its local date in `tz`.
+pub async fn query_todos(
+    db: &SqlitePool,
+    query: &TodoListQuery,
+    limit: Option<u32>,
+    tz: &Tz,
+) -> Result<Vec<Todo>, sqlx::Error> {
+    let mut qb = QueryBuilder::<Sqlite>::new(format!("SELECT {} FROM todos WHERE is_archived = ", TODO_COLUMNS));
+    qb.push_bind(query.archived.unwrap_or(false));
+
+    if let Some(course_id) = &query.course_id {
+        qb.push(" AND course_id = ").push_bind(course_id.clone());
+    }
+    if let Some(semester) = &query.semester {
+        push_semester_filter(&mut qb, "course_id", semester);
+    }
+
+    let statuses = query.statuses();
+    if !statuses.is_empty() {
+        qb.push(" AND status IN (");
+        let mut separated = qb.separated(", ");
+        for status in statuses {
+            separated.push_bind(status);
         }
-        None => {
-            // Insert
-            sqlx::query(
-                "INSERT INTO courses (id, title, semester, day_of_week, period, room, instructor, is_archived, updated_at, sync_state, last_synced_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
-            )
-            .bind(&course.id)
-            .bind(&course.title)
-            .bind(&course.semester)
-            .bind(&course.day_of_week)
-            .bind(course.period)
-            .bind(&course.room)
-            .bind(&course.instructor)
-            .bind(course.is_archived)
-            .bind(&course.updated_at)
-            .bind(&course.sync_state)
-            .bind(&course.last_synced_at)
-            .execute(db)
-            .await?;
+        separated.push_unseparated(")");
+    }
+
+    push_due_range(
+        &mut qb,
+        query.due_from.as_deref().and_then(|d| d.parse::<DueDate>().ok()),
+        query.due_to.as_deref().and_then(|d| d.parse::<DueDate>().ok()),
+        tz,
+    );
+
+    if let Some(sync_state) = &query.sync_state {
+        qb.push(" AND sync_state = ").push_bind(sync_state.clone());
+    }
+    if let Some(series_id) = &query.series_id {
+        qb.push(" AND series_id = ").push_bind(series_id.clone());
+    }
+
+    let tags = query.tags();
+    if !tags.is_empty() {
+        qb.push(" AND id IN (SELECT todo_id FROM todo_tags WHERE name IN (");
+        let mut separated = qb.separated(", ");
+        for tag in tags {
+            separated.push_bind(tag);
         }
+        separated.push_unseparated("))");
     }
 
-    find_course_by_id(db, &course.id)
-        .await?
-        .ok_or_else(|| sqlx::Error::RowNotFound)
+    if let Some(q) = query.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        let pattern = format!("%{}%", escape_like(q));
+        qb.push(" AND (title LIKE ")
+            .push_bind(pattern.clone())
+            .push(" ESCAPE '\\' OR notes LIKE ")
+            .push_bind(pattern)
+            .push(" ESCAPE '\\')");
+    }
+
+    let column = query.sort.column();
+    let (direction, comparison) = match query.order {
+        SortOrder::Asc => ("ASC", ">"),
+        SortOrder::Desc => ("DESC", "<"),
+    };
+
+    if let Some(cursor) = &query.cursor {
+        qb.push(format!(" AND ({column}, id) {comparison} (SELECT {column}, id FROM todos WHERE id = "))
+            .push_bind(cursor.clone())
+            .push(")");
+    }
+
+    qb.push(format!(" ORDER BY {column} {direction}, id {direction}"));
+
+    if let Some(limit) = limit {
+        qb.push(" LIMIT ").push_bind(limit as i64);
+    }
+
+    qb.build_query_as::<Todo>().fetch_all(db).await
 }
 
-pub async fn find_todo_by_id(db: &SqlitePool, id: &str) -> Result<Option<Todo>, sqlx::Error> {
-    sqlx::query_as::<_, Todo>(
-        "SELECT id, course_id, title, due_date, status, completed_at, is_archived, updated_at, sync_state, last_synced_at FROM todos WHERE id = ?"
-    )
-    .bind(id)
-    .fetch_optional(db)
-    .await
+/// Restricts to todos whose due range (`due_date` to `due_end`, or just
+/// `due_date`) overlaps `[from, to]`.
+///
+/// Bounds are compared in the stored text form. An all-day `to` covers the
+/// whole day, and an all-day end stays active for the whole of its day (in
+/// `tz`) even against a timed `from`.
+fn push_due_range(qb: &mut QueryBuilder<'_, Sqlite>, from: Option<DueDate>, to: Option<DueDate>, tz: &Tz) {
+    // all-day rows compare dates, timed rows instants; an all-day bound is a day in `tz`
+    if let Some(from) = from {
+        let start = match from {
+            DueDate::AllDay(date) => DueDate::Timed(local_midnight(tz, date)),
+            timed => timed,
+        };
+        qb.push(" AND CASE WHEN length(coalesce(due_end, due_date)) = 10 THEN coalesce(due_end, due_date) >= ")
+            .push_bind(DueDate::AllDay(from.local_date(tz)))
+            .push(" ELSE coalesce(due_end, due_date) >= ")
+            .push_bind(start)
+            .push(" END");
+    }
+    if let Some(to) = to {
+        qb.push(" AND CASE WHEN length(due_date) = 10 THEN due_date <= ")
+            .push_bind(DueDate::AllDay(to.local_date(tz)));
+        match to {
+            DueDate::AllDay(date) => {
+                qb.push(" ELSE due_date < ").push_bind(DueDate::Timed(local_midnight(tz, date + Duration::days(1))));
+            }
+            timed => {
+                qb.push(" ELSE due_date <= ").push_bind(timed);
+            }
+        }
+        qb.push(" END");
+    }
 }
 
-pub async fn upsert_todo(db: &SqlitePool, todo: &Todo) -> Result<Todo, sqlx::Error> {
-    match find_todo_by_id(db, &todo.id).await? {
-        Some(_) => {
-            sqlx::query(
-                "UPDATE todos SET course_id = ?, title = ?, due_date = ?, status = ?, completed_at = ?, is_archived = ?, updated_at = ?, sync_state = ?, last_synced_at = ? WHERE id = ?"
-            )
-            .bind(&todo.course_id)
-            .bind(&todo.title)
-            .bind(&todo.due_date)
-            .bind(&todo.status)
-            .bind(&todo.completed_at)
-            .bind(todo.is_archived)
-            .bind(&todo.updated_at)
-            .bind(&todo.sync_state)
-            .bind(&todo.last_synced_at)
-            .bind(&todo.id)
-            .execute(db)
-            .await?;
+/// Non-archived courses for `GET /timetable`, each with the number of its
+/// open todos (not archived and not in the done group).
+pub async fn fetch_timetable_entries(
+    db: &SqlitePool,
+    semester: Option<&str>,
+    done_statuses: &[TodoStatus],
+) -> Result<Vec<TimetableEntry>, sqlx::Error> {
+    let mut qb = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT {}, (SELECT COUNT(*) FROM todos t WHERE t.course_id = courses.id AND t.is_archived = 0",
+        COURSE_COLUMNS
+    ));
+    if !done_statuses.is_empty() {
+        qb.push(" AND t.status NOT IN (");
+        let mut separated = qb.separated(", ");
+        for status in done_statuses {
+            separated.push_bind(status.clone());
         }
-        None => {
-            sqlx::query(
-                "INSERT INTO todos (id, course_id, title, due_date, status, completed_at, is_archived, updated_at, sync_state, last_synced_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
-            )
-            .bind(&todo.id)
-            .bind(&todo.course_id)
-            .bind(&todo.title)
-            .bind(&todo.due_date)
-            .bind(&todo.status)
-            .bind(&todo.completed_at)
-            .bind(todo.is_archived)
-            .bind(&todo.updated_at)
-            .bind(&todo.sync_state)
-            .bind(&todo.last_synced_at)
-            .execute(db)
-            .await?;
+        separated.push_unseparated(")");
+    }
+    qb.push(") AS open_todos FROM courses WHERE is_archived = 0");
+    if let Some(semester) = semester {
+        push_semester_filter(&mut qb, "id", semester);
+    }
+    qb.push(" ORDER BY title ASC, id ASC");
+    qb.build_query_as::<TimetableEntry>().fetch_all(db).await
+}
+
+/// Base query for the open todos shown on `GET /agenda`: not archived and
+/// not in the done group.
+fn open_todos_query<'a>(done_statuses: &[TodoStatus], course_id: Option<&'a str>) -> QueryBuilder<'a, Sqlite> {
+    let mut qb = QueryBuilder::<Sqlite>::new(format!("SELECT {} FROM todos WHERE is_archived = 0", TODO_COLUMNS));
+    if !done_statuses.is_empty() {
+        qb.push(" AND status NOT IN (");
+        let mut separated = qb.separated(", ");
+        for status in done_statuses {
+            separated.push_bind(status.clone());
         }
+        separated.push_unseparated(")");
+    }
+    if let Some(course_id) = course_id {
+        qb.push(" AND course_id = ").push_bind(course_id);
     }
+    qb
+}
 
-    find_todo_by_id(db, &todo.id)
-        .await?
-        .ok_or_else(|| sqlx::Error::RowNotFound)
+/// A todo is overdue once its range end has passed: all-day ends at the end
+/// of that (local) day, timed ends at the instant.
+fn push_overdue(qb: &mut QueryBuilder<'_, Sqlite>, now: DateTime<Utc>, today: NaiveDate) {
+    qb.push(" CASE WHEN length(coalesce(due_end, due_date)) = 10 THEN coalesce(due_end, due_date) < ")
+        .push_bind(DueDate::AllDay(today))
+        .push(" ELSE coalesce(due_end, due_date) < ")
+        .push_bind(DueDate::Timed(now))
+        .push(" END");
+}
+
+/// Open todos past their due date, oldest first.
+pub async fn fetch_overdue_todos(
+    db: &SqlitePool,
+    done_statuses: &[TodoStatus],
+    course_id: Option<&str>,
+    now: DateTime<Utc>,
+    today: NaiveDate,
+) -> Result<Vec<Todo>, sqlx::Error> {
+    let mut qb = open_todos_query(done_statuses, course_id);
+    qb.push(" AND");
+    push_overdue(&mut qb, now, today);
+    qb.push(" ORDER BY due_date ASC, id ASC");
+    qb.build_query_as::<Todo>().fetch_all(db).await
+}
+
+/// Open, not yet overdue todos whose due range overlaps `[from, to]`,
+/// ordered by due date. Grouping into local days is left to the caller.
+pub async fn fetch_agenda_todos(
+    db: &SqlitePool,
+    done_statuses: &[TodoStatus],
+    course_id: Option<&str>,
+    (from, to): (DateTime<Utc>, DateTime<Utc>),
+    now: DateTime<Utc>,
+    today: NaiveDate,
+    tz: &Tz,
+) -> Result<Vec<Todo>, sqlx::Error> {
+    let mut qb = open_todos_query(done_statuses, course_id);
+    push_due_range(&mut qb, Some(DueDate::Timed(from)), Some(DueDate::Timed(to)), tz);
+    qb.push(" AND NOT");
+    push_overdue(&mut qb, now, today);
+    qb.push(" ORDER BY due_date ASC, id ASC");
+    qb.build_query_as::<Todo>().fetch_all(db).await
+}
+
+/// Request due dates are validated by the API layer; anything that still fails
+/// to parse is reported as a decode error rather than stored as-is.
+fn parse_due_date(value: &str) -> Result<DueDate, sqlx::Error> {
+    value.parse::<DueDate>().map_err(|e| sqlx::Error::Decode(e.into()))
+}
+
+fn parse_priority(value: &str) -> Result<Priority, sqlx::Error> {
+    value.parse::<Priority>().map_err(|e| sqlx::Error::Decode(e.into()))
+}
+
+fn escape_like(input: &str) -> String {
+    input
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Minimum term length the trigram tokenizer can match through `MATCH`.
+const FTS_MIN_TERM_CHARS: usize = 3;
+
+/// Ranked full-text search across todos and courses.
+///
+/// Whitespace-separated terms are ANDed. Terms shorter than the trigram size
+/// cannot go through the FTS index, so such queries fall back to a substring
+/// scan of the index tables and are returned unranked.
+pub async fn search(db: &SqlitePool, q: &str, limit: u32) -> Result<Vec<SearchHit>, sqlx::Error> {
+    let terms: Vec<&str> = q.split_whitespace().collect();
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    if terms.iter().all(|t| t.chars().count() >= FTS_MIN_TERM_CHARS) {
+        let fts_query = terms
+            .iter()
+            .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        return sqlx::query_as::<_, SearchHit>(
+            r#"
+            SELECT 'todo' AS kind, t.id AS id, t.title AS title,
+                snippet(todos_fts, -1, '<mark>', '</mark>', '…', 16) AS snippet,
+                bm25(todos_fts, 10.0, 1.0) AS rank
+            FROM todos_fts
+            JOIN search_ids s ON s.id = todos_fts.rowid
+            JOIN todos t ON t.id = s.entity_id
+            WHERE todos_fts MATCH ?1
+            UNION ALL
+            SELECT 'course', c.id, c.title,
+                snippet(courses_fts, -1, '<mark>', '</mark>', '…', 16),
+                bm25(courses_fts, 10.0, 1.0)
+            FROM courses_fts
+            JOIN search_ids s ON s.id = courses_fts.rowid
+            JOIN courses c ON c.id = s.entity_id
+            WHERE courses_fts MATCH ?1
+            ORDER BY rank
+            LIMIT ?2
+            "#,
+        )
+        .bind(fts_query)
+        .bind(limit as i64)
+        .fetch_all(db)
+        .await;
+    }
+
+    let mut qb = QueryBuilder::<Sqlite>::new("");
+    for (i, (kind, fts, table)) in [("todo", "todos_fts", "todos"), ("course", "courses_fts", "courses")]
+        .into_iter()
+        .enumerate()
+    {
+        if i > 0 {
+            qb.push(" UNION ALL ");
+        }
+        qb.push(format!(
+            "SELECT '{kind}' AS kind, e.id AS id, e.title AS title, {fts}.body AS snippet, 0.0 AS rank \
+             FROM {fts} JOIN search_ids s ON s.id = {fts}.rowid \
+             JOIN {table} e ON e.id = s.entity_id WHERE 1 = 1"
+        ));
+        for term in &terms {
+            let pattern = format!("%{}%", escape_like(term));
+            qb.push(format!(" AND ({fts}.title LIKE "))
+                .push_bind(pattern.clone())
+                .push(format!(" ESCAPE '\\' OR {fts}.body LIKE "))
+                .push_bind(pattern)
+                .push(" ESCAPE '\\')");
+        }
+    }
+    qb.push(" ORDER BY kind DESC, title LIMIT ").push_bind(limit as i64);
+
+    let mut hits = qb.build_query_as::<SearchHit>().fetch_all(db).await?;
+    for hit in &mut hits {
+        let source = if terms.iter().any(|t| contains_ignore_ascii_case(&hit.title, t)) {
+            &hit.title
+        } else {
+            &hit.snippet
+        };
+        hit.snippet = highlight(&snippet_window(source, &terms), &terms);
+    }
+    Ok(hits)
+}
+
+/// Cuts long text (todo notes) down to the part around the first match,
+/// like the `snippet()` of the FTS path.
+fn snippet_window(text: &str, terms: &[&str]) -> String {
+    const BEFORE: usize = 30;
+    const AFTER: usize = 70;
+    let lower = text.to_ascii_lowercase();
+    let first = terms
+        .iter()
+        .filter_map(|t| lower.find(&t.to_ascii_lowercase()))
+        .min()
+        .unwrap_or(0);
+    let chars_before = text[..first].chars().count();
+    let start = chars_before.saturating_sub(BEFORE);
+    let total = text.chars().count();
+    let end = (chars_before + AFTER).min(total);
+
+    let mut out: String = text.chars().skip(start).take(end - start).collect();
+    if start > 0 {
+        out.insert(0, '…');
+    }
+    if end < total {
+        out.push('…');
+    }
+    out
+}
+
+fn contains_ignore_ascii_case(text: &str, term: &str) -> bool {
+    text.to_ascii_lowercase().contains(&term.to_ascii_lowercase())
+}
+
+/// Wraps every (ASCII case-insensitive) occurrence of `terms` in `<mark>`.
+fn highlight(text: &str, terms: &[&str]) -> String {
+    // ASCII lowercasing keeps byte offsets identical to `text`
+    let lower = text.to_ascii_lowercase();
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for term in terms {
+        let term = term.to_ascii_lowercase();
+        ranges.extend(lower.match_indices(&term).map(|(start, m)| (start, start + m.len())));
+    }
+    ranges.sort();
+
+    let mut out = String::with_capacity(text.len());
+    let mut pos = 0;
+    for (start, end) in ranges {
+        if start < pos {
+            continue;
+        }
+        out.push_str(&text[pos..start]);
+        out.push_str("<mark>");
+        out.push_str(&text[start..end]);
+        out.push_str("</mark>");
+        pos = end;
+    }
+    out.push_str(&text[pos..]);
+    out
+}
+
+/// Todos with local changes waiting to be pushed, archived ones included.
+pub async fn fetch_pending_todos(db: &SqlitePool) -> Result<Vec<Todo>, sqlx::Error> {
+    sqlx::query_as::<_, Todo>(&format!("SELECT {} FROM todos WHERE sync_state != 'synced'", TODO_COLUMNS))
+        .fetch_all(db)
+        .await
+}
+
+pub async fn insert_todo(
+    db: &SqlitePool,
+    req: NewTodoRequest,
+) -> Result<Todo, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let todo = insert_todo_in(&mut tx, Uuid::new_v4().to_string(), req).await?;
+    tx.commit().await?;
+    Ok(todo)
+}
+
+/// `insert_todo` within the caller's transaction, with a given id
+/// (client-generated ids from `POST /batch`).
+pub async fn insert_todo_in(
+    conn: &mut SqliteConnection,
+    id: String,
+    req: NewTodoRequest,
+) -> Result<Todo, sqlx::Error> {
+    let due_date = parse_due_date(&req.due_date)?;
+    let due_end = req.due_end.as_deref().map(parse_due_date).transpose()?;
+    let priority = req.priority.as_deref().map(parse_priority).transpose()?;
+    let now = Utc::now();
+    let sync_state = "pending".to_string();
+
+    let stamp = timestamp(now);
+    sqlx::query!(
+        r#"
+        INSERT INTO todos
+            (id, course_id, title, due_date, due_end, due_timezone, status, priority, notes,
+            is_archived, updated_at, sync_state, last_synced_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 0, ?10, ?11, NULL)
+        "#,
+        id,
+        req.course_id,
+        req.title,
+        due_date,
+        due_end,
+        req.due_timezone,
+        req.status,
+        priority,
+        req.notes,
+        stamp,
+        sync_state,
+    )
+    .execute(&mut *conn)
+    .await?;
+    set_todo_tags(conn, &id, &req.tags).await?;
+
+    // re-read for the version the triggers assigned
+    find_todo_in(conn, &id).await?.ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Applies a partial update and marks the todo pending.
+///
+/// `completed_at` follows the status: it is stamped when the todo moves into
+/// the done group and cleared when it moves to any other group.
+pub async fn update_todo(
+    db: &SqlitePool,
+    id: &str,
+    req: UpdateTodoRequest,
+    statuses: &StatusMapping,
+) -> Result<Option<Todo>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let todo = update_todo_in(&mut tx, id, req, statuses).await?;
+    tx.commit().await?;
+    Ok(todo)
+}
+
+pub async fn update_todo_in(
+    conn: &mut SqliteConnection,
+    id: &str,
+    req: UpdateTodoRequest,
+    statuses: &StatusMapping,
+) -> Result<Option<Todo>, sqlx::Error> {
+    let mut current = match find_todo_in(conn, id).await? {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+
+    if let Some(title) = req.title {
+        current.title = title;
+    }
+    if let Some(due_date) = req.due_date {
+        current.due_date = parse_due_date(&due_date)?;
+    }
+    // An empty string clears the range end / time zone
+    if let Some(due_end) = req.due_end {
+        current.due_end = Some(due_end).filter(|v| !v.is_empty()).as_deref().map(parse_due_date).transpose()?;
+    }
+    if let Some(due_timezone) = req.due_timezone {
+        current.due_timezone = Some(due_timezone).filter(|v| !v.is_empty());
+    }
+    // An empty string clears the priority
+    if let Some(priority) = req.priority {
+        current.priority = Some(priority).filter(|v| !v.is_empty()).as_deref().map(parse_priority).transpose()?;
+    }
+    if let Some(tags) = req.tags {
+        current.tags = tags;
+    }
+    if let Some(notes) = req.notes {
+        current.notes = notes;
+    }
+    let now = Utc::now();
+    if let Some(status) = req.status {
+        let was_done = statuses.is_done(&current.status);
+        current.status = statuses.normalize(status);
+        if !statuses.is_done(&current.status) {
+            current.completed_at = None;
+        } else if !was_done || current.completed_at.is_none() {
+            current.completed_at = Some(now);
+        }
+    }
+    current.updated_at = now;
+    current.sync_state = "pending".to_string();
+
+    let completed_at = current.completed_at.map(timestamp);
+    let stamp = timestamp(now);
+    sqlx::query!(
+        r#"
+        UPDATE todos
+        SET title = ?1,
+            due_date = ?2,
+            due_end = ?3,
+            due_timezone = ?4,
+            status = ?5,
+            priority = ?6,
+            notes = ?7,
+            completed_at = ?8,
+            updated_at = ?9,
+            sync_state = ?10
+        WHERE id = ?11
+        "#,
+        current.title,
+        current.due_date,
+        current.due_end,
+        current.due_timezone,
+        current.status,
+        current.priority,
+        current.notes,
+        completed_at,
+        stamp,
+        current.sync_state,
+        id
+    )
+    .execute(&mut *conn)
+    .await?;
+    set_todo_tags(conn, id, &current.tags).await?;
+
+    find_todo_in(conn, id).await
+}
+
+/// Moves a todo to `done` (`completed = true`) or back to `not_started`.
+pub async fn set_todo_completed(
+    db: &SqlitePool,
+    id: &str,
+    completed: bool,
+    statuses: &StatusMapping,
+) -> Result<Option<Todo>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let todo = set_todo_completed_in(&mut tx, id, completed, statuses).await?;
+    tx.commit().await?;
+    Ok(todo)
+}
+
+pub async fn set_todo_completed_in(
+    conn: &mut SqliteConnection,
+    id: &str,
+    completed: bool,
+    statuses: &StatusMapping,
+) -> Result<Option<Todo>, sqlx::Error> {
+    let status = if completed { TodoStatus::Done } else { TodoStatus::NotStarted };
+    let req = UpdateTodoRequest {
+        title: None,
+        due_date: None,
+        due_end: None,
+        due_timezone: None,
+        status: Some(status),
+        priority: None,
+        tags: None,
+        notes: None,
+    };
+    update_todo_in(conn, id, req, statuses).await
+}
+
+pub async fn archive_todo(db: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    archive_todo_in(&mut conn, id).await
+}
+
+pub async fn archive_todo_in(conn: &mut SqliteConnection, id: &str) -> Result<bool, sqlx::Error> {
+    let now = Utc::now();
+    let stamp = timestamp(now);
+    let result = sqlx::query!(
+        r#"
+        UPDATE todos
+        SET is_archived = 1,
+            updated_at = ?2,
+            sync_state = 'pending'
+        WHERE id = ?1
+        "#,
+        id,
+        stamp,
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    Ok(result > 0)
+}
+
+/// Hard-deletes a todo that has never been synced to Notion.
+pub async fn delete_todo_draft(db: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    delete_todo_draft_in(&mut conn, id).await
+}
+
+pub async fn delete_todo_draft_in(conn: &mut SqliteConnection, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "DELETE FROM todos WHERE id = ?1 AND last_synced_at IS NULL",
+        id,
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    Ok(result > 0)
+}
+
+/// Local ids of the courses whose Notion page has another id, keyed by page id.
+pub async fn fetch_course_ids_by_page(db: &SqlitePool) -> Result<HashMap<String, String>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT notion_page_id AS "page_id!", id AS "id!" FROM courses WHERE notion_page_id IS NOT NULL"#
+    )
+    .fetch_all(db)
+    .await?;
+    Ok(rows.into_iter().map(|row| (row.page_id, row.id)).collect())
+}
+
+/// Records the Notion page created for a local course; the course keeps its id.
+///
+/// Written right after the page is created, like [`set_todo_notion_page`], so a
+/// later failure does not lead to a second page. The course stays pending.
+pub async fn set_course_notion_page(db: &SqlitePool, id: &str, page_id: &str) -> Result<(), sqlx::Error> {
+    let now = timestamp(Utc::now());
+    sqlx::query!(
+        "UPDATE courses SET notion_page_id = ?1, last_synced_at = ?2 WHERE id = ?3",
+        page_id,
+        now,
+        id,
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Records the Notion page created for a local todo; the todo keeps its id.
+///
+/// The page id and `last_synced_at` are written in one statement right after
+/// the page is created, so if pushing the body fails afterwards the next sync
+/// updates this page instead of creating another. The todo stays pending.
+pub async fn set_todo_notion_page(db: &SqlitePool, id: &str, page_id: &str) -> Result<(), sqlx::Error> {
+    let now = timestamp(Utc::now());
+    sqlx::query!(
+        "UPDATE todos SET notion_page_id = ?1, last_synced_at = ?2 WHERE id = ?3",
+        page_id,
+        now,
+        id,
+    )
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+pub async fn find_course_by_id(db: &SqlitePool, id: &str) -> Result<Option<Course>, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    find_course_in(&mut conn, id).await
+}
+
+pub async fn find_course_in(conn: &mut SqliteConnection, id: &str) -> Result<Option<Course>, sqlx::Error> {
+    sqlx::query_as::<_, Course>(&format!("SELECT {} FROM courses WHERE id = ?", COURSE_COLUMNS))
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await
+}
+
+/// Records that a pulled row matched the local content: only Notion's edit
+/// time and the sync time move, so no history entry is written. Nothing is
+/// written unless the edit time moved forward (a sync without Notion edits
+/// touches no row); bookkeeping columns are not logged to `change_log`.
+pub async fn mark_pulled_unchanged(
+    db: &SqlitePool,
+    entity: ChangeEntity,
+    id: &str,
+    updated_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    let updated_at = timestamp(updated_at);
+    let now = timestamp(Utc::now());
+    match entity {
+        ChangeEntity::Course => {
+            sqlx::query!(
+                "UPDATE courses SET updated_at = ?1, last_synced_at = ?2 WHERE id = ?3 AND updated_at < ?1",
+                updated_at,
+                now,
+                id,
+            )
+            .execute(db)
+            .await?;
+        }
+        ChangeEntity::Todo => {
+            sqlx::query!(
+                "UPDATE todos SET updated_at = ?1, last_synced_at = ?2 WHERE id = ?3 AND updated_at < ?1",
+                updated_at,
+                now,
+                id,
+            )
+            .execute(db)
+            .await?;
+        }
+        // semesters are not synced
+        ChangeEntity::Semester => {}
+    }
+    Ok(())
+}
+
+pub async fn upsert_course(db: &SqlitePool, course: &Course) -> Result<Course, sqlx::Error> {
+    let existing = find_course_by_id(db, &course.id).await?;
+    let mut tx = db.begin().await?;
+    match existing {
+        Some(_) => {
+            // Update
+            sqlx::query(
+                "UPDATE courses SET title = ?, room = ?, is_archived = ?, updated_at = ?, sync_state = ?, last_synced_at = ?, notion_page_id = coalesce(?, notion_page_id) WHERE id = ?"
+            )
+            .bind(&course.title)
+            .bind(&course.room)
+            .bind(course.is_archived)
+            .bind(timestamp(course.updated_at))
+            .bind(&course.sync_state)
+            .bind(course.last_synced_at.map(timestamp))
+            .bind(&course.notion_page_id)
+            .bind(&course.id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        None => {
+            // Insert
+            sqlx::query(
+                "INSERT INTO courses (id, title, room, is_archived, updated_at, sync_state, last_synced_at, notion_page_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&course.id)
+            .bind(&course.title)
+            .bind(&course.room)
+            .bind(course.is_archived)
+            .bind(timestamp(course.updated_at))
+            .bind(&course.sync_state)
+            .bind(course.last_synced_at.map(timestamp))
+            .bind(&course.notion_page_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    set_course_semesters(&mut tx, &course.id, &course.semesters).await?;
+    set_course_meetings(&mut tx, &course.id, &course.meetings).await?;
+    set_course_instructors(&mut tx, &course.id, &course.instructors).await?;
+    tx.commit().await?;
+
+    find_course_by_id(db, &course.id)
+        .await?
+        .ok_or_else(|| sqlx::Error::RowNotFound)
+}
+
+pub async fn find_todo_by_id(db: &SqlitePool, id: &str) -> Result<Option<Todo>, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    find_todo_in(&mut conn, id).await
+}
+
+pub async fn find_todo_in(conn: &mut SqliteConnection, id: &str) -> Result<Option<Todo>, sqlx::Error> {
+    sqlx::query_as::<_, Todo>(&format!("SELECT {} FROM todos WHERE id = ?", TODO_COLUMNS))
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await
+}
+
+pub async fn upsert_todo(db: &SqlitePool, todo: &Todo) -> Result<Todo, sqlx::Error> {
+    let existing = find_todo_by_id(db, &todo.id).await?;
+    let mut tx = db.begin().await?;
+    match existing {
+        Some(_) => {
+            sqlx::query(
+                "UPDATE todos SET course_id = ?, title = ?, due_date = ?, due_end = ?, due_timezone = ?, status = ?, priority = ?, notes = ?, completed_at = ?, is_archived = ?, updated_at = ?, sync_state = ?, last_synced_at = ?, notion_page_id = coalesce(?, notion_page_id) WHERE id = ?"
+            )
+            .bind(&todo.course_id)
+            .bind(&todo.title)
+            .bind(todo.due_date)
+            .bind(todo.due_end)
+            .bind(&todo.due_timezone)
+            .bind(&todo.status)
+            .bind(todo.priority)
+            .bind(&todo.notes)
+            .bind(todo.completed_at.map(timestamp))
+            .bind(todo.is_archived)
+            .bind(timestamp(todo.updated_at))
+            .bind(&todo.sync_state)
+            .bind(todo.last_synced_at.map(timestamp))
+            .bind(&todo.notion_page_id)
+            .bind(&todo.id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        None => {
+            sqlx::query(
+                "INSERT INTO todos (id, course_id, title, due_date, due_end, due_timezone, status, priority, notes, completed_at, is_archived, updated_at, sync_state, last_synced_at, notion_page_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&todo.id)
+            .bind(&todo.course_id)
+            .bind(&todo.title)
+            .bind(todo.due_date)
+            .bind(todo.due_end)
+            .bind(&todo.due_timezone)
+            .bind(&todo.status)
+            .bind(todo.priority)
+            .bind(&todo.notes)
+            .bind(todo.completed_at.map(timestamp))
+            .bind(todo.is_archived)
+            .bind(timestamp(todo.updated_at))
+            .bind(&todo.sync_state)
+            .bind(todo.last_synced_at.map(timestamp))
+            .bind(&todo.notion_page_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    set_todo_tags(&mut tx, &todo.id, &todo.tags).await?;
+    tx.commit().await?;
+
+    find_todo_by_id(db, &todo.id)
+        .await?
+        .ok_or_else(|| sqlx::Error::RowNotFound)
+}
+
+pub async fn fetch_subtasks(db: &SqlitePool, todo_id: &str) -> Result<Vec<Subtask>, sqlx::Error> {
+    sqlx::query_as::<_, Subtask>(
+        "SELECT id, todo_id, title, done, position, updated_at FROM subtasks WHERE todo_id = ? ORDER BY position, id",
+    )
+    .bind(todo_id)
+    .fetch_all(db)
+    .await
+}
+
+async fn find_subtask(conn: &mut SqliteConnection, todo_id: &str, id: &str) -> Result<Option<Subtask>, sqlx::Error> {
+    sqlx::query_as::<_, Subtask>(
+        "SELECT id, todo_id, title, done, position, updated_at FROM subtasks WHERE todo_id = ? AND id = ?",
+    )
+    .bind(todo_id)
+    .bind(id)
+    .fetch_optional(conn)
+    .await
+}
+
+/// Moves `id` to `position` (clamped to the end) and renumbers the list 0..n.
+async fn move_subtask(conn: &mut SqliteConnection, todo_id: &str, id: &str, position: i64) -> Result<(), sqlx::Error> {
+    let mut ids: Vec<String> = sqlx::query_scalar(
+        "SELECT id FROM subtasks WHERE todo_id = ? AND id != ? ORDER BY position, id",
+    )
+    .bind(todo_id)
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let index = usize::try_from(position).unwrap_or(0).min(ids.len());
+    ids.insert(index, id.to_string());
+
+    for (position, id) in ids.iter().enumerate() {
+        sqlx::query("UPDATE subtasks SET position = ? WHERE id = ?")
+            .bind(position as i64)
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Marks the parent todo pending so the checklist is pushed on the next sync.
+async fn touch_todo(conn: &mut SqliteConnection, todo_id: &str, now: DateTime<Utc>) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE todos SET updated_at = ?, sync_state = 'pending' WHERE id = ?")
+        .bind(timestamp(now))
+        .bind(todo_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+pub async fn insert_subtask(db: &SqlitePool, todo_id: &str, req: NewSubtaskRequest) -> Result<Subtask, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let subtask = insert_subtask_in(&mut tx, todo_id, req).await?;
+    tx.commit().await?;
+    Ok(subtask)
+}
+
+pub async fn insert_subtask_in(
+    conn: &mut SqliteConnection,
+    todo_id: &str,
+    req: NewSubtaskRequest,
+) -> Result<Subtask, sqlx::Error> {
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+    sqlx::query(
+        "INSERT INTO subtasks (id, todo_id, title, done, position, updated_at) \
+         VALUES (?, ?, ?, ?, (SELECT coalesce(max(position) + 1, 0) FROM subtasks WHERE todo_id = ?), ?)",
+    )
+    .bind(&id)
+    .bind(todo_id)
+    .bind(&req.title)
+    .bind(req.done)
+    .bind(todo_id)
+    .bind(timestamp(now))
+    .execute(&mut *conn)
+    .await?;
+    if let Some(position) = req.position {
+        move_subtask(conn, todo_id, &id, position).await?;
+    }
+    touch_todo(conn, todo_id, now).await?;
+
+    find_subtask(conn, todo_id, &id).await?.ok_or(sqlx::Error::RowNotFound)
+}
+
+pub async fn update_subtask(
+    db: &SqlitePool,
+    todo_id: &str,
+    id: &str,
+    req: UpdateSubtaskRequest,
+) -> Result<Option<Subtask>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let subtask = update_subtask_in(&mut tx, todo_id, id, req).await?;
+    tx.commit().await?;
+    Ok(subtask)
+}
+
+pub async fn update_subtask_in(
+    conn: &mut SqliteConnection,
+    todo_id: &str,
+    id: &str,
+    req: UpdateSubtaskRequest,
+) -> Result<Option<Subtask>, sqlx::Error> {
+    let Some(mut current) = find_subtask(conn, todo_id, id).await? else {
+        return Ok(None);
+    };
+
+    if let Some(title) = req.title {
+        current.title = title;
+    }
+    if let Some(done) = req.done {
+        current.done = done;
+    }
+    let now = Utc::now();
+    sqlx::query("UPDATE subtasks SET title = ?, done = ?, updated_at = ? WHERE id = ?")
+        .bind(&current.title)
+        .bind(current.done)
+        .bind(timestamp(now))
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    if let Some(position) = req.position {
+        move_subtask(conn, todo_id, id, position).await?;
+    }
+    touch_todo(conn, todo_id, now).await?;
+
+    find_subtask(conn, todo_id, id).await
+}
+
+pub async fn delete_subtask(db: &SqlitePool, todo_id: &str, id: &str) -> Result<bool, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let deleted = delete_subtask_in(&mut tx, todo_id, id).await?;
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+pub async fn delete_subtask_in(conn: &mut SqliteConnection, todo_id: &str, id: &str) -> Result<bool, sqlx::Error> {
+    let deleted = sqlx::query("DELETE FROM subtasks WHERE todo_id = ? AND id = ?")
+        .bind(todo_id)
+        .bind(id)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected()
+        > 0;
+    if deleted {
+        touch_todo(conn, todo_id, Utc::now()).await?;
+    }
+    Ok(deleted)
+}
+
+/// Replaces the checklist with the to-do blocks pulled from Notion.
+///
+/// Rows whose title is unchanged keep their id so clients can follow them
+/// across syncs. The todo's sync state is left alone, and nothing is written
+/// when the checklist is the same.
+pub async fn replace_subtasks(db: &SqlitePool, todo_id: &str, items: &[SubtaskItem]) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let mut existing: Vec<(String, String, bool)> = sqlx::query_as(
+        "SELECT id, title, done FROM subtasks WHERE todo_id = ? ORDER BY position, id",
+    )
+    .bind(todo_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    if existing.iter().map(|(_, title, done)| (title, *done)).eq(items.iter().map(|i| (&i.title, i.done))) {
+        return Ok(());
+    }
+    sqlx::query("DELETE FROM subtasks WHERE todo_id = ?")
+        .bind(todo_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let now = Utc::now();
+    for (position, item) in items.iter().enumerate() {
+        let id = match existing.iter().position(|(_, title, _)| *title == item.title) {
+            Some(index) => existing.remove(index).0,
+            None => Uuid::new_v4().to_string(),
+        };
+        sqlx::query(
+            "INSERT INTO subtasks (id, todo_id, title, done, position, updated_at) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(todo_id)
+        .bind(&item.title)
+        .bind(item.done)
+        .bind(position as i64)
+        .bind(timestamp(now))
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await
+}
+
+const SERIES_COLUMNS: &str = "id, template_todo_id, rule, start_date, generated_until, is_active, created_at, updated_at";
+
+/// Attaches a recurrence rule to `template`; the template becomes the first instance.
+pub async fn insert_series(
+    db: &SqlitePool,
+    template: &Todo,
+    rule: &str,
+    start_date: NaiveDate,
+) -> Result<TodoSeries, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let series = insert_series_in(&mut tx, template, rule, start_date).await?;
+    tx.commit().await?;
+    Ok(series)
+}
+
+pub async fn insert_series_in(
+    conn: &mut SqliteConnection,
+    template: &Todo,
+    rule: &str,
+    start_date: NaiveDate,
+) -> Result<TodoSeries, sqlx::Error> {
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+    sqlx::query(
+        "INSERT INTO todo_series (id, template_todo_id, rule, start_date, generated_until, is_active, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, 1, ?, ?)",
+    )
+    .bind(&id)
+    .bind(&template.id)
+    .bind(rule)
+    .bind(start_date)
+    .bind(start_date)
+    .bind(timestamp(now))
+    .bind(timestamp(now))
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query("UPDATE todos SET series_id = ?, series_date = ? WHERE id = ?")
+        .bind(&id)
+        .bind(start_date)
+        .bind(&template.id)
+        .execute(&mut *conn)
+        .await?;
+
+    find_series_in(conn, &id).await?.ok_or(sqlx::Error::RowNotFound)
+}
+
+pub async fn find_series(db: &SqlitePool, id: &str) -> Result<Option<TodoSeries>, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    find_series_in(&mut conn, id).await
+}
+
+pub async fn find_series_in(conn: &mut SqliteConnection, id: &str) -> Result<Option<TodoSeries>, sqlx::Error> {
+    sqlx::query_as::<_, TodoSeries>(&format!("SELECT {} FROM todo_series WHERE id = ?", SERIES_COLUMNS))
+        .bind(id)
+        .fetch_optional(conn)
+        .await
+}
+
+/// All series, active ones first, newest first.
+pub async fn fetch_series(db: &SqlitePool) -> Result<Vec<TodoSeries>, sqlx::Error> {
+    sqlx::query_as::<_, TodoSeries>(&format!(
+        "SELECT {} FROM todo_series ORDER BY is_active DESC, created_at DESC, id",
+        SERIES_COLUMNS
+    ))
+    .fetch_all(db)
+    .await
+}
+
+pub async fn fetch_active_series(db: &SqlitePool) -> Result<Vec<TodoSeries>, sqlx::Error> {
+    sqlx::query_as::<_, TodoSeries>(&format!("SELECT {} FROM todo_series WHERE is_active = 1", SERIES_COLUMNS))
+        .fetch_all(db)
+        .await
+}
+
+/// Replaces the rule; instances are generated again from `generated_until`.
+pub async fn update_series_rule_in(
+    conn: &mut SqliteConnection,
+    id: &str,
+    rule: &str,
+    generated_until: NaiveDate,
+) -> Result<Option<TodoSeries>, sqlx::Error> {
+    sqlx::query("UPDATE todo_series SET rule = ?, generated_until = ?, updated_at = ? WHERE id = ?")
+        .bind(rule)
+        .bind(generated_until)
+        .bind(timestamp(Utc::now()))
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    find_series_in(conn, id).await
+}
+
+pub async fn stop_series_in(conn: &mut SqliteConnection, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE todo_series SET is_active = 0, updated_at = ? WHERE id = ? AND is_active = 1")
+        .bind(timestamp(Utc::now()))
+        .bind(id)
+        .execute(conn)
+        .await?
+        .rows_affected();
+    Ok(result > 0)
+}
+
+pub async fn set_series_generated_until_in(
+    conn: &mut SqliteConnection,
+    id: &str,
+    date: NaiveDate,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE todo_series SET generated_until = ? WHERE id = ?")
+        .bind(date)
+        .bind(id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Weekdays the course meets on, used by weekly rules without `BYDAY`.
+pub async fn fetch_course_meeting_days_in(
+    conn: &mut SqliteConnection,
+    course_id: &str,
+) -> Result<Vec<Weekday>, sqlx::Error> {
+    let days: Vec<String> = sqlx::query_scalar("SELECT DISTINCT day_of_week FROM course_meetings WHERE course_id = ?")
+        .bind(course_id)
+        .fetch_all(conn)
+        .await?;
+    Ok(days.iter().filter_map(|d| d.parse().ok()).collect())
+}
+
+/// Creates the instance of a series for `series_date` as a pending copy of
+/// `template` (tags, notes and an unchecked checklist included).
+///
+/// Returns `None` when the series already has a todo for that date.
+pub async fn insert_series_instance_in(
+    conn: &mut SqliteConnection,
+    template: &Todo,
+    series_id: &str,
+    series_date: NaiveDate,
+    due_date: DueDate,
+    due_end: Option<DueDate>,
+) -> Result<Option<Todo>, sqlx::Error> {
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+    let inserted = sqlx::query(
+        "INSERT OR IGNORE INTO todos \
+            (id, course_id, title, due_date, due_end, due_timezone, status, priority, notes, \
+             series_id, series_date, is_archived, updated_at, sync_state, last_synced_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0, ?, 'pending', NULL)",
+    )
+    .bind(&id)
+    .bind(&template.course_id)
+    .bind(&template.title)
+    .bind(due_date)
+    .bind(due_end)
+    .bind(&template.due_timezone)
+    .bind(TodoStatus::NotStarted)
+    .bind(template.priority)
+    .bind(&template.notes)
+    .bind(series_id)
+    .bind(series_date)
+    .bind(timestamp(now))
+    .execute(&mut *conn)
+    .await?
+    .rows_affected()
+        > 0;
+    if !inserted {
+        return Ok(None);
+    }
+
+    set_todo_tags(conn, &id, &template.tags).await?;
+    let checklist: Vec<(String, i64)> =
+        sqlx::query_as("SELECT title, position FROM subtasks WHERE todo_id = ? ORDER BY position, id")
+            .bind(&template.id)
+            .fetch_all(&mut *conn)
+            .await?;
+    for (title, position) in checklist {
+        sqlx::query(
+            "INSERT INTO subtasks (id, todo_id, title, done, position, updated_at) VALUES (?, ?, ?, 0, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&id)
+        .bind(title)
+        .bind(position)
+        .bind(timestamp(now))
+        .execute(&mut *conn)
+        .await?;
+    }
+    find_todo_in(conn, &id).await
+}
+
+/// A series instance taken out by `remove_future_series_instances_in`:
+/// (todo id, state before, whether it was archived rather than deleted).
+pub type RemovedInstance = (String, Option<Snapshot>, bool);
+
+pub async fn remove_future_series_instances(
+    db: &SqlitePool,
+    series_id: &str,
+    after: NaiveDate,
+) -> Result<Vec<RemovedInstance>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let removed = remove_future_series_instances_in(&mut tx, series_id, after).await?;
+    tx.commit().await?;
+    Ok(removed)
+}
+
+/// Removes the instances of a series dated after `after`: drafts that never
+/// reached Notion are deleted, synced ones are archived (and pushed as such)
+/// and give up their date so the series can fill it again. The template is kept.
+pub async fn remove_future_series_instances_in(
+    conn: &mut SqliteConnection,
+    series_id: &str,
+    after: NaiveDate,
+) -> Result<Vec<RemovedInstance>, sqlx::Error> {
+    let ids: Vec<String> = sqlx::query_scalar(
+        "SELECT id FROM todos WHERE series_id = ?1 AND series_date > ?2 \
+         AND id NOT IN (SELECT template_todo_id FROM todo_series WHERE id = ?1)",
+    )
+    .bind(series_id)
+    .bind(after)
+    .fetch_all(&mut *conn)
+    .await?;
+    let mut before = Vec::with_capacity(ids.len());
+    for id in &ids {
+        before.push(snapshot_in(conn, ChangeEntity::Todo, id).await?);
+    }
+
+    sqlx::query(
+        "DELETE FROM todos WHERE series_id = ?1 AND series_date > ?2 AND last_synced_at IS NULL \
+         AND id NOT IN (SELECT template_todo_id FROM todo_series WHERE id = ?1)",
+    )
+    .bind(series_id)
+    .bind(after)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(
+        "UPDATE todos SET is_archived = 1, series_date = NULL, updated_at = ?3, sync_state = 'pending' \
+         WHERE series_id = ?1 AND series_date > ?2 \
+         AND id NOT IN (SELECT template_todo_id FROM todo_series WHERE id = ?1)",
+    )
+    .bind(series_id)
+    .bind(after)
+    .bind(timestamp(Utc::now()))
+    .execute(&mut *conn)
+    .await?;
+
+    let mut removed = Vec::with_capacity(ids.len());
+    for (id, before) in ids.into_iter().zip(before) {
+        let archived = find_todo_in(conn, &id).await?.is_some();
+        removed.push((id, before, archived));
+    }
+    Ok(removed)
+}
+
+fn parse_offsets(value: &str) -> Result<Vec<i64>, sqlx::Error> {
+    serde_json::from_str(value).map_err(|e| sqlx::Error::Decode(e.into()))
+}
+
+/// The course's default reminder offsets, `None` if the course does not exist.
+pub async fn fetch_course_reminder_settings(db: &SqlitePool, course_id: &str) -> Result<Option<ReminderSettings>, sqlx::Error> {
+    let offsets: Option<String> = sqlx::query_scalar("SELECT reminder_offsets FROM courses WHERE id = ?")
+        .bind(course_id)
+        .fetch_optional(db)
+        .await?;
+    offsets
+        .map(|offsets| {
+            let offsets = parse_offsets(&offsets)?;
+            Ok(ReminderSettings { offsets: Some(offsets.clone()), effective: offsets })
+        })
+        .transpose()
+}
+
+/// Reminder settings are local to this backend; they don't touch `sync_state`.
+pub async fn set_course_reminder_offsets(db: &SqlitePool, course_id: &str, offsets: &[i64]) -> Result<bool, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    set_course_reminder_offsets_in(&mut conn, course_id, offsets).await
+}
+
+pub async fn set_course_reminder_offsets_in(
+    conn: &mut SqliteConnection,
+    course_id: &str,
+    offsets: &[i64],
+) -> Result<bool, sqlx::Error> {
+    let offsets = serde_json::to_string(offsets).map_err(|e| sqlx::Error::Encode(e.into()))?;
+    let result = sqlx::query("UPDATE courses SET reminder_offsets = ? WHERE id = ?")
+        .bind(offsets)
+        .bind(course_id)
+        .execute(conn)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// The todo's own offsets and the ones actually used (falling back to the course's).
+pub async fn fetch_todo_reminder_settings(db: &SqlitePool, todo_id: &str) -> Result<Option<ReminderSettings>, sqlx::Error> {
+    let row: Option<(Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT todos.reminder_offsets, courses.reminder_offsets FROM todos \
+         LEFT JOIN courses ON courses.id = todos.course_id WHERE todos.id = ?",
+    )
+    .bind(todo_id)
+    .fetch_optional(db)
+    .await?;
+    let Some((own, course)) = row else {
+        return Ok(None);
+    };
+    let offsets = own.as_deref().map(parse_offsets).transpose()?;
+    let effective = match &offsets {
+        Some(offsets) => offsets.clone(),
+        None => course.as_deref().map(parse_offsets).transpose()?.unwrap_or_default(),
+    };
+    Ok(Some(ReminderSettings { offsets, effective }))
+}
+
+/// `None` makes the todo follow its course's defaults again.
+pub async fn set_todo_reminder_offsets(db: &SqlitePool, todo_id: &str, offsets: Option<&[i64]>) -> Result<bool, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    set_todo_reminder_offsets_in(&mut conn, todo_id, offsets).await
+}
+
+pub async fn set_todo_reminder_offsets_in(
+    conn: &mut SqliteConnection,
+    todo_id: &str,
+    offsets: Option<&[i64]>,
+) -> Result<bool, sqlx::Error> {
+    let offsets = offsets
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| sqlx::Error::Encode(e.into()))?;
+    let result = sqlx::query("UPDATE todos SET reminder_offsets = ? WHERE id = ?")
+        .bind(offsets)
+        .bind(todo_id)
+        .execute(conn)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Open todos (not archived, not in the done group) with a non-empty
+/// effective offset list, paired with those offsets.
+pub async fn fetch_reminder_targets(
+    db: &SqlitePool,
+    done_statuses: &[TodoStatus],
+) -> Result<Vec<(Todo, Vec<i64>)>, sqlx::Error> {
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT todos.id, coalesce(todos.reminder_offsets, courses.reminder_offsets, '[]') FROM todos \
+         LEFT JOIN courses ON courses.id = todos.course_id WHERE todos.is_archived = 0",
+    )
+    .fetch_all(db)
+    .await?;
+    let mut offsets = HashMap::new();
+    for (id, value) in rows {
+        let value = parse_offsets(&value)?;
+        if !value.is_empty() {
+            offsets.insert(id, value);
+        }
+    }
+    if offsets.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let todos = open_todos_query(done_statuses, None).build_query_as::<Todo>().fetch_all(db).await?;
+    Ok(todos
+        .into_iter()
+        .filter_map(|todo| offsets.remove(&todo.id).map(|offsets| (todo, offsets)))
+        .collect())
+}
+
+/// Makes the unacknowledged reminders match `planned`: reminders no longer
+/// planned (deadline moved, todo completed, offsets changed) are dropped and
+/// missing ones are created. Acknowledged reminders are kept, so a reminder
+/// that was delivered is not created again. Returns the number created.
+pub async fn sync_reminders(db: &SqlitePool, planned: &[PlannedReminder]) -> Result<u64, sqlx::Error> {
+    let now = Utc::now();
+    let mut tx = db.begin().await?;
+    let existing: Vec<(String, String, i64, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT id, todo_id, offset_minutes, remind_at FROM reminders WHERE acknowledged_at IS NULL",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let wanted: HashSet<(&str, i64, DateTime<Utc>)> = planned
+        .iter()
+        .map(|p| (p.todo_id.as_str(), p.offset_minutes, p.remind_at))
+        .collect();
+    for (id, todo_id, offset_minutes, remind_at) in &existing {
+        if !wanted.contains(&(todo_id.as_str(), *offset_minutes, *remind_at)) {
+            sqlx::query("DELETE FROM reminders WHERE id = ?").bind(id).execute(&mut *tx).await?;
+        }
+    }
+
+    let mut created = 0;
+    for reminder in planned {
+        created += sqlx::query(
+            "INSERT OR IGNORE INTO reminders (id, todo_id, offset_minutes, remind_at, due_at, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&reminder.todo_id)
+        .bind(reminder.offset_minutes)
+        .bind(timestamp(reminder.remind_at))
+        .bind(timestamp(reminder.due_at))
+        .bind(timestamp(now))
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+    tx.commit().await?;
+    Ok(created)
+}
+
+const REMINDER_COLUMNS: &str = "reminders.id, reminders.todo_id, todos.course_id, todos.title, \
+    reminders.offset_minutes, reminders.remind_at, reminders.due_at, reminders.acknowledged_at";
+
+/// Reminders whose time has come and whose deadline has not passed yet,
+/// not acknowledged by any device, oldest first.
+pub async fn fetch_due_reminders(db: &SqlitePool, now: DateTime<Utc>) -> Result<Vec<Reminder>, sqlx::Error> {
+    sqlx::query_as::<_, Reminder>(&format!(
+        "SELECT {} FROM reminders JOIN todos ON todos.id = reminders.todo_id \
+         WHERE reminders.acknowledged_at IS NULL AND reminders.remind_at <= ?1 AND reminders.due_at > ?1 \
+         ORDER BY reminders.remind_at, reminders.id",
+        REMINDER_COLUMNS
+    ))
+    .bind(timestamp(now))
+    .fetch_all(db)
+    .await
+}
+
+/// Due reminders that have not been broadcast yet; they are marked as
+/// notified so each one goes out on the stream once.
+pub async fn take_unnotified_reminders(db: &SqlitePool, now: DateTime<Utc>) -> Result<Vec<Reminder>, sqlx::Error> {
+    let mut tx = db.begin().await?;
+    let reminders = sqlx::query_as::<_, Reminder>(&format!(
+        "SELECT {} FROM reminders JOIN todos ON todos.id = reminders.todo_id \
+         WHERE reminders.acknowledged_at IS NULL AND reminders.notified_at IS NULL \
+         AND reminders.remind_at <= ?1 AND reminders.due_at > ?1 \
+         ORDER BY reminders.remind_at, reminders.id",
+        REMINDER_COLUMNS
+    ))
+    .bind(timestamp(now))
+    .fetch_all(&mut *tx)
+    .await?;
+    for reminder in &reminders {
+        sqlx::query("UPDATE reminders SET notified_at = ? WHERE id = ?")
+            .bind(timestamp(now))
+            .bind(&reminder.id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(reminders)
+}
+
+pub async fn find_reminder(db: &SqlitePool, id: &str) -> Result<Option<Reminder>, sqlx::Error> {
+    sqlx::query_as::<_, Reminder>(&format!(
+        "SELECT {} FROM reminders JOIN todos ON todos.id = reminders.todo_id WHERE reminders.id = ?",
+        REMINDER_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(db)
+    .await
+}
+
+/// Claims the reminder for the calling device. Only the first call succeeds.
+pub async fn acknowledge_reminder(db: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE reminders SET acknowledged_at = ? WHERE id = ? AND acknowledged_at IS NULL")
+        .bind(timestamp(Utc::now()))
+        .bind(id)
+        .execute(db)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Courses and todos changed after `since` (a `change_log` seq), oldest
+/// change first, at most `limit` entities. Archived and deleted entities are
+/// reported in `removed`.
+///
+/// The cursor is the seq of the last entity returned, so anything written
+/// while the page is assembled shows up on the next call.
+pub async fn fetch_changes(db: &SqlitePool, since: i64, limit: u32) -> Result<Changes, sqlx::Error> {
+    let mut rows: Vec<(i64, String, String)> =
+        sqlx::query_as("SELECT seq, entity, entity_id FROM change_log WHERE seq > ? ORDER BY seq LIMIT ?")
+            .bind(since)
+            .bind(i64::from(limit) + 1)
+            .fetch_all(db)
+            .await?;
+    let has_more = rows.len() > limit as usize;
+    rows.truncate(limit as usize);
+    let cursor = rows.last().map_or(since, |(seq, _, _)| *seq);
+
+    let ids_of = |entity: ChangeEntity| -> Vec<&str> {
+        rows.iter()
+            .filter(|(_, e, _)| e == entity.as_str())
+            .map(|(_, _, id)| id.as_str())
+            .collect()
+    };
+    let course_ids = ids_of(ChangeEntity::Course);
+    let todo_ids = ids_of(ChangeEntity::Todo);
+
+    let mut courses: HashMap<String, Course> = HashMap::new();
+    if !course_ids.is_empty() {
+        let mut qb = QueryBuilder::<Sqlite>::new(format!("SELECT {} FROM courses WHERE id IN (", COURSE_COLUMNS));
+        let mut separated = qb.separated(", ");
+        for id in &course_ids {
+            separated.push_bind(*id);
+        }
+        separated.push_unseparated(")");
+        courses = qb.build_query_as::<Course>().fetch_all(db).await?.into_iter().map(|c| (c.id.clone(), c)).collect();
+    }
+    let mut todos: HashMap<String, Todo> = HashMap::new();
+    if !todo_ids.is_empty() {
+        let mut qb = QueryBuilder::<Sqlite>::new(format!("SELECT {} FROM todos WHERE id IN (", TODO_COLUMNS));
+        let mut separated = qb.separated(", ");
+        for id in &todo_ids {
+            separated.push_bind(*id);
+        }
+        separated.push_unseparated(")");
+        todos = qb.build_query_as::<Todo>().fetch_all(db).await?.into_iter().map(|t| (t.id.clone(), t)).collect();
+    }
+
+    let mut changes = Changes { cursor, has_more, ..Default::default() };
+    for (_, entity, id) in rows {
+        let (entity, archived) = match entity.as_str() {
+            "course" => match courses.remove(&id) {
+                Some(course) if !course.is_archived => {
+                    changes.courses.push(course);
+                    continue;
+                }
+                course => (ChangeEntity::Course, course.is_some()),
+            },
+            _ => match todos.remove(&id) {
+                Some(todo) if !todo.is_archived => {
+                    changes.todos.push(todo);
+                    continue;
+                }
+                todo => (ChangeEntity::Todo, todo.is_some()),
+            },
+        };
+        let reason = if archived { RemovalReason::Archived } else { RemovalReason::Deleted };
+        changes.removed.push(RemovedEntity { entity, id, reason });
+    }
+    Ok(changes)
+}
+
+/// An in-flight idempotent request older than this is assumed to have died
+/// with the server and may be taken over by a retry.
+const IDEMPOTENCY_STALE_SECS: i64 = 60;
+
+/// method, path, request_body, status, content_type, etag, response_body
+type IdempotencyRow = (String, String, Vec<u8>, Option<i64>, Option<String>, Option<String>, Option<Vec<u8>>);
+
+/// Registers an `Idempotency-Key` of `client_id`, or reports what an earlier
+/// request of that client with the same key left behind. Keys older than
+/// `ttl_secs` are forgotten.
+pub async fn begin_idempotent_request(
+    db: &SqlitePool,
+    client_id: &str,
+    key: &str,
+    method: &str,
+    path: &str,
+    body: &[u8],
+    ttl_secs: i64,
+) -> Result<IdempotencyState, sqlx::Error> {
+    let now = Utc::now();
+    sqlx::query("DELETE FROM idempotency_keys WHERE created_at < ?")
+        .bind(timestamp(now - Duration::seconds(ttl_secs)))
+        .execute(db)
+        .await?;
+
+    let inserted = sqlx::query(
+        "INSERT OR IGNORE INTO idempotency_keys (client_id, key, method, path, request_body, created_at) \
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(client_id)
+    .bind(key)
+    .bind(method)
+    .bind(path)
+    .bind(body)
+    .bind(timestamp(now))
+    .execute(db)
+    .await?
+    .rows_affected()
+        > 0;
+    if inserted {
+        return Ok(IdempotencyState::New);
+    }
+
+    let row = sqlx::query_as::<_, IdempotencyRow>(
+        "SELECT method, path, request_body, status, content_type, etag, response_body FROM idempotency_keys \
+         WHERE client_id = ? AND key = ?",
+    )
+    .bind(client_id)
+    .bind(key)
+    .fetch_optional(db)
+    .await?;
+    let Some((stored_method, stored_path, stored_body, status, content_type, etag, response_body)) = row else {
+        // expired between the insert and the lookup; treat it as new next time
+        return Ok(IdempotencyState::InProgress);
+    };
+    if stored_method != method || stored_path != path || stored_body != body {
+        return Ok(IdempotencyState::Mismatch);
+    }
+    if let Some(status) = status {
+        return Ok(IdempotencyState::Replay(StoredResponse {
+            status: status as u16,
+            content_type,
+            etag,
+            body: response_body.unwrap_or_default(),
+        }));
+    }
+
+    let taken_over = sqlx::query(
+        "UPDATE idempotency_keys SET created_at = ? WHERE client_id = ? AND key = ? AND status IS NULL AND created_at < ?",
+    )
+    .bind(timestamp(now))
+    .bind(client_id)
+    .bind(key)
+    .bind(timestamp(now - Duration::seconds(IDEMPOTENCY_STALE_SECS)))
+    .execute(db)
+    .await?
+    .rows_affected()
+        > 0;
+    Ok(if taken_over { IdempotencyState::New } else { IdempotencyState::InProgress })
+}
+
+pub async fn complete_idempotent_request(
+    db: &SqlitePool,
+    client_id: &str,
+    key: &str,
+    response: &StoredResponse,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE idempotency_keys SET status = ?, content_type = ?, etag = ?, response_body = ? \
+         WHERE client_id = ? AND key = ?",
+    )
+    .bind(i64::from(response.status))
+    .bind(&response.content_type)
+    .bind(&response.etag)
+    .bind(&response.body)
+    .bind(client_id)
+    .bind(key)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Forgets a key whose request failed on the server side so it can be retried.
+pub async fn abandon_idempotent_request(db: &SqlitePool, client_id: &str, key: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM idempotency_keys WHERE client_id = ? AND key = ? AND status IS NULL")
+        .bind(client_id)
+        .bind(key)
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+pub async fn snapshot(db: &SqlitePool, entity: ChangeEntity, id: &str) -> Result<Option<Snapshot>, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    snapshot_in(&mut conn, entity, id).await
+}
+
+/// The current state of a course, of a todo with its subtasks and the
+/// series it is the template of, or of a semester.
+pub async fn snapshot_in(
+    conn: &mut SqliteConnection,
+    entity: ChangeEntity,
+    id: &str,
+) -> Result<Option<Snapshot>, sqlx::Error> {
+    Ok(match entity {
+        ChangeEntity::Course => match find_course_in(conn, id).await? {
+            Some(course) => {
+                let offsets: String = sqlx::query_scalar("SELECT reminder_offsets FROM courses WHERE id = ?")
+                    .bind(id)
+                    .fetch_one(&mut *conn)
+                    .await?;
+                Some(Snapshot::Course { course, reminder_offsets: parse_offsets(&offsets)? })
+            }
+            None => None,
+        },
+        ChangeEntity::Todo => match find_todo_in(conn, id).await? {
+            Some(todo) => {
+                let subtasks = sqlx::query_as::<_, Subtask>(
+                    "SELECT id, todo_id, title, done, position, updated_at FROM subtasks WHERE todo_id = ? ORDER BY position, id",
+                )
+                .bind(id)
+                .fetch_all(&mut *conn)
+                .await?;
+                let (series_date, offsets): (Option<NaiveDate>, Option<String>) =
+                    sqlx::query_as("SELECT series_date, reminder_offsets FROM todos WHERE id = ?")
+                        .bind(id)
+                        .fetch_one(&mut *conn)
+                        .await?;
+                let reminder_offsets = offsets.as_deref().map(parse_offsets).transpose()?;
+                let series = sqlx::query_as::<_, TodoSeries>(&format!(
+                    "SELECT {} FROM todo_series WHERE template_todo_id = ?",
+                    SERIES_COLUMNS
+                ))
+                .bind(id)
+                .fetch_optional(&mut *conn)
+                .await?
+                .map(Box::new);
+                Some(Snapshot::Todo { todo, subtasks, series_date, reminder_offsets, series })
+            }
+            None => None,
+        },
+        ChangeEntity::Semester => find_semester_in(conn, id).await?.map(|semester| Snapshot::Semester { semester }),
+    })
+}
+
+pub async fn record_operation(
+    db: &SqlitePool,
+    client_id: &str,
+    action: &str,
+    entity: ChangeEntity,
+    id: &str,
+    before: Option<Snapshot>,
+) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+    record_operation_in(&mut tx, client_id, action, entity, id, before).await?;
+    tx.commit().await
+}
+
+/// Appends a mutation to the client's undo stack and to the entity's history,
+/// taking the state after it from the database. Discards the client's redo
+/// stack; no-op writes are not recorded.
+pub async fn record_operation_in(
+    conn: &mut SqliteConnection,
+    client_id: &str,
+    action: &str,
+    entity: ChangeEntity,
+    id: &str,
+    before: Option<Snapshot>,
+) -> Result<(), sqlx::Error> {
+    insert_operation(conn, client_id, action, entity, id, before, None).await?;
+    Ok(())
+}
+
+/// Records the entities one request changed as a single undo step, in the
+/// order they were written: undo reverts them last to first, redo first to last.
+pub async fn record_operation_group_in(
+    conn: &mut SqliteConnection,
+    client_id: &str,
+    action: &str,
+    entries: Vec<(ChangeEntity, String, Option<Snapshot>)>,
+) -> Result<(), sqlx::Error> {
+    let mut group = None;
+    for (entity, id, before) in entries {
+        let Some(operation_id) = insert_operation(conn, client_id, action, entity, &id, before, group).await? else {
+            continue;
+        };
+        if group.is_none() {
+            sqlx::query("UPDATE operation_log SET group_id = id WHERE id = ?")
+                .bind(operation_id)
+                .execute(&mut *conn)
+                .await?;
+            group = Some(operation_id);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the id of the new log entry, `None` when nothing changed.
+async fn insert_operation(
+    conn: &mut SqliteConnection,
+    client_id: &str,
+    action: &str,
+    entity: ChangeEntity,
+    id: &str,
+    before: Option<Snapshot>,
+    group: Option<i64>,
+) -> Result<Option<i64>, sqlx::Error> {
+    let after = snapshot_in(conn, entity, id).await?;
+    if same_state(before.as_ref(), after.as_ref()) {
+        return Ok(None);
+    }
+    let context = HistoryContext::api(client_id, action);
+    record_history_in(conn, entity, id, &context, before.as_ref(), after.as_ref()).await?;
+
+    sqlx::query("DELETE FROM operation_log WHERE client_id = ? AND undone_at IS NOT NULL")
+        .bind(client_id)
+        .execute(&mut *conn)
+        .await?;
+    let operation_id = sqlx::query(
+        "INSERT INTO operation_log (client_id, action, entity, entity_id, before, after, created_at, group_id) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(client_id)
+    .bind(action)
+    .bind(entity)
+    .bind(id)
+    .bind(before.map(sqlx::types::Json))
+    .bind(after.map(sqlx::types::Json))
+    .bind(timestamp(Utc::now()))
+    .bind(group)
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+    // keep the latest steps whole: a group counts once
+    sqlx::query(
+        "DELETE FROM operation_log WHERE client_id = ?1 AND coalesce(group_id, id) NOT IN \
+         (SELECT coalesce(group_id, id) AS step FROM operation_log WHERE client_id = ?1 \
+          GROUP BY step ORDER BY max(id) DESC LIMIT ?2)",
+    )
+    .bind(client_id)
+    .bind(MAX_UNDO_DEPTH)
+    .execute(&mut *conn)
+    .await?;
+    Ok(Some(operation_id))
+}
+
+/// Both missing, or the same content apart from version and sync bookkeeping.
+pub fn same_state(a: Option<&Snapshot>, b: Option<&Snapshot>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => a.same_content(b),
+        _ => false,
+    }
+}
+
+const OPERATION_COLUMNS: &str = "id, client_id, action, entity, entity_id, created_at, undone_at";
+
+type OperationSnapshots = (Option<sqlx::types::Json<Snapshot>>, Option<sqlx::types::Json<Snapshot>>);
+
+/// An operation with the entity's state before and after it.
+pub type LoggedOperation = (Operation, Option<Snapshot>, Option<Snapshot>);
+
+/// The client's latest step that has not been undone (one operation, or
+/// every operation of its group), latest first, with their before / after states.
+pub async fn last_undoable_operations_in(
+    conn: &mut SqliteConnection,
+    client_id: &str,
+) -> Result<Vec<LoggedOperation>, sqlx::Error> {
+    let operations = sqlx::query_as::<_, Operation>(&format!(
+        "SELECT {} FROM operation_log WHERE client_id = ?1 AND undone_at IS NULL \
+         AND coalesce(group_id, id) = (SELECT coalesce(group_id, id) FROM operation_log \
+             WHERE client_id = ?1 AND undone_at IS NULL ORDER BY id DESC LIMIT 1) \
+         ORDER BY id DESC",
+        OPERATION_COLUMNS
+    ))
+    .bind(client_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    with_snapshots(conn, operations).await
+}
+
+/// The client's earliest undone step, earliest first: undone entries always
+/// form the tail of the log, so this is the last one undone.
+pub async fn next_redoable_operations_in(
+    conn: &mut SqliteConnection,
+    client_id: &str,
+) -> Result<Vec<LoggedOperation>, sqlx::Error> {
+    let operations = sqlx::query_as::<_, Operation>(&format!(
+        "SELECT {} FROM operation_log WHERE client_id = ?1 AND undone_at IS NOT NULL \
+         AND coalesce(group_id, id) = (SELECT coalesce(group_id, id) FROM operation_log \
+             WHERE client_id = ?1 AND undone_at IS NOT NULL ORDER BY id LIMIT 1) \
+         ORDER BY id",
+        OPERATION_COLUMNS
+    ))
+    .bind(client_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    with_snapshots(conn, operations).await
+}
+
+async fn with_snapshots(
+    conn: &mut SqliteConnection,
+    operations: Vec<Operation>,
+) -> Result<Vec<LoggedOperation>, sqlx::Error> {
+    let mut logged = Vec::with_capacity(operations.len());
+    for operation in operations {
+        let (before, after): OperationSnapshots = sqlx::query_as("SELECT before, after FROM operation_log WHERE id = ?")
+            .bind(operation.id)
+            .fetch_one(&mut *conn)
+            .await?;
+        logged.push((operation, before.map(|s| s.0), after.map(|s| s.0)));
+    }
+    Ok(logged)
+}
+
+/// Moves an operation onto (`Some`) or off (`None`) the redo stack.
+pub async fn set_operation_undone_in(
+    conn: &mut SqliteConnection,
+    id: i64,
+    undone_at: Option<DateTime<Utc>>,
+) -> Result<Operation, sqlx::Error> {
+    sqlx::query("UPDATE operation_log SET undone_at = ? WHERE id = ?")
+        .bind(undone_at.map(timestamp))
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query_as::<_, Operation>(&format!("SELECT {} FROM operation_log WHERE id = ?", OPERATION_COLUMNS))
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await
+}
+
+/// Writes a snapshot back and marks the row pending so the reversal is pushed
+/// to Notion. `None` removes the entity: a draft is deleted, anything already
+/// synced is archived instead.
+pub async fn restore_snapshot_in(
+    conn: &mut SqliteConnection,
+    entity: ChangeEntity,
+    id: &str,
+    snapshot: Option<&Snapshot>,
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+    match snapshot {
+        Some(Snapshot::Course { course, reminder_offsets }) => {
+            let offsets = serde_json::to_string(reminder_offsets).map_err(|e| sqlx::Error::Encode(e.into()))?;
+            sqlx::query(
+                "INSERT INTO courses (id, title, room, is_archived, reminder_offsets, updated_at, sync_state, last_synced_at) \
+                 VALUES (?, ?, ?, ?, ?, ?, 'pending', ?) \
+                 ON CONFLICT(id) DO UPDATE SET title = excluded.title, room = excluded.room, \
+                 is_archived = excluded.is_archived, reminder_offsets = excluded.reminder_offsets, \
+                 updated_at = excluded.updated_at, sync_state = 'pending'",
+            )
+            .bind(&course.id)
+            .bind(&course.title)
+            .bind(&course.room)
+            .bind(course.is_archived)
+            .bind(offsets)
+            .bind(timestamp(now))
+            .bind(course.last_synced_at.map(timestamp))
+            .execute(&mut *conn)
+            .await?;
+            set_course_semesters(conn, &course.id, &course.semesters).await?;
+            set_course_meetings(conn, &course.id, &course.meetings).await?;
+            set_course_instructors(conn, &course.id, &course.instructors).await?;
+        }
+        Some(Snapshot::Todo { todo, subtasks, series_date, reminder_offsets, series }) => {
+            let offsets = reminder_offsets
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()
+                .map_err(|e| sqlx::Error::Encode(e.into()))?;
+            sqlx::query(
+                "INSERT INTO todos (id, course_id, title, due_date, due_end, due_timezone, status, priority, notes, \
+                 series_id, series_date, reminder_offsets, completed_at, is_archived, updated_at, sync_state, last_synced_at) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'pending', ?) \
+                 ON CONFLICT(id) DO UPDATE SET course_id = excluded.course_id, title = excluded.title, \
+                 due_date = excluded.due_date, due_end = excluded.due_end, due_timezone = excluded.due_timezone, \
+                 status = excluded.status, priority = excluded.priority, notes = excluded.notes, \
+                 series_id = excluded.series_id, series_date = excluded.series_date, \
+                 reminder_offsets = excluded.reminder_offsets, completed_at = excluded.completed_at, \
+                 is_archived = excluded.is_archived, updated_at = excluded.updated_at, sync_state = 'pending'",
+            )
+            .bind(&todo.id)
+            .bind(&todo.course_id)
+            .bind(&todo.title)
+            .bind(todo.due_date)
+            .bind(todo.due_end)
+            .bind(&todo.due_timezone)
+            .bind(&todo.status)
+            .bind(todo.priority)
+            .bind(&todo.notes)
+            // the series row needs the template first; it is linked below
+            .bind(if series.is_some() { None } else { todo.series_id.as_deref() })
+            .bind(series_date)
+            .bind(offsets)
+            .bind(todo.completed_at.map(timestamp))
+            .bind(todo.is_archived)
+            .bind(timestamp(now))
+            .bind(todo.last_synced_at.map(timestamp))
+            .execute(&mut *conn)
+            .await?;
+            set_todo_tags(conn, &todo.id, &todo.tags).await?;
+            restore_series_in(conn, &todo.id, series.as_deref()).await?;
+            if series.is_some() {
+                sqlx::query("UPDATE todos SET series_id = ? WHERE id = ?")
+                    .bind(&todo.series_id)
+                    .bind(&todo.id)
+                    .execute(&mut *conn)
+                    .await?;
+            }
+
+            sqlx::query("DELETE FROM subtasks WHERE todo_id = ?")
+                .bind(&todo.id)
+                .execute(&mut *conn)
+                .await?;
+            for subtask in subtasks {
+                sqlx::query(
+                    "INSERT INTO subtasks (id, todo_id, title, done, position, updated_at) VALUES (?, ?, ?, ?, ?, ?)",
+                )
+                .bind(&subtask.id)
+                .bind(&todo.id)
+                .bind(&subtask.title)
+                .bind(subtask.done)
+                .bind(subtask.position)
+                .bind(timestamp(now))
+                .execute(&mut *conn)
+                .await?;
+            }
+        }
+        Some(Snapshot::Semester { semester }) => {
+            sqlx::query("UPDATE semesters SET start_date = ?, end_date = ?, is_current = ? WHERE name = ?")
+                .bind(semester.start_date)
+                .bind(semester.end_date)
+                .bind(semester.is_current)
+                .bind(&semester.name)
+                .execute(&mut *conn)
+                .await?;
+        }
+        None => {
+            let deleted = match entity {
+                ChangeEntity::Course => sqlx::query(
+                    "DELETE FROM courses WHERE id = ?1 AND last_synced_at IS NULL \
+                     AND NOT EXISTS (SELECT 1 FROM todos WHERE course_id = ?1 AND last_synced_at IS NOT NULL)",
+                ),
+                ChangeEntity::Todo => sqlx::query("DELETE FROM todos WHERE id = ? AND last_synced_at IS NULL"),
+                // semesters are only created and removed with the courses in them
+                ChangeEntity::Semester => return Ok(()),
+            }
+            .bind(id)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected()
+                > 0;
+            if !deleted {
+                match entity {
+                    ChangeEntity::Course => archive_course_in(conn, id).await?,
+                    ChangeEntity::Todo => {
+                        // like a removed series instance, give the date back to the series
+                        sqlx::query("UPDATE todos SET series_date = NULL WHERE id = ? AND series_id IS NOT NULL")
+                            .bind(id)
+                            .execute(&mut *conn)
+                            .await?;
+                        archive_todo_in(conn, id).await?
+                    }
+                    ChangeEntity::Semester => false,
+                };
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Puts back the series `template_id` is the template of; `None` drops it.
+async fn restore_series_in(
+    conn: &mut SqliteConnection,
+    template_id: &str,
+    series: Option<&TodoSeries>,
+) -> Result<(), sqlx::Error> {
+    let Some(series) = series else {
+        sqlx::query("DELETE FROM todo_series WHERE template_todo_id = ?")
+            .bind(template_id)
+            .execute(&mut *conn)
+            .await?;
+        return Ok(());
+    };
+    sqlx::query(
+        "INSERT INTO todo_series (id, template_todo_id, rule, start_date, generated_until, is_active, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET rule = excluded.rule, start_date = excluded.start_date, \
+         generated_until = excluded.generated_until, is_active = excluded.is_active, updated_at = excluded.updated_at",
+    )
+    .bind(&series.id)
+    .bind(template_id)
+    .bind(&series.rule)
+    .bind(series.start_date)
+    .bind(series.generated_until)
+    .bind(series.is_active)
+    .bind(timestamp(series.created_at))
+    .bind(timestamp(Utc::now()))
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Records the difference between `before` and the current state, and returns the current state.
+pub async fn record_history(
+    db: &SqlitePool,
+    entity: ChangeEntity,
+    id: &str,
+    context: &HistoryContext,
+    before: Option<&Snapshot>,
+) -> Result<Option<Snapshot>, sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    let after = snapshot_in(&mut conn, entity, id).await?;
+    record_history_in(&mut conn, entity, id, context, before, after.as_ref()).await?;
+    Ok(after)
+}
+
+/// Appends the fields that differ between two states to `entity_history`.
+/// Nothing is written when no field changed, except for conflict resolutions,
+/// which are recorded even when Notion had the same values.
+pub async fn record_history_in(
+    conn: &mut SqliteConnection,
+    entity: ChangeEntity,
+    id: &str,
+    context: &HistoryContext,
+    before: Option<&Snapshot>,
+    after: Option<&Snapshot>,
+) -> Result<(), sqlx::Error> {
+    let changes = diff_snapshots(before, after);
+    if changes.is_empty() && context.source != HistorySource::ConflictResolution {
+        return Ok(());
+    }
+    sqlx::query(
+        "INSERT INTO entity_history (entity, entity_id, source, client_id, action, changes, changed_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(entity)
+    .bind(id)
+    .bind(context.source)
+    .bind(&context.client_id)
+    .bind(&context.action)
+    .bind(sqlx::types::Json(&changes))
+    .bind(timestamp(Utc::now()))
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// The entity's history, newest first.
+pub async fn fetch_history(
+    db: &SqlitePool,
+    entity: ChangeEntity,
+    id: &str,
+    limit: u32,
+) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+    sqlx::query_as::<_, HistoryEntry>(
+        "SELECT id, entity, entity_id, source, client_id, action, changes, changed_at FROM entity_history \
+         WHERE entity = ? AND entity_id = ? ORDER BY id DESC LIMIT ?",
+    )
+    .bind(entity)
+    .bind(id)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+/// Rewrites statuses stored as the Notion option name of a representative
+/// (e.g. "完了" for `done`) to the key, using the current mapping and its
+/// `TODO_STATUS_*` overrides. Returns the number of rows changed.
+pub async fn normalize_todo_statuses(db: &SqlitePool, statuses: &StatusMapping) -> Result<u64, sqlx::Error> {
+    let mut changed = 0;
+    for status in [TodoStatus::NotStarted, TodoStatus::InProgress, TodoStatus::Done] {
+        changed += sqlx::query("UPDATE todos SET status = ? WHERE status = ?")
+            .bind(&status)
+            .bind(statuses.to_notion(&status))
+            .execute(db)
+            .await?
+            .rows_affected();
+    }
+    Ok(changed)
+}
+
+/// A value migration 0005 could not read: (entity, entity id, field, original value).
+pub type QuarantinedValue = (String, String, String, String);
+
+/// Values moved out of the way because they could not be decoded.
+pub async fn fetch_quarantined_values(db: &SqlitePool) -> Result<Vec<QuarantinedValue>, sqlx::Error> {
+    sqlx::query_as("SELECT entity, entity_id, field, value FROM quarantined_values ORDER BY id")
+        .fetch_all(db)
+        .await
 }
@@ -17,22 +17,58 @@ pub enum AppError {
     #[error("Conflict: {0}")]
     Conflict(String),
 
+    /// `If-Match` の版が現在の版と違う
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+
+    #[error("Validation failed")]
+    Validation(Vec<FieldError>),
+
     #[error("Internal server error")]
     InternalServerError,
 }
 
+/// 入力検証エラー 1 件 (`422 Unprocessable Entity` の `details`)
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { field: field.into(), message: message.into() }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub error: String,
     pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<FieldError>,
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let (status, body) = self.into_parts();
+        (status, Json(body)).into_response()
+    }
+}
+
+impl AppError {
+    /// ステータスコードとレスポンスの本文 (`POST /batch` の操作ごとの結果にも使う)
+    pub fn into_parts(self) -> (StatusCode, ErrorResponse) {
+        let mut details = Vec::new();
         let (status, error_message) = match self {
             AppError::NotFound => (StatusCode::NOT_FOUND, "Not Found".to_string()),
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            AppError::PreconditionFailed(msg) => (StatusCode::PRECONDITION_FAILED, msg),
+            AppError::Validation(errors) => {
+                details = errors;
+                (StatusCode::UNPROCESSABLE_ENTITY, "Validation failed".to_string())
+            }
             AppError::Database(e) => {
                 error!("database error: {}", e);
                 (
@@ -46,11 +82,12 @@ impl IntoResponse for AppError {
             ),
         };
 
-        let body = Json(ErrorResponse {
+        let body = ErrorResponse {
             error: status.to_string(),
             message: error_message,
-        });
+            details,
+        };
 
-        (status, body).into_response()
+        (status, body)
     }
 }
\ No newline at end of file
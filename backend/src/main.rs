@@ -1,21 +1,23 @@
-mod error;
-mod models;
-mod db;
-mod api;
-mod state;
-mod notion;
-mod services;
-
 use std::net::SocketAddr;
 use std::sync::Arc;
+use chrono_tz::Tz;
 use sqlx::sqlite::SqlitePoolOptions;
 use tracing::{info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::api::router;
-use crate::state::AppState;
-use crate::notion::{NotionClient, NoopNotionClient, NotionConfig, NotionHttpClient};
-use crate::services::SyncScheduler;
+use backend::api::router;
+use backend::models::{PeriodSchedule, StatusMapping};
+use backend::state::AppState;
+use backend::notion::{NotionClient, NoopNotionClient, NotionConfig, NotionHttpClient};
+use backend::services::recurrence::DEFAULT_HORIZON_DAYS;
+use backend::services::reminder::REMINDER_EVENT_CAPACITY;
+use backend::services::{EventBus, ReminderScheduler, ReminderService, RecurrenceScheduler, RecurrenceService, SyncScheduler};
+use tokio::sync::broadcast;
+
+const DEFAULT_TIMEZONE: Tz = chrono_tz::Asia::Tokyo;
+
+/// `Idempotency-Key` のレスポンスを保存しておくデフォルトの秒数: 24 時間
+const DEFAULT_IDEMPOTENCY_TTL_SECS: i64 = 24 * 60 * 60;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -38,6 +40,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     sqlx::migrate!("./migrations").run(&pool).await?;
 
+    for (entity, id, field, value) in backend::db::repository::fetch_quarantined_values(&pool).await? {
+        warn!("Unreadable {} of {} {} was replaced: '{}' (see quarantined_values)", field, entity, id, value);
+    }
+
     let notion_client: Arc<dyn NotionClient> = match NotionConfig::new_from_env() {
         Ok(cfg) => Arc::new(NotionHttpClient::new(cfg)?),
         Err(e) => {
@@ -45,7 +51,59 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             Arc::new(NoopNotionClient)
         }
     };
-    let state = AppState { db: pool.clone(), notion: notion_client.clone() };
+    let statuses = match notion_client.fetch_status_mapping().await {
+        Ok(mapping) => mapping,
+        Err(e) => {
+            warn!("Failed to load Notion status options: {}. Using defaults.", e);
+            StatusMapping::new_from_env()
+        }
+    };
+    match backend::db::repository::normalize_todo_statuses(&pool, &statuses).await {
+        Ok(0) => {}
+        Ok(changed) => info!("Normalized {} todo statuses stored as Notion option names", changed),
+        Err(e) => warn!("Failed to normalize todo statuses: {}", e),
+    }
+    let timezone = std::env::var("APP_TIMEZONE")
+        .ok()
+        .and_then(|tz| match tz.parse::<Tz>() {
+            Ok(tz) => Some(tz),
+            Err(e) => {
+                warn!("Invalid APP_TIMEZONE '{}': {}. Using {}.", tz, e, DEFAULT_TIMEZONE);
+                None
+            }
+        })
+        .unwrap_or(DEFAULT_TIMEZONE);
+    let periods = match std::env::var("PERIOD_TIMES") {
+        Ok(value) if !value.trim().is_empty() => PeriodSchedule::parse(&value).unwrap_or_else(|e| {
+            warn!("Invalid PERIOD_TIMES: {}. Using defaults.", e);
+            PeriodSchedule::default()
+        }),
+        _ => PeriodSchedule::default(),
+    };
+    let recurrence_horizon_days = std::env::var("RECURRENCE_HORIZON_DAYS")
+        .ok()
+        .and_then(|days| days.parse::<i64>().ok())
+        .filter(|days| (1..=366).contains(days))
+        .unwrap_or(DEFAULT_HORIZON_DAYS);
+    let idempotency_ttl_secs = std::env::var("IDEMPOTENCY_TTL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse::<i64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_IDEMPOTENCY_TTL_SECS);
+    let statuses = Arc::new(statuses);
+    let (reminder_events, _) = broadcast::channel(REMINDER_EVENT_CAPACITY);
+    let events = EventBus::default();
+    let state = AppState {
+        db: pool.clone(),
+        notion: notion_client.clone(),
+        statuses: statuses.clone(),
+        timezone,
+        periods: Arc::new(periods),
+        recurrence_horizon_days,
+        reminder_events: reminder_events.clone(),
+        events: events.clone(),
+        idempotency_ttl_secs,
+    };
 
     // Auto-sync scheduler を環境変数で設定可能にする
     let sync_interval_secs = std::env::var("SYNC_INTERVAL_SECS")
@@ -54,11 +112,34 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .unwrap_or(300);
 
     // Auto-sync をバックグラウンドで実行
-    let scheduler = SyncScheduler::new(pool.clone(), notion_client, sync_interval_secs);
+    let scheduler = SyncScheduler::new(pool.clone(), notion_client, sync_interval_secs).with_events(events.clone());
     tokio::spawn(async move {
         scheduler.start().await;
     });
 
+    // 繰り返しの todo の先の回をバックグラウンドで作る
+    let recurrence = RecurrenceScheduler::new(
+        RecurrenceService::new(pool.clone(), timezone, recurrence_horizon_days).with_events(events),
+    );
+    tokio::spawn(async move {
+        recurrence.start().await;
+    });
+
+    // リマインダーを作り、時刻になったものを配信する
+    let reminder_interval_secs = std::env::var("REMINDER_INTERVAL_SECS")
+        .ok()
+        .and_then(|secs| secs.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(30);
+    let reminders = ReminderScheduler::new(
+        ReminderService::new(pool.clone(), statuses, timezone),
+        reminder_events,
+        reminder_interval_secs,
+    );
+    tokio::spawn(async move {
+        reminders.start().await;
+    });
+
     let app = router(state);
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
@@ -0,0 +1,214 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::error::FieldError;
+use super::todo::Todo;
+use super::validation::{check_date, Validate};
+
+/// `range=custom` で指定できる最大日数
+pub const MAX_AGENDA_DAYS: i64 = 62;
+
+/// `GET /agenda` の期間
+///
+/// - `today`: 今日のみ
+/// - `week`: 今日から 7 日間
+/// - `custom`: `from`〜`to` (両端を含む)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgendaRange {
+    #[default]
+    Today,
+    Week,
+    Custom,
+}
+
+/// `GET /agenda` のクエリパラメータ
+///
+/// 日付はすべて `APP_TIMEZONE` の現地日付 (`YYYY-MM-DD`)。
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AgendaQuery {
+    #[serde(default)]
+    pub range: AgendaRange,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub course_id: Option<String>,
+}
+
+impl AgendaQuery {
+    /// 対象期間の最初と最後の日付 (両端を含む)
+    pub fn days(&self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let parse = |value: &Option<String>| {
+            value
+                .as_deref()
+                .and_then(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d").ok())
+                .unwrap_or(today)
+        };
+        match self.range {
+            AgendaRange::Today => (today, today),
+            AgendaRange::Week => (today, today + Duration::days(6)),
+            AgendaRange::Custom => (parse(&self.from), parse(&self.to)),
+        }
+    }
+}
+
+impl Validate for AgendaQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.range != AgendaRange::Custom {
+            for (field, value) in [("from", &self.from), ("to", &self.to)] {
+                if value.is_some() {
+                    errors.push(FieldError::new(field, "only allowed with range=custom"));
+                }
+            }
+            return errors;
+        }
+
+        for (field, value) in [("from", &self.from), ("to", &self.to)] {
+            match value {
+                Some(value) => check_date(&mut errors, field, value),
+                None => errors.push(FieldError::new(field, "required when range=custom")),
+            }
+        }
+        if errors.is_empty() {
+            let (first, last) = self.days(NaiveDate::MIN);
+            if last < first {
+                errors.push(FieldError::new("to", "must not be before from"));
+            } else if (last - first).num_days() >= MAX_AGENDA_DAYS {
+                errors.push(FieldError::new("to", format!("range must be at most {} days", MAX_AGENDA_DAYS)));
+            }
+        }
+        errors
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgendaDay {
+    pub date: NaiveDate,
+    pub todos: Vec<Todo>,
+}
+
+/// `GET /agenda` のレスポンス
+///
+/// `overdue` は期間の終わりを過ぎた未完了の todo (期間に関係なく常に先頭)。
+/// `days` は対象期間の全日付を含み、複数日にわたる todo は該当する各日に現れる。
+#[derive(Debug, Clone, Serialize)]
+pub struct Agenda {
+    pub timezone: String,
+    pub today: NaiveDate,
+    pub overdue: Vec<Todo>,
+    pub days: Vec<AgendaDay>,
+}
+
+impl Agenda {
+    /// 期間内の todo (締め切り順) を現地日付ごとに振り分ける
+    pub fn build(
+        tz: Tz,
+        today: NaiveDate,
+        (first, last): (NaiveDate, NaiveDate),
+        overdue: Vec<Todo>,
+        todos: Vec<Todo>,
+    ) -> Self {
+        let mut days: Vec<AgendaDay> = first
+            .iter_days()
+            .take_while(|date| *date <= last)
+            .map(|date| AgendaDay { date, todos: Vec::new() })
+            .collect();
+
+        for todo in todos {
+            let start = todo.due_date.local_date(&tz).max(first);
+            let end = todo.due_end.unwrap_or(todo.due_date).local_date(&tz).min(last);
+            for date in start.iter_days().take_while(|date| *date <= end) {
+                let index = (date - first).num_days() as usize;
+                days[index].todos.push(todo.clone());
+            }
+        }
+
+        Self {
+            timezone: tz.name().to_string(),
+            today,
+            overdue,
+            days,
+        }
+    }
+}
+
+/// 現地日付の 0:00 を UTC で返す (夏時間で 0:00 が存在しない日はその日の UTC 0:00)
+pub fn local_midnight(tz: &Tz, date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_time(NaiveTime::MIN);
+    tz.from_local_datetime(&midnight)
+        .earliest()
+        .map(|at| at.with_timezone(&Utc))
+        .unwrap_or_else(|| midnight.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DueDate, TodoStatus};
+
+    fn todo(id: &str, due_date: &str, due_end: Option<&str>) -> Todo {
+        Todo {
+            id: id.to_string(),
+            course_id: "course-a".to_string(),
+            title: id.to_string(),
+            due_date: due_date.parse().unwrap(),
+            due_end: due_end.map(|end| end.parse::<DueDate>().unwrap()),
+            due_timezone: None,
+            status: TodoStatus::NotStarted,
+            priority: None,
+            tags: Vec::new(),
+            completed_at: None,
+            is_archived: false,
+            notes: String::new(),
+            progress: Default::default(),
+            series_id: None,
+            version: 0,
+            updated_at: Utc::now(),
+            sync_state: "pending".to_string(),
+            last_synced_at: None,
+            notion_page_id: None,
+        }
+    }
+
+    #[test]
+    fn test_groups_by_local_day() {
+        let tz: Tz = "Asia/Tokyo".parse().unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let week = AgendaQuery { range: AgendaRange::Week, ..Default::default() }.days(today);
+
+        let agenda = Agenda::build(
+            tz,
+            today,
+            week,
+            Vec::new(),
+            vec![
+                todo("project", "2026-10-15", Some("2026-10-21")),
+                // 2026-10-20 08:30 in Tokyo
+                todo("quiz", "2026-10-19T23:30:00Z", None),
+            ],
+        );
+
+        assert_eq!(agenda.days.len(), 7);
+        let ids = |i: usize| agenda.days[i].todos.iter().map(|t| t.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids(0), vec!["project"]);
+        assert_eq!(ids(1), vec!["project", "quiz"]);
+        assert_eq!(ids(2), vec!["project"]);
+        assert!(ids(3).is_empty());
+    }
+
+    #[test]
+    fn test_custom_range_validation() {
+        let query = AgendaQuery {
+            range: AgendaRange::Custom,
+            from: Some("2026-10-25".to_string()),
+            to: Some("2026-10-19".to_string()),
+            ..Default::default()
+        };
+        let fields: Vec<_> = query.validate().into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec!["to"]);
+
+        let query = AgendaQuery { from: Some("2026-10-25".to_string()), ..Default::default() };
+        assert_eq!(query.validate().len(), 1, "from is only allowed with range=custom");
+    }
+}
@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{ErrorResponse, FieldError};
+use super::change::ChangeEntity;
+use super::course::{Course, NewCourseRequest, UpdateCourseRequest};
+use super::todo::{NewTodoRequest, Todo, UpdateTodoRequest};
+use super::validation::Validate;
+
+/// 1 回の `POST /batch` で送れる操作の数
+pub const MAX_BATCH_OPERATIONS: usize = 200;
+
+/// `POST /batch`: オフラインの間に溜まった操作を順番に 1 つのトランザクションで適用する
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchRequest {
+    pub operations: Vec<BatchOperation>,
+}
+
+/// 操作 1 件。`create_*` の `id` はクライアントが作った UUID (省略時はサーバーで作る)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchOperation {
+    CreateCourse {
+        #[serde(default)]
+        id: Option<String>,
+        data: NewCourseRequest,
+    },
+    UpdateCourse { id: String, data: UpdateCourseRequest },
+    ArchiveCourse { id: String },
+    CreateTodo {
+        #[serde(default)]
+        id: Option<String>,
+        data: NewTodoRequest,
+    },
+    UpdateTodo { id: String, data: UpdateTodoRequest },
+    CompleteTodo { id: String },
+    UncompleteTodo { id: String },
+    ArchiveTodo { id: String },
+}
+
+impl BatchOperation {
+    /// `op` の名前 (操作ログの `action`)
+    pub fn name(&self) -> &'static str {
+        match self {
+            BatchOperation::CreateCourse { .. } => "create_course",
+            BatchOperation::UpdateCourse { .. } => "update_course",
+            BatchOperation::ArchiveCourse { .. } => "archive_course",
+            BatchOperation::CreateTodo { .. } => "create_todo",
+            BatchOperation::UpdateTodo { .. } => "update_todo",
+            BatchOperation::CompleteTodo { .. } => "complete_todo",
+            BatchOperation::UncompleteTodo { .. } => "uncomplete_todo",
+            BatchOperation::ArchiveTodo { .. } => "archive_todo",
+        }
+    }
+
+    pub fn entity(&self) -> ChangeEntity {
+        match self {
+            BatchOperation::CreateCourse { .. }
+            | BatchOperation::UpdateCourse { .. }
+            | BatchOperation::ArchiveCourse { .. } => ChangeEntity::Course,
+            _ => ChangeEntity::Todo,
+        }
+    }
+
+    /// 対象の id (サーバーで作る場合は `None`)
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            BatchOperation::CreateCourse { id, .. } | BatchOperation::CreateTodo { id, .. } => id.as_deref(),
+            BatchOperation::UpdateCourse { id, .. }
+            | BatchOperation::ArchiveCourse { id }
+            | BatchOperation::UpdateTodo { id, .. }
+            | BatchOperation::CompleteTodo { id }
+            | BatchOperation::UncompleteTodo { id }
+            | BatchOperation::ArchiveTodo { id } => Some(id),
+        }
+    }
+}
+
+/// 各操作の `data` を検証する。フィールド名は `operations[2].data.title` の形
+impl Validate for BatchRequest {
+    fn normalize(&mut self) {
+        for operation in &mut self.operations {
+            match operation {
+                BatchOperation::CreateCourse { id, data } => {
+                    normalize_client_id(id);
+                    data.normalize();
+                }
+                BatchOperation::UpdateCourse { data, .. } => data.normalize(),
+                BatchOperation::CreateTodo { id, data } => {
+                    normalize_client_id(id);
+                    data.normalize();
+                }
+                BatchOperation::UpdateTodo { data, .. } => data.normalize(),
+                _ => {}
+            }
+        }
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if self.operations.is_empty() {
+            errors.push(FieldError::new("operations", "must not be empty"));
+        }
+        if self.operations.len() > MAX_BATCH_OPERATIONS {
+            errors.push(FieldError::new(
+                "operations",
+                format!("must have at most {} entries", MAX_BATCH_OPERATIONS),
+            ));
+        }
+        for (index, operation) in self.operations.iter().enumerate() {
+            let (client_id, data_errors) = match operation {
+                BatchOperation::CreateCourse { id, data } => (id.as_deref(), data.validate()),
+                BatchOperation::UpdateCourse { data, .. } => (None, data.validate()),
+                BatchOperation::CreateTodo { id, data } => (id.as_deref(), data.validate()),
+                BatchOperation::UpdateTodo { data, .. } => (None, data.validate()),
+                _ => (None, Vec::new()),
+            };
+            if let Some(id) = client_id
+                && Uuid::parse_str(id).is_err()
+            {
+                errors.push(FieldError::new(format!("operations[{}].id", index), "must be a UUID"));
+            }
+            errors.extend(data_errors.into_iter().map(|e| {
+                FieldError::new(format!("operations[{}].data.{}", index, e.field), e.message)
+            }));
+        }
+        errors
+    }
+}
+
+/// UUID は小文字のハイフン区切りに揃える (Notion のページ id と同じ形)
+fn normalize_client_id(id: &mut Option<String>) {
+    if let Some(value) = id {
+        *value = match Uuid::parse_str(value.trim()) {
+            Ok(uuid) => uuid.hyphenated().to_string(),
+            Err(_) => value.trim().to_string(),
+        };
+    }
+}
+
+/// `POST /batch` の結果
+///
+/// `applied` が `false` なら、`results` の最後の操作が失敗してすべて取り消された (以降の操作は実行していない)。
+#[derive(Debug, Serialize)]
+pub struct BatchResponse {
+    pub applied: bool,
+    pub results: Vec<BatchResult>,
+}
+
+/// 操作 1 件の結果。`status` は個別の API を呼んだ場合のステータスコード
+#[derive(Debug, Serialize)]
+pub struct BatchResult {
+    pub index: usize,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub course: Option<Course>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo: Option<Todo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorResponse>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operations_are_tagged_and_validated_with_indexes() {
+        let mut req: BatchRequest = serde_json::from_value(serde_json::json!({
+            "operations": [
+                { "op": "create_todo", "id": "8F1C2D3E-0000-4000-8000-000000000001",
+                  "data": { "course_id": "c1", "title": " Lab ", "due_date": "2026-10-20" } },
+                { "op": "update_todo", "id": "t1", "data": { "title": "  " } },
+                { "op": "create_course", "id": "not-a-uuid", "data": { "title": "Optics", "semesters": ["2A1"] } },
+                { "op": "archive_todo", "id": "t2" }
+            ]
+        }))
+        .unwrap();
+        req.normalize();
+
+        assert_eq!(req.operations[0].id(), Some("8f1c2d3e-0000-4000-8000-000000000001"));
+        let fields: Vec<_> = req.validate().into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec!["operations[1].data.title", "operations[2].id"]);
+        assert_eq!(req.operations[3].id(), Some("t2"));
+    }
+}
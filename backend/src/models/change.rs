@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+
+use super::course::Course;
+use super::todo::Todo;
+
+/// `GET /changes` のデフォルト件数
+pub const DEFAULT_CHANGES_LIMIT: u32 = 500;
+
+/// `GET /changes` の最大件数
+pub const MAX_CHANGES_LIMIT: u32 = 1000;
+
+/// `GET /changes?since=<cursor>&limit=500`
+///
+/// `since` は前回の応答の `cursor` (初回は 0 で全件)。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChangesQuery {
+    #[serde(default)]
+    pub since: i64,
+    pub limit: Option<u32>,
+}
+
+/// `since` 以降に変更されたコースと todo
+///
+/// 変更の古い順に最大 `limit` 件。`has_more` なら `cursor` で続きを取得する。
+/// 同じエンティティが何度変更されても最新の状態が 1 回だけ含まれる。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Changes {
+    /// 次の `since`
+    pub cursor: i64,
+    pub has_more: bool,
+    pub courses: Vec<Course>,
+    pub todos: Vec<Todo>,
+    /// アーカイブ・削除されたもの (クライアントのキャッシュから外す)
+    pub removed: Vec<RemovedEntity>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum ChangeEntity {
+    Course,
+    Todo,
+    /// 操作ログと履歴のみ (`change_log` には載らない。id は学期名)
+    Semester,
+}
+
+impl ChangeEntity {
+    /// `change_log.entity` などの値
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeEntity::Course => "course",
+            ChangeEntity::Todo => "todo",
+            ChangeEntity::Semester => "semester",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemovalReason {
+    Archived,
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemovedEntity {
+    pub entity: ChangeEntity,
+    pub id: String,
+    pub reason: RemovalReason,
+}
@@ -1,27 +1,180 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::error::FieldError;
+use super::meeting::{check_meetings, normalize_meetings, CourseMeeting};
+use super::semester::check_semesters;
+use super::validation::{check_select_names, check_title, normalize_select_names, Validate};
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Course {
     pub id: String,
     pub title: String,
-    pub semester: String,
-    pub day_of_week: String,
-    pub period: i32,
+    /// 所属する学期名 (Notion の "Semester" マルチセレクトの順)
+    #[sqlx(json)]
+    pub semesters: Vec<String>,
+    /// 授業枠 (Notion の "Meetings" の順)
+    #[sqlx(json)]
+    pub meetings: Vec<CourseMeeting>,
     pub room: Option<String>,
-    pub instructor: Option<String>,
+    /// 教員名 (Notion の "Instructor" マルチセレクトの順)
+    #[sqlx(json)]
+    pub instructors: Vec<String>,
+    /// 変更のたびに増える版 (`ETag`)。Notion には同期しない
+    #[serde(default)]
+    pub version: i64,
     pub is_archived: bool,
-    pub updated_at: String,
+    pub updated_at: DateTime<Utc>,
     pub sync_state: String,
-    pub last_synced_at: Option<String>,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    /// Notion のページ id (ローカルで作った授業のページ)。`None` なら `id` がページ id
+    #[serde(skip)]
+    pub notion_page_id: Option<String>,
+}
+
+impl Course {
+    /// Notion のページ id
+    pub fn page_id(&self) -> &str {
+        self.notion_page_id.as_deref().unwrap_or(&self.id)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewCourseRequest {
     pub title: String,
-    pub semester: String,
-    pub day_of_week: String,
-    pub period: i32,
+    pub semesters: Vec<String>,
+    #[serde(default)]
+    pub meetings: Vec<CourseMeeting>,
+    pub room: Option<String>,
+    #[serde(default)]
+    pub instructors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateCourseRequest {
+    pub title: Option<String>,
+    pub semesters: Option<Vec<String>>,
+    /// 指定した場合は授業枠をすべて置き換える
+    pub meetings: Option<Vec<CourseMeeting>>,
     pub room: Option<String>,
+    /// 指定した場合は教員をすべて置き換える
+    pub instructors: Option<Vec<String>>,
+}
+
+/// `GET /courses` のクエリパラメータ
+///
+/// - `semester`: 学期名 (`current` で現在の学期のみ)
+/// - `instructor`: 教員名 (完全一致)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CourseListQuery {
+    pub semester: Option<String>,
     pub instructor: Option<String>,
 }
+
+/// 曜日 (Notion の "Day" セレクトの値と同じ表記)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Weekday::Mon => "Mon",
+            Weekday::Tue => "Tue",
+            Weekday::Wed => "Wed",
+            Weekday::Thu => "Thu",
+            Weekday::Fri => "Fri",
+            Weekday::Sat => "Sat",
+            Weekday::Sun => "Sun",
+        }
+    }
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(day: chrono::Weekday) -> Self {
+        Weekday::ALL[day.num_days_from_monday() as usize]
+    }
+}
+
+impl FromStr for Weekday {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Weekday::ALL
+            .into_iter()
+            .find(|d| d.as_str() == s)
+            .ok_or_else(|| format!("'{}' is not a weekday (Mon, Tue, Wed, Thu, Fri, Sat, Sun)", s))
+    }
+}
+
+impl Validate for NewCourseRequest {
+    fn normalize(&mut self) {
+        self.title = self.title.trim().to_string();
+        normalize_select_names(&mut self.semesters);
+        normalize_meetings(&mut self.meetings);
+        normalize_select_names(&mut self.instructors);
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_title(&mut errors, "title", &self.title);
+        check_semesters(&mut errors, "semesters", &self.semesters);
+        check_meetings(&mut errors, "meetings", &self.meetings);
+        check_select_names(&mut errors, "instructors", &self.instructors);
+        errors
+    }
+}
+
+impl Validate for UpdateCourseRequest {
+    fn normalize(&mut self) {
+        if let Some(title) = &mut self.title {
+            *title = title.trim().to_string();
+        }
+        if let Some(semesters) = &mut self.semesters {
+            normalize_select_names(semesters);
+        }
+        if let Some(meetings) = &mut self.meetings {
+            normalize_meetings(meetings);
+        }
+        if let Some(instructors) = &mut self.instructors {
+            normalize_select_names(instructors);
+        }
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(title) = &self.title {
+            check_title(&mut errors, "title", title);
+        }
+        if let Some(semesters) = &self.semesters {
+            check_semesters(&mut errors, "semesters", semesters);
+        }
+        if let Some(meetings) = &self.meetings {
+            check_meetings(&mut errors, "meetings", meetings);
+        }
+        if let Some(instructors) = &self.instructors {
+            check_select_names(&mut errors, "instructors", instructors);
+        }
+        errors
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt;
+use std::str::FromStr;
+
+use std::cmp::Ordering;
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::sqlite::{SqliteTypeInfo, SqliteValueRef};
+use sqlx::{Decode, Encode, Sqlite, Type};
+
+/// 締め切り
+///
+/// Notion の Date プロパティと同様に、日付のみ (終日) と時刻付きを区別する。
+/// 時刻付きは UTC に正規化して保持する。文字列表現は API と DB で共通で、
+/// 終日は `2026-10-20`、時刻付きは `2026-10-20T14:59:00Z`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DueDate {
+    AllDay(NaiveDate),
+    Timed(DateTime<Utc>),
+}
+
+impl DueDate {
+    /// 締め切りの日付 (時刻付きは UTC の日付)
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            DueDate::AllDay(date) => *date,
+            DueDate::Timed(at) => at.date_naive(),
+        }
+    }
+
+    /// 指定タイムゾーンでの日付 (終日はそのまま)
+    pub fn local_date(&self, tz: &Tz) -> NaiveDate {
+        match self {
+            DueDate::AllDay(date) => *date,
+            DueDate::Timed(at) => at.with_timezone(tz).date_naive(),
+        }
+    }
+
+    /// `days` 日ずらす。時刻付きは指定タイムゾーンでの時刻を保つ
+    pub fn shift_days(&self, days: i64, tz: &Tz) -> DueDate {
+        match self {
+            DueDate::AllDay(date) => DueDate::AllDay(*date + Duration::days(days)),
+            DueDate::Timed(at) => {
+                let local = at.with_timezone(tz).naive_local() + Duration::days(days);
+                tz.from_local_datetime(&local)
+                    .earliest()
+                    .map(|at| DueDate::Timed(at.with_timezone(&Utc)))
+                    .unwrap_or_else(|| DueDate::Timed(*at + Duration::days(days)))
+            }
+        }
+    }
+
+    /// Notion の Date の `start` / `end` を読む
+    ///
+    /// `time_zone` が指定されている場合、Notion はオフセット無しの現地時刻を返すので
+    /// そのタイムゾーンで解釈して UTC に変換する。
+    pub fn from_notion(value: &str, time_zone: Option<&str>) -> Option<DueDate> {
+        if let Ok(due) = value.parse::<DueDate>() {
+            return Some(due);
+        }
+        let tz = time_zone?.parse::<Tz>().ok()?;
+        let local = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+        tz.from_local_datetime(&local)
+            .earliest()
+            .map(|at| DueDate::Timed(at.with_timezone(&Utc)))
+    }
+
+    /// Notion に送る `start` / `end` の文字列
+    ///
+    /// `time_zone` を併せて送る場合はオフセット無しの現地時刻にする (Notion の制約)。
+    pub fn to_notion(&self, time_zone: Option<&str>) -> String {
+        match (self, time_zone.and_then(|tz| tz.parse::<Tz>().ok())) {
+            (DueDate::Timed(at), Some(tz)) => {
+                at.with_timezone(&tz).format("%Y-%m-%dT%H:%M:%S").to_string()
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    /// 並び順のキー。終日はその日の時刻付きより前に来る (DB の文字列比較と同じ順序)
+    fn sort_key(&self) -> (NaiveDate, Option<NaiveTime>) {
+        match self {
+            DueDate::AllDay(date) => (*date, None),
+            DueDate::Timed(at) => (at.date_naive(), Some(at.time())),
+        }
+    }
+}
+
+impl PartialOrd for DueDate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DueDate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl fmt::Display for DueDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DueDate::AllDay(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            DueDate::Timed(at) => f.write_str(&at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        }
+    }
+}
+
+impl FromStr for DueDate {
+    type Err = String;
+
+    /// `YYYY-MM-DD` またはタイムゾーン付きの RFC 3339 日時
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return Ok(DueDate::AllDay(date));
+        }
+        DateTime::parse_from_rfc3339(s)
+            .map(|at| DueDate::Timed(at.with_timezone(&Utc)))
+            .map_err(|_| format!("invalid due date: {}", s))
+    }
+}
+
+impl Serialize for DueDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for DueDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl Type<Sqlite> for DueDate {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <String as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for DueDate {
+    fn encode_by_ref(
+        &self,
+        buf: &mut <Sqlite as sqlx::Database>::ArgumentBuffer<'q>,
+    ) -> Result<IsNull, BoxDynError> {
+        <String as Encode<'q, Sqlite>>::encode(self.to_string(), buf)
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for DueDate {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        let s = <&str as Decode<'r, Sqlite>>::decode(value)?;
+        Ok(s.parse()?)
+    }
+}
+
+/// Notion の日時文字列 (日付のみも可) を UTC の日時に変換する。日付のみは 00:00 UTC。
+pub fn parse_notion_datetime(value: &str) -> Option<DateTime<Utc>> {
+    match value.parse::<DueDate>().ok()? {
+        DueDate::AllDay(date) => date.and_hms_opt(0, 0, 0).map(|dt| dt.and_utc()),
+        DueDate::Timed(at) => Some(at),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_due_date_round_trip() {
+        let all_day: DueDate = "2026-10-20".parse().unwrap();
+        assert_eq!(all_day, DueDate::AllDay(NaiveDate::from_ymd_opt(2026, 10, 20).unwrap()));
+        assert_eq!(all_day.to_string(), "2026-10-20");
+
+        let timed: DueDate = "2026-10-20T23:59:00.000+09:00".parse().unwrap();
+        assert_eq!(timed.to_string(), "2026-10-20T14:59:00Z");
+        assert_eq!(timed.to_string().parse::<DueDate>().unwrap(), timed);
+
+        assert!("tomorrow".parse::<DueDate>().is_err());
+    }
+
+    #[test]
+    fn test_parse_notion_datetime() {
+        assert_eq!(
+            parse_notion_datetime("2026-01-02").unwrap().to_rfc3339(),
+            "2026-01-02T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_notion_datetime("2026-01-02T12:33:00.000Z").unwrap().to_rfc3339(),
+            "2026-01-02T12:33:00+00:00"
+        );
+        assert!(parse_notion_datetime("").is_none());
+    }
+
+    #[test]
+    fn test_notion_time_zone_round_trip() {
+        let due = DueDate::from_notion("2026-10-20T10:00:00.000", Some("Asia/Tokyo")).unwrap();
+        assert_eq!(due.to_string(), "2026-10-20T01:00:00Z");
+        assert_eq!(due.to_notion(Some("Asia/Tokyo")), "2026-10-20T10:00:00");
+        assert_eq!(due.to_notion(None), "2026-10-20T01:00:00Z");
+        assert!(DueDate::from_notion("2026-10-20T10:00:00.000", None).is_none());
+
+        let day: DueDate = "2026-10-20".parse().unwrap();
+        assert_eq!(day.to_notion(Some("Asia/Tokyo")), "2026-10-20");
+        assert!(day < due, "an all-day date sorts before times on the same day");
+    }
+}
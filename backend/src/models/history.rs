@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::FromRow;
+
+use crate::error::FieldError;
+use super::change::ChangeEntity;
+use super::operation::Snapshot;
+use super::validation::Validate;
+
+/// `GET /todos/{id}/history` のデフォルト件数
+pub const DEFAULT_HISTORY_LIMIT: u32 = 100;
+
+/// `GET /todos/{id}/history` の最大件数
+pub const MAX_HISTORY_LIMIT: u32 = 1000;
+
+/// 履歴に含めないフィールド (変更のたびに変わるもの、サブタスクや所属コースから集計するもの)
+const IGNORED_FIELDS: [&str; 4] = ["version", "updated_at", "progress", "course_count"];
+
+/// 変更がどこから来たか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum HistorySource {
+    /// REST API (`client_id` は `X-Client-Id`)
+    Api,
+    /// Notion からの取り込み
+    SyncPull,
+    /// Notion への送信 (`sync_state` と `last_synced_at` が変わる)
+    SyncPush,
+    /// Pull でローカルの変更を残した。`from` が取り込まなかった Notion の値、`to` が残したローカルの値
+    ConflictResolution,
+    /// 繰り返しの系列から作った回
+    Recurrence,
+}
+
+/// フィールド 1 つの変更
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub from: Value,
+    pub to: Value,
+}
+
+/// 変更の記録 1 件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub entity: ChangeEntity,
+    pub entity_id: String,
+    pub source: HistorySource,
+    pub client_id: Option<String>,
+    /// `update_todo`, `pull`, `push`, `local_pending` など
+    pub action: String,
+    #[sqlx(json)]
+    pub changes: BTreeMap<String, FieldChange>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// 履歴を書き込むときの変更元
+#[derive(Debug, Clone)]
+pub struct HistoryContext {
+    pub source: HistorySource,
+    pub client_id: Option<String>,
+    pub action: String,
+}
+
+impl HistoryContext {
+    pub fn new(source: HistorySource, action: impl Into<String>) -> Self {
+        Self { source, client_id: None, action: action.into() }
+    }
+
+    pub fn api(client_id: &str, action: impl Into<String>) -> Self {
+        Self { source: HistorySource::Api, client_id: Some(client_id.to_string()), action: action.into() }
+    }
+}
+
+/// `GET /todos/{id}/history?limit=100` (新しい順)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryQuery {
+    pub limit: Option<u32>,
+}
+
+impl Validate for HistoryQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(limit) = self.limit
+            && !(1..=MAX_HISTORY_LIMIT).contains(&limit)
+        {
+            errors.push(FieldError::new("limit", format!("must be between 1 and {}", MAX_HISTORY_LIMIT)));
+        }
+        errors
+    }
+}
+
+/// 2 つの状態の間で変わったフィールド。無い側は `null`
+pub fn diff_snapshots(before: Option<&Snapshot>, after: Option<&Snapshot>) -> BTreeMap<String, FieldChange> {
+    let before = fields(before);
+    let after = fields(after);
+    let mut changes = BTreeMap::new();
+    for name in before.keys().chain(after.keys()) {
+        if IGNORED_FIELDS.contains(&name.as_str()) || changes.contains_key(name) {
+            continue;
+        }
+        let from = before.get(name).cloned().unwrap_or(Value::Null);
+        let to = after.get(name).cloned().unwrap_or(Value::Null);
+        if from != to {
+            changes.insert(name.clone(), FieldChange { from, to });
+        }
+    }
+    changes
+}
+
+/// コース・todo・学期のフィールドとリマインダー。todo はサブタスクの一覧を `subtasks` に入れる (`updated_at` は除く)
+fn fields(snapshot: Option<&Snapshot>) -> Map<String, Value> {
+    let value = match snapshot {
+        Some(Snapshot::Course { course, reminder_offsets }) => serde_json::to_value(course).map(|mut value| {
+            value["reminder_offsets"] = serde_json::json!(reminder_offsets);
+            value
+        }),
+        Some(Snapshot::Todo { todo, subtasks, series_date, reminder_offsets, .. }) => serde_json::to_value(todo).map(|mut value| {
+            let items = subtasks
+                .iter()
+                .map(|s| serde_json::json!({ "id": s.id, "title": s.title, "done": s.done, "position": s.position }))
+                .collect();
+            value["subtasks"] = Value::Array(items);
+            value["series_date"] = serde_json::json!(series_date);
+            value["reminder_offsets"] = serde_json::json!(reminder_offsets);
+            value
+        }),
+        Some(Snapshot::Semester { semester }) => serde_json::to_value(semester),
+        None => return Map::new(),
+    };
+    match value {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Subtask, SubtaskProgress, Todo, TodoStatus};
+
+    fn todo(status: TodoStatus, version: i64) -> Todo {
+        Todo {
+            id: "t1".to_string(),
+            course_id: "c1".to_string(),
+            title: "Report".to_string(),
+            due_date: "2026-10-20".parse().unwrap(),
+            due_end: None,
+            due_timezone: None,
+            status,
+            priority: None,
+            tags: Vec::new(),
+            notes: String::new(),
+            progress: SubtaskProgress::default(),
+            series_id: None,
+            version,
+            completed_at: None,
+            is_archived: false,
+            updated_at: Utc::now(),
+            sync_state: "synced".to_string(),
+            last_synced_at: None,
+            notion_page_id: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_lists_changed_fields_only() {
+        let step = Subtask {
+            id: "s1".to_string(),
+            todo_id: "t1".to_string(),
+            title: "Outline".to_string(),
+            done: false,
+            position: 0,
+            updated_at: Utc::now(),
+        };
+        let before = Snapshot::todo(todo(TodoStatus::NotStarted, 1), vec![step.clone()]);
+        let after = Snapshot::todo(todo(TodoStatus::Done, 5), vec![Subtask { done: true, updated_at: Utc::now(), ..step }]);
+
+        let changes = diff_snapshots(Some(&before), Some(&after));
+        assert_eq!(changes.keys().collect::<Vec<_>>(), vec!["status", "subtasks"]);
+        assert_eq!(changes["status"], FieldChange { from: "not_started".into(), to: "done".into() });
+
+        let created = diff_snapshots(None, Some(&after));
+        assert_eq!(created["title"], FieldChange { from: Value::Null, to: "Report".into() });
+    }
+}
@@ -0,0 +1,25 @@
+/// `Idempotency-Key` の最大長
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// 保存した最初のレスポンス
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// 作成・更新のレスポンスの `ETag` (再送にも付ける)
+    pub etag: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// `Idempotency-Key` 付きのリクエストを受け付けたときの状態
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyState {
+    /// 初めてのキー。処理してレスポンスを保存する
+    New,
+    /// 同じリクエストの再送。保存したレスポンスを返す
+    Replay(StoredResponse),
+    /// 最初のリクエストをまだ処理中
+    InProgress,
+    /// 同じキーで別のメソッド・パス・本文が送られた
+    Mismatch,
+}
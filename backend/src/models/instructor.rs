@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// `GET /instructors` の 1 件分: 教員と担当コース (アーカイブ済みを除く、タイトル順)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Instructor {
+    pub name: String,
+    #[sqlx(json)]
+    pub courses: Vec<InstructorCourse>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstructorCourse {
+    pub id: String,
+    pub title: String,
+}
+
+/// `GET /instructors` のクエリパラメータ。`semester=current` で現在の学期のコースのみ
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct InstructorQuery {
+    pub semester: Option<String>,
+}
@@ -0,0 +1,200 @@
+use std::fmt;
+
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+
+use crate::error::FieldError;
+use super::course::Weekday;
+use super::validation::{check_period, MAX_PERIOD};
+
+/// コースの授業枠 (週 1 コマ分)
+///
+/// 時限 (`period`) か開始・終了時刻 (`start_time` / `end_time`, `HH:MM`) のどちらかで指定する。
+/// 2 コマ続きの授業は時限ごとに 1 件ずつ持つ。`room` が無い枠はコースの `room` を使う。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CourseMeeting {
+    pub day_of_week: Weekday,
+    #[serde(default)]
+    pub period: Option<i32>,
+    #[serde(default, with = "hh_mm")]
+    pub start_time: Option<NaiveTime>,
+    #[serde(default, with = "hh_mm")]
+    pub end_time: Option<NaiveTime>,
+    #[serde(default)]
+    pub room: Option<String>,
+}
+
+impl CourseMeeting {
+    pub fn period(day_of_week: Weekday, period: i32) -> Self {
+        Self { day_of_week, period: Some(period), start_time: None, end_time: None, room: None }
+    }
+}
+
+/// Notion の "Meetings" テキストでの表記 (`Mon 2`, `Thu 13:00-14:30 @ B204`)
+impl fmt::Display for CourseMeeting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.day_of_week.as_str())?;
+        if let Some(period) = self.period {
+            write!(f, " {}", period)?;
+        }
+        if let (Some(start), Some(end)) = (self.start_time, self.end_time) {
+            write!(f, " {}-{}", start.format("%H:%M"), end.format("%H:%M"))?;
+        }
+        if let Some(room) = &self.room {
+            write!(f, " @ {}", room)?;
+        }
+        Ok(())
+    }
+}
+
+/// Notion の "Meetings" テキストを読む
+///
+/// 枠は `;` か改行で区切る。`Mon 2-3` のような時限の範囲は時限ごとの枠に展開する。
+/// 逆順の範囲 (`Mon 3-2`) や 1〜`MAX_PERIOD` 外の時限はエラーにする。
+pub fn parse_meetings(text: &str) -> Result<Vec<CourseMeeting>, String> {
+    let mut meetings = Vec::new();
+    for slot in text.split([';', '\n']).map(str::trim).filter(|s| !s.is_empty()) {
+        let (slot_time, room) = match slot.split_once('@') {
+            Some((slot_time, room)) => (slot_time.trim(), Some(room.trim().to_string()).filter(|r| !r.is_empty())),
+            None => (slot, None),
+        };
+        let (day, time) = slot_time
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("'{}' needs a day and a period or time", slot))?;
+        let day_of_week = day.parse::<Weekday>()?;
+        let (first, last) = time.trim().split_once('-').unwrap_or((time, time));
+        let (first, last) = (first.trim(), last.trim());
+
+        if first.contains(':') {
+            let parse = |t: &str| {
+                NaiveTime::parse_from_str(t, "%H:%M").map_err(|_| format!("'{}' is not HH:MM-HH:MM", time.trim()))
+            };
+            let (start, end) = (parse(first)?, parse(last)?);
+            if end <= start {
+                return Err(format!("'{}' ends before it starts", time.trim()));
+            }
+            meetings.push(CourseMeeting {
+                day_of_week,
+                period: None,
+                start_time: Some(start),
+                end_time: Some(end),
+                room,
+            });
+        } else {
+            let parse = |p: &str| {
+                p.parse::<i32>()
+                    .ok()
+                    .filter(|period| (1..=MAX_PERIOD).contains(period))
+                    .ok_or_else(|| format!("'{}' is not a period between 1 and {}", p, MAX_PERIOD))
+            };
+            let (first, last) = (parse(first)?, parse(last)?);
+            if last < first {
+                return Err(format!("'{}' ends before it starts", time.trim()));
+            }
+            for period in first..=last {
+                meetings.push(CourseMeeting { room: room.clone(), ..CourseMeeting::period(day_of_week, period) });
+            }
+        }
+    }
+    Ok(meetings)
+}
+
+pub fn format_meetings(meetings: &[CourseMeeting]) -> String {
+    meetings.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+}
+
+/// 教室名の前後の空白を除去し (空なら削除)、同じ枠の重複を取り除く
+pub fn normalize_meetings(meetings: &mut Vec<CourseMeeting>) {
+    let mut seen: Vec<CourseMeeting> = Vec::new();
+    for mut meeting in meetings.drain(..) {
+        meeting.room = meeting.room.map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+        if !seen.contains(&meeting) {
+            seen.push(meeting);
+        }
+    }
+    *meetings = seen;
+}
+
+/// 各枠が時限か開始・終了時刻のどちらか一方を持つこと
+pub fn check_meetings(errors: &mut Vec<FieldError>, field: &str, meetings: &[CourseMeeting]) {
+    for (i, meeting) in meetings.iter().enumerate() {
+        let field = format!("{}[{}]", field, i);
+        match (meeting.period, meeting.start_time, meeting.end_time) {
+            (Some(period), None, None) => check_period(errors, &format!("{}.period", field), period),
+            (None, Some(start), Some(end)) => {
+                if end <= start {
+                    errors.push(FieldError::new(format!("{}.end_time", field), "must be after start_time"));
+                }
+            }
+            _ => errors.push(FieldError::new(field, "needs either period or both start_time and end_time")),
+        }
+    }
+}
+
+/// `Option<NaiveTime>` を `HH:MM` で読み書きする
+mod hh_mm {
+    use chrono::NaiveTime;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &Option<NaiveTime>, serializer: S) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(time) => serializer.collect_str(&time.format("%H:%M")),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveTime>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| {
+                NaiveTime::parse_from_str(&value, "%H:%M")
+                    .map_err(|_| de::Error::custom(format!("'{}' is not a time (HH:MM)", value)))
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_meetings() {
+        let meetings = parse_meetings("Mon 2-3 @ A101; Thu 3\nFri 13:00-14:30").unwrap();
+        assert_eq!(meetings.len(), 4);
+        assert_eq!(meetings[1], CourseMeeting { room: Some("A101".to_string()), ..CourseMeeting::period(Weekday::Mon, 3) });
+        assert_eq!(meetings[3].start_time, NaiveTime::from_hms_opt(13, 0, 0));
+        assert_eq!(
+            format_meetings(&meetings),
+            "Mon 2 @ A101; Mon 3 @ A101; Thu 3; Fri 13:00-14:30"
+        );
+        assert_eq!(parse_meetings(&format_meetings(&meetings)).unwrap(), meetings);
+
+        assert!(parse_meetings("Monday 2").is_err());
+        assert!(parse_meetings("Mon").is_err());
+        assert!(parse_meetings("").unwrap().is_empty());
+        assert!(parse_meetings("Mon 3-2").is_err());
+        assert!(parse_meetings("Mon 0").is_err());
+        assert!(parse_meetings(&format!("Mon 2-{}", MAX_PERIOD + 1)).is_err());
+        assert!(parse_meetings("Fri 14:30-13:00").is_err());
+    }
+
+    #[test]
+    fn test_check_meetings() {
+        let mut evening = CourseMeeting::period(Weekday::Tue, 8);
+        evening.start_time = NaiveTime::from_hms_opt(18, 0, 0);
+        let meetings = vec![
+            CourseMeeting::period(Weekday::Mon, 8),
+            evening,
+            CourseMeeting {
+                period: None,
+                start_time: NaiveTime::from_hms_opt(14, 0, 0),
+                end_time: NaiveTime::from_hms_opt(13, 0, 0),
+                ..CourseMeeting::period(Weekday::Fri, 1)
+            },
+        ];
+        let mut errors = Vec::new();
+        check_meetings(&mut errors, "meetings", &meetings);
+        let fields: Vec<_> = errors.into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec!["meetings[0].period", "meetings[1]", "meetings[2].end_time"]);
+    }
+}
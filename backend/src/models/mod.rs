@@ -1,5 +1,46 @@
+pub mod agenda;
+pub mod batch;
+pub mod change;
 pub mod course;
+pub mod due_date;
+pub mod history;
+pub mod idempotency;
+pub mod instructor;
+pub mod meeting;
+pub mod operation;
+pub mod priority;
+pub mod recurrence;
+pub mod reminder;
+pub mod search;
+pub mod semester;
+pub mod status;
+pub mod subtask;
+pub mod tag;
+pub mod timetable;
 pub mod todo;
+pub mod validation;
 
-pub use course::{Course, NewCourseRequest};
-pub use todo::{Todo, NewTodoRequest, UpdateTodoRequest};
+pub use agenda::{Agenda, AgendaDay, AgendaQuery, AgendaRange};
+pub use batch::{BatchOperation, BatchRequest, BatchResponse, BatchResult};
+pub use change::{ChangeEntity, Changes, ChangesQuery, RemovalReason, RemovedEntity};
+pub use course::{Course, CourseListQuery, NewCourseRequest, UpdateCourseRequest, Weekday};
+pub use due_date::{parse_notion_datetime, DueDate};
+pub use history::{FieldChange, HistoryContext, HistoryEntry, HistoryQuery, HistorySource};
+pub use idempotency::{IdempotencyState, StoredResponse};
+pub use instructor::{Instructor, InstructorCourse, InstructorQuery};
+pub use meeting::CourseMeeting;
+pub use operation::{Operation, Snapshot, UndoResponse};
+pub use priority::Priority;
+pub use recurrence::{NewSeriesRequest, RecurrenceRule, TodoSeries, UpdateSeriesRequest};
+pub use reminder::{PlannedReminder, Reminder, ReminderEvent, ReminderSettings, UpdateReminderSettingsRequest};
+pub use search::{SearchHit, SearchQuery};
+pub use semester::{Semester, UpdateSemesterRequest, CURRENT_SEMESTER};
+pub use status::{StatusGroup, StatusMapping, StatusOption, TodoStatus};
+pub use subtask::{NewSubtaskRequest, Subtask, SubtaskItem, SubtaskProgress, UpdateSubtaskRequest};
+pub use tag::Tag;
+pub use timetable::{
+    PeriodSchedule, PeriodTime, Timetable, TimetableCell, TimetableConflict, TimetableDay, TimetableEntry,
+    TimetableQuery,
+};
+pub use todo::{Todo, NewTodoRequest, UpdateTodoRequest, TodoListQuery, TodoSortField, SortOrder};
+pub use validation::Validate;
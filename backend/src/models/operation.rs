@@ -0,0 +1,164 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::change::ChangeEntity;
+use super::course::Course;
+use super::recurrence::TodoSeries;
+use super::semester::Semester;
+use super::subtask::Subtask;
+use super::todo::Todo;
+
+/// `X-Client-Id` が無いリクエストの操作をまとめる端末 id
+pub const DEFAULT_CLIENT_ID: &str = "default";
+
+/// `X-Client-Id` の最大長
+pub const MAX_CLIENT_ID_LEN: usize = 255;
+
+/// 端末ごとに取り消せる操作の数。古いものから消える
+pub const MAX_UNDO_DEPTH: i64 = 100;
+
+/// 取り消し・やり直しのために保存するエンティティの状態
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "entity", rename_all = "snake_case")]
+pub enum Snapshot {
+    /// `reminder_offsets` はコースのリマインダーの既定値 (`Course` には含まれない)
+    Course {
+        course: Course,
+        #[serde(default)]
+        reminder_offsets: Vec<i64>,
+    },
+    /// サブタスクも todo の一部として戻す。`series_date` は系列の回の日付、
+    /// `reminder_offsets` は todo 自身のリマインダー (`None` ならコースの既定値)、
+    /// `series` はこの todo をテンプレートにする系列
+    Todo {
+        todo: Todo,
+        subtasks: Vec<Subtask>,
+        #[serde(default)]
+        series_date: Option<NaiveDate>,
+        #[serde(default)]
+        reminder_offsets: Option<Vec<i64>>,
+        #[serde(default)]
+        series: Option<Box<TodoSeries>>,
+    },
+    /// 学期の期間と `is_current` (`course_count` は比べない)
+    Semester { semester: Semester },
+}
+
+impl Snapshot {
+    /// ローカルだけの項目 (リマインダー) を持たないコース (Notion から取得した値など)
+    pub fn course(course: Course) -> Self {
+        Snapshot::Course { course, reminder_offsets: Vec::new() }
+    }
+
+    /// ローカルだけの項目 (系列、リマインダー) を持たない todo
+    pub fn todo(todo: Todo, subtasks: Vec<Subtask>) -> Self {
+        Snapshot::Todo { todo, subtasks, series_date: None, reminder_offsets: None, series: None }
+    }
+
+    /// 版・更新日時・同期状態を除いて同じ内容か
+    ///
+    /// 同期の Push で `sync_state` が変わっただけなら、その後も取り消せる。
+    /// 系列の作成済みの範囲 (`generated_until`) も、定期的な回の作成で進むので比べない。
+    pub fn same_content(&self, other: &Snapshot) -> bool {
+        serde_json::to_value(self.content()).ok() == serde_json::to_value(other.content()).ok()
+    }
+
+    fn content(&self) -> Snapshot {
+        let mut snapshot = self.clone();
+        match &mut snapshot {
+            Snapshot::Course { course, .. } => {
+                course.version = 0;
+                course.updated_at = DateTime::<Utc>::UNIX_EPOCH;
+                course.sync_state.clear();
+                course.last_synced_at = None;
+            }
+            Snapshot::Todo { todo, subtasks, series, .. } => {
+                todo.version = 0;
+                todo.updated_at = DateTime::<Utc>::UNIX_EPOCH;
+                todo.sync_state.clear();
+                todo.last_synced_at = None;
+                for subtask in subtasks {
+                    subtask.updated_at = DateTime::<Utc>::UNIX_EPOCH;
+                }
+                if let Some(series) = series {
+                    series.generated_until = None;
+                    series.updated_at = DateTime::<Utc>::UNIX_EPOCH;
+                }
+            }
+            Snapshot::Semester { semester } => {
+                semester.course_count = 0;
+            }
+        }
+        snapshot
+    }
+}
+
+/// 操作ログの 1 件 (`POST /undo`, `POST /redo` の応答に含める)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct Operation {
+    pub id: i64,
+    pub client_id: String,
+    /// `create_todo`, `archive_todo`, `update_subtask` など
+    pub action: String,
+    pub entity: ChangeEntity,
+    pub entity_id: String,
+    pub created_at: DateTime<Utc>,
+    pub undone_at: Option<DateTime<Utc>>,
+}
+
+/// `POST /undo`, `POST /redo`
+///
+/// 戻した後のコース、todo または学期 (削除した場合はどれも `null`)。
+/// 系列の変更のように 1 回のリクエストで複数の todo を変えた操作はまとめて戻し、
+/// 最初に記録した操作 (系列ならテンプレート) を `operation`、残りを `related` に入れる。
+#[derive(Debug, Clone, Serialize)]
+pub struct UndoResponse {
+    pub operation: Operation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub course: Option<Course>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo: Option<Todo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub semester: Option<Semester>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<Operation>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{SubtaskProgress, TodoStatus};
+
+    #[test]
+    fn test_same_content_ignores_sync_bookkeeping() {
+        let todo = Todo {
+            id: "t1".to_string(),
+            course_id: "c1".to_string(),
+            title: "Report".to_string(),
+            due_date: "2026-10-20".parse().unwrap(),
+            due_end: None,
+            due_timezone: None,
+            status: TodoStatus::NotStarted,
+            priority: None,
+            tags: Vec::new(),
+            notes: String::new(),
+            progress: SubtaskProgress::default(),
+            series_id: None,
+            version: 3,
+            completed_at: None,
+            is_archived: false,
+            updated_at: Utc::now(),
+            sync_state: "pending".to_string(),
+            last_synced_at: None,
+            notion_page_id: None,
+        };
+        let before = Snapshot::todo(todo.clone(), Vec::new());
+
+        let pushed = Todo { version: 4, sync_state: "synced".to_string(), last_synced_at: Some(Utc::now()), ..todo.clone() };
+        assert!(before.same_content(&Snapshot::todo(pushed, Vec::new())));
+
+        let archived = Todo { is_archived: true, ..todo };
+        assert!(!before.same_content(&Snapshot::todo(archived, Vec::new())));
+    }
+}
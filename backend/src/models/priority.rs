@@ -0,0 +1,95 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// todo の優先度 (Notion の "Priority" セレクト: High / Medium / Low、または 高 / 中 / 低)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum Priority {
+    High,
+    Medium,
+    Low,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::High, Priority::Medium, Priority::Low];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        }
+    }
+
+    /// Notion のオプション名 (`TODO_PRIORITY_HIGH` などで変更可、省略時: High / Medium / Low)
+    pub fn to_notion(self) -> String {
+        let (key, default) = match self {
+            Priority::High => ("TODO_PRIORITY_HIGH", "High"),
+            Priority::Medium => ("TODO_PRIORITY_MEDIUM", "Medium"),
+            Priority::Low => ("TODO_PRIORITY_LOW", "Low"),
+        };
+        std::env::var(key)
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| default.to_string())
+    }
+
+    /// データベースの "Priority" のオプションのうち、この優先度に当たる名前 (無ければ `to_notion`)
+    pub fn to_notion_in(self, options: &[String]) -> String {
+        let configured = self.to_notion();
+        if options.contains(&configured) {
+            return configured;
+        }
+        options
+            .iter()
+            .find(|name| name.parse::<Priority>() == Ok(self))
+            .cloned()
+            .unwrap_or(configured)
+    }
+
+    /// 日本語のオプション名
+    fn localized(self) -> &'static [&'static str] {
+        match self {
+            Priority::High => &["高", "高い"],
+            Priority::Medium => &["中", "普通"],
+            Priority::Low => &["低", "低い"],
+        }
+    }
+}
+
+/// API の値 (`high`)、Notion のオプション名 (`High` と `TODO_PRIORITY_*` の名前)、日本語名 (`高`) を受け付ける
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        Priority::ALL
+            .into_iter()
+            .find(|p| {
+                p.as_str().eq_ignore_ascii_case(s)
+                    || p.to_notion().eq_ignore_ascii_case(s)
+                    || p.localized().contains(&s)
+            })
+            .ok_or_else(|| format!("'{}' is not a priority (high, medium, low)", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_localized_priority_names() {
+        assert_eq!("高".parse::<Priority>(), Ok(Priority::High));
+        assert_eq!(" Medium ".parse::<Priority>(), Ok(Priority::Medium));
+        assert_eq!("低い".parse::<Priority>(), Ok(Priority::Low));
+        assert!("urgent".parse::<Priority>().is_err());
+
+        let options = vec!["高".to_string(), "中".to_string(), "低".to_string()];
+        assert_eq!(Priority::Medium.to_notion_in(&options), "中");
+        assert_eq!(Priority::Medium.to_notion_in(&[]), "Medium");
+    }
+}
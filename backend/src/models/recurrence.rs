@@ -0,0 +1,254 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::error::FieldError;
+use super::course::Weekday;
+use super::validation::Validate;
+
+/// 繰り返しの todo の系列
+///
+/// `template_todo_id` の todo (最初の回) を元に、`rule` に従って先の回を通常の pending な todo として作る。
+/// 作成済みの範囲は `generated_until` まで (削除した回は作り直さない)。
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TodoSeries {
+    pub id: String,
+    pub template_todo_id: String,
+    /// RRULE (`FREQ=WEEKLY;BYDAY=MO,TH;UNTIL=20270131`)
+    pub rule: String,
+    /// 最初の回の日付 (テンプレートの締め切りの現地の日付)
+    pub start_date: NaiveDate,
+    pub generated_until: Option<NaiveDate>,
+    pub is_active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// `POST /todos/{id}/series`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewSeriesRequest {
+    pub rule: String,
+}
+
+/// `PATCH /series/{id}`。ルールを変えると未来の回を作り直す
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateSeriesRequest {
+    pub rule: String,
+}
+
+impl Validate for NewSeriesRequest {
+    fn normalize(&mut self) {
+        self.rule = self.rule.trim().to_string();
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        check_rule(&self.rule)
+    }
+}
+
+impl Validate for UpdateSeriesRequest {
+    fn normalize(&mut self) {
+        self.rule = self.rule.trim().to_string();
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        check_rule(&self.rule)
+    }
+}
+
+fn check_rule(rule: &str) -> Vec<FieldError> {
+    match rule.parse::<RecurrenceRule>() {
+        Ok(_) => Vec::new(),
+        Err(message) => vec![FieldError::new("rule", message)],
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+}
+
+/// RRULE のサブセット
+///
+/// - `FREQ=DAILY;INTERVAL=3`: 3 日ごと
+/// - `FREQ=WEEKLY;BYDAY=MO,TH`: 毎週月・木曜 (`INTERVAL=2` で隔週)
+/// - `FREQ=WEEKLY` (`BYDAY` なし): コースの授業枠の曜日 (授業枠が無ければ最初の回の曜日)
+/// - `UNTIL=20270131`: その日まで
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<Weekday>,
+    pub until: Option<NaiveDate>,
+}
+
+impl RecurrenceRule {
+    /// `start` から始まる回のうち `from`〜`to` (両端を含む) にある日付
+    ///
+    /// `meeting_days` は `BYDAY` の無い週次ルールで使うコースの授業の曜日。
+    pub fn occurrences(&self, start: NaiveDate, meeting_days: &[Weekday], from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        let to = self.until.map_or(to, |until| to.min(until));
+        let interval = i64::from(self.interval.max(1));
+        let days: Vec<Weekday> = match (self.freq, self.by_day.is_empty(), meeting_days.is_empty()) {
+            (Frequency::Weekly, false, _) => self.by_day.clone(),
+            (Frequency::Weekly, true, false) => meeting_days.to_vec(),
+            _ => vec![start.weekday().into()],
+        };
+        let start_week = start - Duration::days(i64::from(start.weekday().num_days_from_monday()));
+
+        let mut dates = Vec::new();
+        let mut date = from.max(start);
+        while date <= to {
+            let offset = (date - start).num_days();
+            let matches = match self.freq {
+                Frequency::Daily => offset % interval == 0,
+                Frequency::Weekly => {
+                    let week = (date - start_week).num_days() / 7;
+                    week % interval == 0 && days.contains(&date.weekday().into())
+                }
+            };
+            if matches {
+                dates.push(date);
+            }
+            date += Duration::days(1);
+        }
+        dates
+    }
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix("RRULE:").unwrap_or(s);
+        let mut freq = None;
+        let mut interval = 1;
+        let mut by_day = Vec::new();
+        let mut until = None;
+
+        for part in s.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or_else(|| format!("'{}' is not KEY=VALUE", part))?;
+            match key.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.trim().to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        other => return Err(format!("FREQ={} is not supported (DAILY, WEEKLY)", other)),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .trim()
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|i| (1..=366).contains(i))
+                        .ok_or_else(|| format!("INTERVAL={} must be between 1 and 366", value))?
+                }
+                "BYDAY" => {
+                    for day in value.split(',').map(str::trim) {
+                        let day = RRULE_DAYS
+                            .iter()
+                            .position(|d| d.eq_ignore_ascii_case(day))
+                            .map(|i| Weekday::ALL[i])
+                            .ok_or_else(|| format!("'{}' is not a weekday (MO, TU, WE, TH, FR, SA, SU)", day))?;
+                        if !by_day.contains(&day) {
+                            by_day.push(day);
+                        }
+                    }
+                }
+                "UNTIL" => {
+                    // UNTIL=20270131 or UNTIL=20270131T000000Z; only the date is used
+                    let date = value.trim().get(..8).unwrap_or_default();
+                    until = Some(
+                        NaiveDate::parse_from_str(date, "%Y%m%d")
+                            .map_err(|_| format!("UNTIL={} is not a date (YYYYMMDD)", value))?,
+                    );
+                }
+                other => return Err(format!("{} is not supported", other)),
+            }
+        }
+
+        let freq = freq.ok_or_else(|| "FREQ is required".to_string())?;
+        if freq == Frequency::Daily && !by_day.is_empty() {
+            return Err("BYDAY is only supported with FREQ=WEEKLY".to_string());
+        }
+        by_day.sort();
+        Ok(Self { freq, interval, by_day, until })
+    }
+}
+
+const RRULE_DAYS: [&str; 7] = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+
+/// 正規化した RRULE (`FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH;UNTIL=20270131`)
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.freq {
+            Frequency::Daily => f.write_str("FREQ=DAILY")?,
+            Frequency::Weekly => f.write_str("FREQ=WEEKLY")?,
+        }
+        if self.interval > 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<_> = self.by_day.iter().map(|d| RRULE_DAYS[*d as usize]).collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%d"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_format_rule() {
+        let rule: RecurrenceRule = "RRULE:freq=weekly;byday=TH,MO;interval=2;UNTIL=20270131T000000Z".parse().unwrap();
+        assert_eq!(rule.by_day, vec![Weekday::Mon, Weekday::Thu]);
+        assert_eq!(rule.to_string(), "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH;UNTIL=20270131");
+        assert_eq!(rule.to_string().parse::<RecurrenceRule>().unwrap(), rule);
+
+        assert!("FREQ=MONTHLY".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=DAILY;BYDAY=MO".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=WEEKLY;COUNT=3".parse::<RecurrenceRule>().is_err());
+        assert!("INTERVAL=2".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=DAILY;INTERVAL=0".parse::<RecurrenceRule>().is_err());
+    }
+
+    #[test]
+    fn test_occurrences() {
+        // 2026-10-19 is a Monday
+        let start = date("2026-10-19");
+        let window = |rule: &str, meeting_days: &[Weekday]| {
+            let rule: RecurrenceRule = rule.parse().unwrap();
+            rule.occurrences(start, meeting_days, date("2026-10-01"), date("2026-11-08"))
+                .into_iter()
+                .map(|d| d.format("%m-%d").to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(window("FREQ=DAILY;INTERVAL=6", &[]), vec!["10-19", "10-25", "10-31", "11-06"]);
+        assert_eq!(
+            window("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH", &[]),
+            vec!["10-19", "10-22", "11-02", "11-05"]
+        );
+        // course meeting days when BYDAY is missing, the start's weekday without meetings
+        assert_eq!(
+            window("FREQ=WEEKLY;UNTIL=20261029", &[Weekday::Wed, Weekday::Fri]),
+            vec!["10-21", "10-23", "10-28"]
+        );
+        assert_eq!(window("FREQ=WEEKLY", &[]), vec!["10-19", "10-26", "11-02"]);
+    }
+}
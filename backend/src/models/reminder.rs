@@ -0,0 +1,199 @@
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::error::FieldError;
+use super::agenda::local_midnight;
+use super::due_date::DueDate;
+use super::todo::Todo;
+use super::validation::Validate;
+
+/// リマインダーのオフセット (締め切りの何分前か) の上限: 30 日
+pub const MAX_REMINDER_OFFSET_MINUTES: i64 = 30 * 24 * 60;
+
+/// 1 つの todo / コースに設定できるオフセットの数
+pub const MAX_REMINDER_OFFSETS: usize = 10;
+
+/// 締め切り前のリマインダー
+///
+/// `ReminderScheduler` が todo ごとのオフセットから作る。どれかの端末が
+/// `POST /reminders/{id}/ack` すると、他の端末には配信されなくなる。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct Reminder {
+    pub id: String,
+    pub todo_id: String,
+    pub course_id: String,
+    /// todo のタイトル
+    pub title: String,
+    pub offset_minutes: i64,
+    pub remind_at: DateTime<Utc>,
+    /// リマインダーの基準になる締め切りの時刻
+    pub due_at: DateTime<Utc>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+}
+
+/// `GET /reminders/stream` で送るイベント
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReminderEvent {
+    /// リマインダーの時刻になった
+    Due { reminder: Reminder },
+    /// どれかの端末が表示した (他の端末は通知を取り下げる)
+    Acknowledged { id: String },
+}
+
+impl ReminderEvent {
+    /// SSE の `event:` 名
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReminderEvent::Due { .. } => "reminder",
+            ReminderEvent::Acknowledged { .. } => "acknowledged",
+        }
+    }
+}
+
+/// `GET /todos/{id}/reminders`, `GET /courses/{id}/reminders`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReminderSettings {
+    /// 設定されたオフセット (分、大きい順)。todo で `null` ならコースのデフォルトを使う
+    pub offsets: Option<Vec<i64>>,
+    /// 実際に使うオフセット
+    pub effective: Vec<i64>,
+}
+
+/// `PUT /todos/{id}/reminders`, `PUT /courses/{id}/reminders`
+///
+/// todo では `null` でコースのデフォルトに戻し、`[]` でリマインダーを無効にする。
+/// コースでは `null` は `[]` と同じ。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateReminderSettingsRequest {
+    #[serde(default)]
+    pub offsets: Option<Vec<i64>>,
+}
+
+impl Validate for UpdateReminderSettingsRequest {
+    fn normalize(&mut self) {
+        if let Some(offsets) = &mut self.offsets {
+            offsets.sort_unstable_by(|a, b| b.cmp(a));
+            offsets.dedup();
+        }
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        let Some(offsets) = &self.offsets else {
+            return errors;
+        };
+        if offsets.len() > MAX_REMINDER_OFFSETS {
+            errors.push(FieldError::new("offsets", format!("must have at most {} entries", MAX_REMINDER_OFFSETS)));
+        }
+        if offsets.iter().any(|m| !(0..=MAX_REMINDER_OFFSET_MINUTES).contains(m)) {
+            errors.push(FieldError::new(
+                "offsets",
+                format!("minutes must be between 0 and {}", MAX_REMINDER_OFFSET_MINUTES),
+            ));
+        }
+        errors
+    }
+}
+
+/// リマインダーの基準になる締め切りの時刻
+///
+/// 時刻付きは締め切り (期間なら始まり) の時刻、終日は最後の日の終わり (翌日の現地 0 時)。
+pub fn reminder_due_at(todo: &Todo, tz: &Tz) -> DateTime<Utc> {
+    match todo.due_date {
+        DueDate::Timed(at) => at,
+        DueDate::AllDay(date) => {
+            let last = match todo.due_end {
+                Some(DueDate::AllDay(end)) => end,
+                _ => date,
+            };
+            local_midnight(tz, last + Duration::days(1))
+        }
+    }
+}
+
+/// 作るべきリマインダー 1 件分
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedReminder {
+    pub todo_id: String,
+    pub offset_minutes: i64,
+    pub remind_at: DateTime<Utc>,
+    pub due_at: DateTime<Utc>,
+}
+
+/// `todo` の締め切りとオフセットからリマインダーを計画する。締め切りを過ぎた todo には作らない
+pub fn plan_reminders(todo: &Todo, offsets: &[i64], tz: &Tz, now: DateTime<Utc>) -> Vec<PlannedReminder> {
+    let due_at = reminder_due_at(todo, tz);
+    if due_at <= now {
+        return Vec::new();
+    }
+    offsets
+        .iter()
+        .map(|&minutes| PlannedReminder {
+            todo_id: todo.id.clone(),
+            offset_minutes: minutes,
+            remind_at: due_at - Duration::minutes(minutes),
+            due_at,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{SubtaskProgress, TodoStatus};
+
+    fn todo(due_date: &str, due_end: Option<&str>) -> Todo {
+        Todo {
+            id: "t1".to_string(),
+            course_id: "c1".to_string(),
+            title: "Report".to_string(),
+            due_date: due_date.parse().unwrap(),
+            due_end: due_end.map(|end| end.parse().unwrap()),
+            due_timezone: None,
+            status: TodoStatus::NotStarted,
+            priority: None,
+            tags: Vec::new(),
+            notes: String::new(),
+            progress: SubtaskProgress::default(),
+            series_id: None,
+            version: 0,
+            completed_at: None,
+            is_archived: false,
+            updated_at: Utc::now(),
+            sync_state: "synced".to_string(),
+            last_synced_at: None,
+            notion_page_id: None,
+        }
+    }
+
+    #[test]
+    fn test_plan_reminders() {
+        let tz = chrono_tz::Asia::Tokyo;
+        let now: DateTime<Utc> = "2026-10-19T00:00:00Z".parse().unwrap();
+
+        // all-day: end of the last local day
+        let planned = plan_reminders(&todo("2026-10-20", Some("2026-10-21")), &[1440, 60], &tz, now);
+        let times: Vec<_> = planned.iter().map(|p| p.remind_at.to_rfc3339()).collect();
+        assert_eq!(times, vec!["2026-10-20T15:00:00+00:00", "2026-10-21T14:00:00+00:00"]);
+
+        // timed: the start of the range
+        let planned = plan_reminders(&todo("2026-10-19T03:00:00Z", Some("2026-10-19T05:00:00Z")), &[30], &tz, now);
+        assert_eq!(planned[0].remind_at.to_rfc3339(), "2026-10-19T02:30:00+00:00");
+
+        assert!(plan_reminders(&todo("2026-10-18", None), &[60], &tz, now).is_empty(), "already past");
+    }
+
+    #[test]
+    fn test_offsets_are_sorted_and_checked() {
+        let mut req = UpdateReminderSettingsRequest { offsets: Some(vec![60, 1440, 60]) };
+        req.normalize();
+        assert_eq!(req.offsets, Some(vec![1440, 60]));
+        assert!(req.validate().is_empty());
+
+        let req = UpdateReminderSettingsRequest { offsets: Some(vec![-5, MAX_REMINDER_OFFSET_MINUTES + 1]) };
+        assert_eq!(req.validate().len(), 1);
+    }
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// `GET /search` の 1 件分の結果
+///
+/// `kind` は `"todo"` または `"course"`。`snippet` は一致箇所を `<mark>` で囲んだ抜粋。
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SearchHit {
+    pub kind: String,
+    pub id: String,
+    pub title: String,
+    pub snippet: String,
+    pub rank: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<u32>,
+}
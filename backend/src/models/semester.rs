@@ -0,0 +1,57 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::error::FieldError;
+use super::validation::{check_date, check_select_names, Validate};
+
+/// `semester` フィルターで「現在の学期」を表す値
+pub const CURRENT_SEMESTER: &str = "current";
+
+/// 学期
+///
+/// Notion の "Semester" マルチセレクトのオプション名を `name` とする。
+/// 期間と `is_current` はこの API からのみ設定する (Notion には無い)。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct Semester {
+    pub name: String,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub is_current: bool,
+    /// アーカイブされていない所属コースの数
+    pub course_count: i64,
+}
+
+/// `PATCH /semesters/{name}`。日付は空文字で削除、`is_current: true` で他の学期の current を外す
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateSemesterRequest {
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+    pub is_current: Option<bool>,
+}
+
+impl Validate for UpdateSemesterRequest {
+    fn normalize(&mut self) {
+        for value in [&mut self.start_date, &mut self.end_date].into_iter().flatten() {
+            *value = value.trim().to_string();
+        }
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        for (field, value) in [("start_date", &self.start_date), ("end_date", &self.end_date)] {
+            if let Some(value) = value.as_deref().filter(|v| !v.is_empty()) {
+                check_date(&mut errors, field, value);
+            }
+        }
+        errors
+    }
+}
+
+/// コースの学期一覧の検証 (1 件以上、空白のみ・カンマは不可)
+pub fn check_semesters(errors: &mut Vec<FieldError>, field: &str, semesters: &[String]) {
+    if semesters.is_empty() {
+        errors.push(FieldError::new(field, "must contain at least one semester"));
+    }
+    check_select_names(errors, field, semesters);
+}
@@ -0,0 +1,279 @@
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::sqlite::{SqliteTypeInfo, SqliteValueRef};
+use sqlx::{Decode, Encode, Sqlite, Type};
+
+/// Notion の Status プロパティのグループ (To-do / In progress / Complete)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusGroup {
+    NotStarted,
+    InProgress,
+    Done,
+}
+
+/// Todo の進捗状態
+///
+/// 各グループの代表オプションは `not_started` / `in_progress` / `done` として扱い、
+/// それ以外の Notion オプション (例: `最終確認`) は `Custom` にオプション名をそのまま持つ。
+/// API と DB ではどちらも文字列として表現される。
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum TodoStatus {
+    #[default]
+    NotStarted,
+    InProgress,
+    Done,
+    Custom(String),
+}
+
+impl TodoStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TodoStatus::NotStarted => "not_started",
+            TodoStatus::InProgress => "in_progress",
+            TodoStatus::Done => "done",
+            TodoStatus::Custom(name) => name,
+        }
+    }
+
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "not_started" => TodoStatus::NotStarted,
+            "in_progress" => TodoStatus::InProgress,
+            "done" => TodoStatus::Done,
+            other => TodoStatus::Custom(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for TodoStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for TodoStatus {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for TodoStatus {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(TodoStatus::parse(&s))
+    }
+}
+
+impl Type<Sqlite> for TodoStatus {
+    fn type_info() -> SqliteTypeInfo {
+        <String as Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <String as Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'q> Encode<'q, Sqlite> for TodoStatus {
+    fn encode_by_ref(
+        &self,
+        buf: &mut <Sqlite as sqlx::Database>::ArgumentBuffer<'q>,
+    ) -> Result<IsNull, BoxDynError> {
+        <String as Encode<'q, Sqlite>>::encode(self.as_str().to_string(), buf)
+    }
+}
+
+impl<'r> Decode<'r, Sqlite> for TodoStatus {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, BoxDynError> {
+        let s = <String as Decode<'r, Sqlite>>::decode(value)?;
+        Ok(TodoStatus::parse(&s))
+    }
+}
+
+/// Notion の Status オプション 1 件
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusOption {
+    /// API / DB 上の値
+    pub status: TodoStatus,
+    /// Notion 上のオプション名
+    pub name: String,
+    pub group: StatusGroup,
+}
+
+/// `TodoStatus` と Notion の Status オプション名の対応表
+///
+/// 起動時に Notion の Todos データベースのスキーマから構築する。
+/// 各グループの代表オプションは環境変数 `TODO_STATUS_NOT_STARTED` /
+/// `TODO_STATUS_IN_PROGRESS` / `TODO_STATUS_DONE` で指定でき、未指定ならグループ先頭のオプション。
+#[derive(Debug, Clone)]
+pub struct StatusMapping {
+    pub not_started: String,
+    pub in_progress: String,
+    pub done: String,
+    /// Status プロパティの全オプション名とそのグループ
+    pub options: Vec<(String, StatusGroup)>,
+}
+
+impl Default for StatusMapping {
+    fn default() -> Self {
+        Self {
+            not_started: "未着手".to_string(),
+            in_progress: "進行中".to_string(),
+            done: "完了".to_string(),
+            options: vec![
+                ("未着手".to_string(), StatusGroup::NotStarted),
+                ("進行中".to_string(), StatusGroup::InProgress),
+                ("最終確認".to_string(), StatusGroup::InProgress),
+                ("完了".to_string(), StatusGroup::Done),
+            ],
+        }
+    }
+}
+
+impl StatusMapping {
+    /// Notion に接続できない場合のデフォルト (環境変数の上書きのみ適用)
+    pub fn new_from_env() -> Self {
+        Self::from_options(Self::default().options)
+    }
+
+    /// Notion のスキーマから得たオプション一覧から対応表を作る
+    pub fn from_options(options: Vec<(String, StatusGroup)>) -> Self {
+        let var = |key: &str| std::env::var(key).ok().filter(|v| !v.trim().is_empty());
+        let representative = |group: StatusGroup, key: &str, fallback: String| {
+            var(key)
+                .or_else(|| {
+                    options
+                        .iter()
+                        .find(|(_, g)| *g == group)
+                        .map(|(name, _)| name.clone())
+                })
+                .unwrap_or(fallback)
+        };
+        let default = Self::default();
+
+        Self {
+            not_started: representative(StatusGroup::NotStarted, "TODO_STATUS_NOT_STARTED", default.not_started),
+            in_progress: representative(StatusGroup::InProgress, "TODO_STATUS_IN_PROGRESS", default.in_progress),
+            done: representative(StatusGroup::Done, "TODO_STATUS_DONE", default.done),
+            options,
+        }
+    }
+
+    /// Notion のオプション名 → `TodoStatus`
+    pub fn from_notion(&self, name: &str) -> TodoStatus {
+        if name == self.not_started {
+            TodoStatus::NotStarted
+        } else if name == self.in_progress {
+            TodoStatus::InProgress
+        } else if name == self.done {
+            TodoStatus::Done
+        } else {
+            TodoStatus::Custom(name.to_string())
+        }
+    }
+
+    /// Notion のオプション名のまま渡された `Custom` を代表のキーに揃える
+    ///
+    /// `完了` と `done` のように同じステータスが 2 通りに保存されないようにする。
+    pub fn normalize(&self, status: TodoStatus) -> TodoStatus {
+        match status {
+            TodoStatus::Custom(name) => self.from_notion(&name),
+            status => status,
+        }
+    }
+
+    /// `TodoStatus` → Notion のオプション名
+    pub fn to_notion<'a>(&'a self, status: &'a TodoStatus) -> &'a str {
+        match status {
+            TodoStatus::NotStarted => &self.not_started,
+            TodoStatus::InProgress => &self.in_progress,
+            TodoStatus::Done => &self.done,
+            TodoStatus::Custom(name) => name,
+        }
+    }
+
+    pub fn group_of(&self, status: &TodoStatus) -> Option<StatusGroup> {
+        match status {
+            TodoStatus::NotStarted => Some(StatusGroup::NotStarted),
+            TodoStatus::InProgress => Some(StatusGroup::InProgress),
+            TodoStatus::Done => Some(StatusGroup::Done),
+            TodoStatus::Custom(name) => self
+                .options
+                .iter()
+                .find(|(option, _)| option == name)
+                .map(|(_, group)| *group),
+        }
+    }
+
+    /// Notion 側に存在するステータスかどうか
+    pub fn is_known(&self, status: &TodoStatus) -> bool {
+        self.group_of(status).is_some()
+    }
+
+    pub fn is_done(&self, status: &TodoStatus) -> bool {
+        self.group_of(status) == Some(StatusGroup::Done)
+    }
+
+    /// 完了グループに属するステータス (代表の `done` を含む)
+    pub fn done_statuses(&self) -> Vec<TodoStatus> {
+        let mut statuses: Vec<TodoStatus> = self
+            .options
+            .iter()
+            .filter(|(_, group)| *group == StatusGroup::Done)
+            .map(|(name, _)| self.from_notion(name))
+            .collect();
+        if !statuses.contains(&TodoStatus::Done) {
+            statuses.push(TodoStatus::Done);
+        }
+        statuses
+    }
+
+    /// API で利用可能なステータスの一覧
+    pub fn list(&self) -> Vec<StatusOption> {
+        self.options
+            .iter()
+            .map(|(name, group)| StatusOption {
+                status: self.from_notion(name),
+                name: name.clone(),
+                group: *group,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_through_notion_names() {
+        let mapping = StatusMapping::default();
+
+        assert_eq!(mapping.from_notion("完了"), TodoStatus::Done);
+        assert_eq!(mapping.to_notion(&TodoStatus::Done), "完了");
+        assert_eq!(mapping.from_notion("最終確認"), TodoStatus::Custom("最終確認".to_string()));
+        assert_eq!(mapping.group_of(&TodoStatus::parse("最終確認")), Some(StatusGroup::InProgress));
+        assert!(!mapping.is_known(&TodoStatus::parse("完了了")));
+
+        assert_eq!(mapping.normalize(TodoStatus::parse("完了")), TodoStatus::Done);
+        assert_eq!(mapping.normalize(TodoStatus::parse("最終確認")), TodoStatus::Custom("最終確認".to_string()));
+    }
+
+    #[test]
+    fn test_representative_is_first_option_of_group() {
+        let mapping = StatusMapping::from_options(vec![
+            ("Todo".to_string(), StatusGroup::NotStarted),
+            ("Doing".to_string(), StatusGroup::InProgress),
+            ("Shipped".to_string(), StatusGroup::Done),
+            ("Dropped".to_string(), StatusGroup::Done),
+        ]);
+
+        assert_eq!(mapping.to_notion(&TodoStatus::Done), "Shipped");
+        assert!(mapping.is_done(&TodoStatus::parse("Dropped")));
+        assert_eq!(TodoStatus::parse("done").as_str(), "done");
+    }
+}
@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use crate::error::FieldError;
+use super::validation::{check_title, Validate};
+
+/// todo のチェックリストの 1 項目
+///
+/// Notion ではページ直下の to-do ブロックに対応する (`position` はページ内の順番)。
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Subtask {
+    pub id: String,
+    pub todo_id: String,
+    pub title: String,
+    pub done: bool,
+    pub position: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Notion の to-do ブロック 1 つ分 (同期用)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubtaskItem {
+    pub title: String,
+    pub done: bool,
+}
+
+/// `Todo.progress`: 完了したサブタスク数 / サブタスク数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubtaskProgress {
+    pub done: i64,
+    pub total: i64,
+}
+
+/// `POST /todos/{id}/subtasks`。`position` 省略時は末尾に追加する
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewSubtaskRequest {
+    pub title: String,
+    #[serde(default)]
+    pub done: bool,
+    #[serde(default)]
+    pub position: Option<i64>,
+}
+
+/// `PATCH /todos/{id}/subtasks/{subtask_id}`。`position` で並べ替える (範囲外は末尾)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateSubtaskRequest {
+    pub title: Option<String>,
+    pub done: Option<bool>,
+    pub position: Option<i64>,
+}
+
+impl Validate for NewSubtaskRequest {
+    fn normalize(&mut self) {
+        self.title = self.title.trim().to_string();
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_subtask_title(&mut errors, "title", &self.title);
+        if let Some(position) = self.position {
+            check_position(&mut errors, "position", position);
+        }
+        errors
+    }
+}
+
+impl Validate for UpdateSubtaskRequest {
+    fn normalize(&mut self) {
+        if let Some(title) = &mut self.title {
+            *title = title.trim().to_string();
+        }
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(title) = &self.title {
+            check_subtask_title(&mut errors, "title", title);
+        }
+        if let Some(position) = self.position {
+            check_position(&mut errors, "position", position);
+        }
+        errors
+    }
+}
+
+/// to-do ブロックは 1 行なので改行は不可
+fn check_subtask_title(errors: &mut Vec<FieldError>, field: &str, title: &str) {
+    check_title(errors, field, title);
+    if title.contains('\n') {
+        errors.push(FieldError::new(field, "must be a single line"));
+    }
+}
+
+fn check_position(errors: &mut Vec<FieldError>, field: &str, position: i64) {
+    if position < 0 {
+        errors.push(FieldError::new(field, "must not be negative"));
+    }
+}
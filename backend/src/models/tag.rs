@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// `GET /tags` の 1 件分: タグとアーカイブされていない todo の件数
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Tag {
+    pub name: String,
+    pub todo_count: i64,
+}
@@ -0,0 +1,268 @@
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize, Serializer};
+use sqlx::FromRow;
+
+use super::course::{Course, Weekday};
+use super::meeting::CourseMeeting;
+
+/// 1 時限分の開始・終了時刻
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PeriodTime {
+    pub period: i32,
+    #[serde(serialize_with = "serialize_hm")]
+    pub start: NaiveTime,
+    #[serde(serialize_with = "serialize_hm")]
+    pub end: NaiveTime,
+}
+
+fn serialize_hm<S: Serializer>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&time.format("%H:%M"))
+}
+
+/// 時限ごとの時刻表
+///
+/// 環境変数 `PERIOD_TIMES` で 1 限から順にカンマ区切りで指定する
+/// (例: `09:00-10:30,10:40-12:10,...`)。時間割のグリッドはここに定義された時限で作られる。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeriodSchedule {
+    pub periods: Vec<PeriodTime>,
+}
+
+impl Default for PeriodSchedule {
+    fn default() -> Self {
+        Self::parse("09:00-10:30,10:40-12:10,13:00-14:30,14:40-16:10,16:20-17:50,18:00-19:30,19:40-21:10")
+            .expect("default period times are valid")
+    }
+}
+
+impl PeriodSchedule {
+    /// `HH:MM-HH:MM` をカンマ区切りで並べた文字列を読む
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let periods = value
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .enumerate()
+            .map(|(i, slot)| {
+                let (start, end) = slot
+                    .split_once('-')
+                    .ok_or_else(|| format!("'{}' is not HH:MM-HH:MM", slot))?;
+                let parse = |t: &str| {
+                    NaiveTime::parse_from_str(t.trim(), "%H:%M")
+                        .map_err(|_| format!("'{}' is not HH:MM-HH:MM", slot))
+                };
+                let (start, end) = (parse(start)?, parse(end)?);
+                if end <= start {
+                    return Err(format!("period {} ends before it starts", i + 1));
+                }
+                Ok(PeriodTime { period: i as i32 + 1, start, end })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        if periods.is_empty() {
+            return Err("no periods defined".to_string());
+        }
+        Ok(Self { periods })
+    }
+}
+
+/// `GET /timetable` のクエリパラメータ。`semester` 未指定時は全学期のコース (`current` で現在の学期)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TimetableQuery {
+    pub semester: Option<String>,
+}
+
+/// 時間割に載せるコースと、その未完了 todo の件数
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct TimetableEntry {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub course: Course,
+    pub open_todos: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimetableCell {
+    #[serde(flatten)]
+    pub time: PeriodTime,
+    pub courses: Vec<TimetableEntry>,
+    /// 同じコマに 2 つ以上のコースがある
+    pub conflict: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimetableDay {
+    pub day: Weekday,
+    pub periods: Vec<TimetableCell>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimetableConflict {
+    pub day: Weekday,
+    pub period: i32,
+    pub course_ids: Vec<String>,
+}
+
+/// `GET /timetable` のレスポンス
+///
+/// `days` は曜日 × 時限のグリッド (月〜日、`PERIOD_TIMES` の全時限)。
+/// コースは授業枠ごとに該当するコマに入り、時刻で指定した枠は時間が重なる時限に入る。
+/// どのコマにも当てはまらないコースは `unscheduled` に入る。
+#[derive(Debug, Clone, Serialize)]
+pub struct Timetable {
+    pub semester: Option<String>,
+    pub days: Vec<TimetableDay>,
+    pub conflicts: Vec<TimetableConflict>,
+    pub unscheduled: Vec<TimetableEntry>,
+}
+
+impl Timetable {
+    pub fn build(schedule: &PeriodSchedule, semester: Option<String>, entries: Vec<TimetableEntry>) -> Self {
+        let mut days: Vec<TimetableDay> = Weekday::ALL
+            .into_iter()
+            .map(|day| TimetableDay {
+                day,
+                periods: schedule
+                    .periods
+                    .iter()
+                    .map(|time| TimetableCell { time: *time, courses: Vec::new(), conflict: false })
+                    .collect(),
+            })
+            .collect();
+        let mut unscheduled = Vec::new();
+
+        for entry in entries {
+            let mut slots: Vec<(usize, usize)> = Vec::new();
+            for meeting in &entry.course.meetings {
+                // `days` follows `Weekday::ALL`, which is declaration order
+                let day = meeting.day_of_week as usize;
+                for (index, time) in schedule.periods.iter().enumerate() {
+                    if meeting_covers(meeting, time) && !slots.contains(&(day, index)) {
+                        slots.push((day, index));
+                    }
+                }
+            }
+            if slots.is_empty() {
+                unscheduled.push(entry);
+                continue;
+            }
+            for (day, index) in slots {
+                days[day].periods[index].courses.push(entry.clone());
+            }
+        }
+
+        let mut conflicts = Vec::new();
+        for day in &mut days {
+            for cell in &mut day.periods {
+                if cell.courses.len() > 1 {
+                    cell.conflict = true;
+                    conflicts.push(TimetableConflict {
+                        day: day.day,
+                        period: cell.time.period,
+                        course_ids: cell.courses.iter().map(|e| e.course.id.clone()).collect(),
+                    });
+                }
+            }
+        }
+
+        Self { semester, days, conflicts, unscheduled }
+    }
+}
+
+fn meeting_covers(meeting: &CourseMeeting, time: &PeriodTime) -> bool {
+    match (meeting.period, meeting.start_time, meeting.end_time) {
+        (Some(period), _, _) => period == time.period,
+        (None, Some(start), Some(end)) => start < time.end && time.start < end,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn entry(id: &str, meetings: Vec<CourseMeeting>) -> TimetableEntry {
+        TimetableEntry {
+            course: Course {
+                id: id.to_string(),
+                title: id.to_string(),
+                semesters: vec!["2A1".to_string()],
+                meetings,
+                room: None,
+                instructors: Vec::new(),
+                version: 0,
+                is_archived: false,
+                updated_at: Utc::now(),
+                sync_state: "synced".to_string(),
+                last_synced_at: None,
+                notion_page_id: None,
+            },
+            open_todos: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_period_times() {
+        let schedule = PeriodSchedule::parse("08:50-10:30, 10:40-12:20").unwrap();
+        assert_eq!(schedule.periods.len(), 2);
+        assert_eq!(schedule.periods[1].period, 2);
+        assert_eq!(schedule.periods[1].start, NaiveTime::from_hms_opt(10, 40, 0).unwrap());
+
+        assert!(PeriodSchedule::parse("10:30-08:50").is_err());
+        assert!(PeriodSchedule::parse("first period").is_err());
+        assert!(PeriodSchedule::parse("").is_err());
+        assert_eq!(PeriodSchedule::default().periods.len(), 7);
+    }
+
+    #[test]
+    fn test_build_grid_with_conflicts() {
+        let schedule = PeriodSchedule::parse("09:00-10:30,10:40-12:10").unwrap();
+        let timetable = Timetable::build(
+            &schedule,
+            None,
+            vec![
+                entry("physics", vec![CourseMeeting::period(Weekday::Mon, 1)]),
+                entry("math", vec![CourseMeeting::period(Weekday::Mon, 1)]),
+                entry("art", vec![CourseMeeting::period(Weekday::Wed, 2)]),
+                entry("late", vec![CourseMeeting::period(Weekday::Tue, 5)]),
+                entry("online", Vec::new()),
+            ],
+        );
+
+        assert_eq!(timetable.days.len(), 7);
+        assert!(timetable.days[0].periods[0].conflict);
+        assert_eq!(timetable.days[2].periods[1].courses[0].course.id, "art");
+        assert_eq!(timetable.conflicts.len(), 1);
+        assert_eq!(timetable.conflicts[0].course_ids, vec!["physics", "math"]);
+        let unscheduled: Vec<_> = timetable.unscheduled.iter().map(|e| e.course.id.as_str()).collect();
+        assert_eq!(unscheduled, vec!["late", "online"]);
+    }
+
+    #[test]
+    fn test_course_with_several_meetings() {
+        let schedule = PeriodSchedule::parse("09:00-10:30,10:40-12:10,13:00-14:30").unwrap();
+        let lab = CourseMeeting {
+            period: None,
+            start_time: NaiveTime::from_hms_opt(10, 0, 0),
+            end_time: NaiveTime::from_hms_opt(12, 0, 0),
+            ..CourseMeeting::period(Weekday::Thu, 1)
+        };
+        let timetable = Timetable::build(
+            &schedule,
+            None,
+            vec![entry(
+                "chemistry",
+                vec![CourseMeeting::period(Weekday::Mon, 2), CourseMeeting::period(Weekday::Mon, 3), lab],
+            )],
+        );
+
+        let occupied = |day: usize| -> Vec<i32> {
+            timetable.days[day].periods.iter().filter(|c| !c.courses.is_empty()).map(|c| c.time.period).collect()
+        };
+        assert_eq!(occupied(0), vec![2, 3]);
+        assert_eq!(occupied(3), vec![1, 2], "a timed meeting fills every period it overlaps");
+        assert!(timetable.conflicts.is_empty());
+        assert!(timetable.unscheduled.is_empty());
+    }
+}
@@ -1,18 +1,61 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::error::FieldError;
+use super::due_date::DueDate;
+use super::priority::Priority;
+use super::status::TodoStatus;
+use super::subtask::SubtaskProgress;
+use super::validation::{
+    check_due_date, check_due_range, check_not_blank, check_select_names, check_sync_state, check_timezone, check_title,
+    normalize_select_names, Validate,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Todo {
     pub id: String,
     pub course_id: String,
     pub title: String,
-    pub due_date: String,
-    pub status: String,
-    pub completed_at: Option<String>,
+    /// 締め切り。期間がある場合はその始まり
+    pub due_date: DueDate,
+    /// 期間の終わり (複数日にわたる課題や開始・終了時刻のある試験)
+    pub due_end: Option<DueDate>,
+    /// Notion の Date の `time_zone` (IANA 名)。日時は常に UTC で保持する
+    pub due_timezone: Option<String>,
+    pub status: TodoStatus,
+    pub priority: Option<Priority>,
+    /// Notion の "Tags" マルチセレクト (`todo_tags` テーブル)
+    #[sqlx(json)]
+    pub tags: Vec<String>,
+    /// Notion のページ本文 (Markdown)
+    #[serde(default)]
+    pub notes: String,
+    /// サブタスクの進捗 (`subtasks` テーブルから集計)
+    #[serde(default)]
+    #[sqlx(json)]
+    pub progress: SubtaskProgress,
+    /// 繰り返しの系列 (`todo_series`) から作られた回、またはそのテンプレート
+    #[serde(default)]
+    pub series_id: Option<String>,
+    /// 変更のたびに増える版 (`ETag`)。Notion には同期しない
+    #[serde(default)]
+    pub version: i64,
+    pub completed_at: Option<DateTime<Utc>>,
     pub is_archived: bool,
-    pub updated_at: String,
+    pub updated_at: DateTime<Utc>,
     pub sync_state: String,
-    pub last_synced_at: Option<String>,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    /// Notion のページ id (ローカルで作った todo のページ)。`None` なら `id` がページ id
+    #[serde(skip)]
+    pub notion_page_id: Option<String>,
+}
+
+impl Todo {
+    /// Notion のページ id
+    pub fn page_id(&self) -> &str {
+        self.notion_page_id.as_deref().unwrap_or(&self.id)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,12 +63,304 @@ pub struct NewTodoRequest {
     pub course_id: String,
     pub title: String,
     pub due_date: String,
-    pub status: String,
+    #[serde(default)]
+    pub due_end: Option<String>,
+    #[serde(default)]
+    pub due_timezone: Option<String>,
+    #[serde(default)]
+    pub status: TodoStatus,
+    /// `high` / `medium` / `low`
+    #[serde(default)]
+    pub priority: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub notes: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateTodoRequest {
     pub title: Option<String>,
     pub due_date: Option<String>,
+    /// 空文字で期間の終わりを削除
+    #[serde(default)]
+    pub due_end: Option<String>,
+    /// 空文字でタイムゾーンを削除
+    #[serde(default)]
+    pub due_timezone: Option<String>,
+    pub status: Option<TodoStatus>,
+    /// 空文字で優先度を削除
+    #[serde(default)]
+    pub priority: Option<String>,
+    /// 指定した場合はタグをすべて置き換える
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    /// ページ本文 (Markdown) を置き換える。空文字で削除
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+impl Validate for NewTodoRequest {
+    fn normalize(&mut self) {
+        self.title = self.title.trim().to_string();
+        self.due_date = self.due_date.trim().to_string();
+        trim_optional(&mut self.due_end);
+        trim_optional(&mut self.due_timezone);
+        // 空文字は未指定と同じ
+        self.due_end = self.due_end.take().filter(|v| !v.is_empty());
+        self.due_timezone = self.due_timezone.take().filter(|v| !v.is_empty());
+        trim_optional(&mut self.priority);
+        self.priority = self.priority.take().filter(|v| !v.is_empty());
+        normalize_select_names(&mut self.tags);
+        normalize_notes(&mut self.notes);
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        check_not_blank(&mut errors, "course_id", &self.course_id);
+        check_title(&mut errors, "title", &self.title);
+        check_due_date(&mut errors, "due_date", &self.due_date);
+        if let Some(due_end) = &self.due_end {
+            check_due_date(&mut errors, "due_end", due_end);
+            check_due_range(&mut errors, "due_end", &self.due_date, due_end);
+        }
+        if let Some(due_timezone) = &self.due_timezone {
+            check_timezone(&mut errors, "due_timezone", due_timezone);
+        }
+        if let Some(priority) = &self.priority {
+            check_priority(&mut errors, "priority", priority);
+        }
+        check_select_names(&mut errors, "tags", &self.tags);
+        check_notes(&mut errors, "notes", &self.notes);
+        errors
+    }
+}
+
+impl Validate for UpdateTodoRequest {
+    fn normalize(&mut self) {
+        if let Some(title) = &mut self.title {
+            *title = title.trim().to_string();
+        }
+        trim_optional(&mut self.due_date);
+        trim_optional(&mut self.due_end);
+        trim_optional(&mut self.due_timezone);
+        trim_optional(&mut self.priority);
+        if let Some(tags) = &mut self.tags {
+            normalize_select_names(tags);
+        }
+        if let Some(notes) = &mut self.notes {
+            normalize_notes(notes);
+        }
+    }
+
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(title) = &self.title {
+            check_title(&mut errors, "title", title);
+        }
+        if let Some(due_date) = &self.due_date {
+            check_due_date(&mut errors, "due_date", due_date);
+        }
+        if let Some(due_end) = self.due_end.as_deref().filter(|v| !v.is_empty()) {
+            check_due_date(&mut errors, "due_end", due_end);
+            // 片方だけの変更は既存の値と合わせてハンドラー側で確認する
+            if let Some(due_date) = &self.due_date {
+                check_due_range(&mut errors, "due_end", due_date, due_end);
+            }
+        }
+        if let Some(due_timezone) = self.due_timezone.as_deref().filter(|v| !v.is_empty()) {
+            check_timezone(&mut errors, "due_timezone", due_timezone);
+        }
+        if let Some(priority) = self.priority.as_deref().filter(|v| !v.is_empty()) {
+            check_priority(&mut errors, "priority", priority);
+        }
+        if let Some(tags) = &self.tags {
+            check_select_names(&mut errors, "tags", tags);
+        }
+        if let Some(notes) = &self.notes {
+            check_notes(&mut errors, "notes", notes);
+        }
+        errors
+    }
+}
+
+impl UpdateTodoRequest {
+    /// 期間の片側だけを変える場合に、既存の値と合わせて終わりが始まりより前にならないか確認する
+    pub fn check_merged_due_range(&self, current: &Todo) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        let start = self.due_date.clone().unwrap_or_else(|| current.due_date.to_string());
+        let end = match self.due_end.as_deref() {
+            Some("") => return errors,
+            Some(end) => end.to_string(),
+            None => match current.due_end {
+                Some(end) => end.to_string(),
+                None => return errors,
+            },
+        };
+        check_due_range(&mut errors, "due_end", &start, &end);
+        errors
+    }
+}
+
+/// `notes` の最大文字数
+pub const MAX_NOTES_CHARS: usize = 50_000;
+
+/// 改行を LF に揃え、前後の空行と末尾の空白を除去する (行頭のインデントは残す)
+fn normalize_notes(notes: &mut String) {
+    *notes = notes.replace("\r\n", "\n").trim_end().trim_start_matches('\n').to_string();
+}
+
+/// Notion ではページ直下の to-do ブロックがサブタスクになるので、`notes` の先頭レベルのチェックリストは受け付けない
+fn check_notes(errors: &mut Vec<FieldError>, field: &str, notes: &str) {
+    if notes.chars().count() > MAX_NOTES_CHARS {
+        errors.push(FieldError::new(field, format!("must be at most {} characters", MAX_NOTES_CHARS)));
+    }
+    if let Some(line) = top_level_checklist_line(notes) {
+        errors.push(FieldError::new(
+            field,
+            format!("line {}: top-level checklist items belong in subtasks", line),
+        ));
+    }
+}
+
+/// 先頭レベルになるチェックリスト行 (`- [ ] ...`) の行番号 (1 始まり)
+///
+/// インデントしていても、子を持てない行 (見出し・コード・区切り線) の下なら先頭レベルになる。
+fn top_level_checklist_line(notes: &str) -> Option<usize> {
+    let mut in_code = false;
+    // indentation of the enclosing lines and whether they can hold nested blocks
+    let mut parents: Vec<(usize, bool)> = Vec::new();
+    for (i, line) in notes.lines().enumerate() {
+        let content = line.trim_start();
+        if content.is_empty() || (in_code && !content.starts_with("```")) {
+            continue;
+        }
+        let indent: usize = line[..line.len() - content.len()]
+            .chars()
+            .map(|c| if c == '\t' { 2 } else { 1 })
+            .sum();
+        while parents.last().is_some_and(|(width, _)| *width >= indent) {
+            parents.pop();
+        }
+        if content.starts_with("```") {
+            in_code = !in_code;
+            parents.push((indent, false));
+            continue;
+        }
+        let checklist = ["- [ ] ", "- [x] ", "- [X] "].iter().any(|marker| content.starts_with(marker));
+        if checklist && !parents.last().is_some_and(|(_, nests)| *nests) {
+            return Some(i + 1);
+        }
+        let heading = ["# ", "## ", "### "].iter().any(|marker| content.starts_with(marker));
+        parents.push((indent, !heading && content != "---"));
+    }
+    None
+}
+
+fn check_priority(errors: &mut Vec<FieldError>, field: &str, priority: &str) {
+    if let Err(message) = priority.parse::<Priority>() {
+        errors.push(FieldError::new(field, message));
+    }
+}
+
+fn trim_optional(value: &mut Option<String>) {
+    if let Some(v) = value {
+        *v = v.trim().to_string();
+    }
+}
+
+/// `GET /todos` の並び替え対象カラム
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoSortField {
+    DueDate,
+    #[default]
+    UpdatedAt,
+    Title,
+    Status,
+}
+
+impl TodoSortField {
+    pub fn column(self) -> &'static str {
+        match self {
+            TodoSortField::DueDate => "due_date",
+            TodoSortField::UpdatedAt => "updated_at",
+            TodoSortField::Title => "title",
+            TodoSortField::Status => "status",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// `GET /todos` のクエリパラメータ
+///
+/// - `status` はカンマ区切りで複数指定可能 (`status=not_started,in_progress`)
+/// - `due_from` / `due_to` は期間 (`due_date`〜`due_end`) がその範囲と重なる todo を返す
+/// - `semester` はコースの学期名 (`current` で現在の学期)
+/// - `series_id` は繰り返しの系列の id
+/// - `tag` はタグ名 (カンマ区切りでいずれかを持つ todo)
+/// - `q` はタイトルとノートの部分一致
+/// - `archived` 未指定時はアーカイブ済みを除外する
+/// - `cursor` は前ページ最後の todo id (レスポンスの `X-Next-Cursor` ヘッダー)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TodoListQuery {
+    pub course_id: Option<String>,
+    pub semester: Option<String>,
     pub status: Option<String>,
+    pub due_from: Option<String>,
+    pub due_to: Option<String>,
+    pub archived: Option<bool>,
+    pub sync_state: Option<String>,
+    pub series_id: Option<String>,
+    pub tag: Option<String>,
+    pub q: Option<String>,
+    #[serde(default)]
+    pub sort: TodoSortField,
+    #[serde(default)]
+    pub order: SortOrder,
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+impl TodoListQuery {
+    pub fn statuses(&self) -> Vec<String> {
+        split_list(self.status.as_deref())
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        split_list(self.tag.as_deref())
+    }
+}
+
+fn split_list(value: Option<&str>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+impl Validate for TodoListQuery {
+    fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        if let Some(due_from) = &self.due_from {
+            check_due_date(&mut errors, "due_from", due_from);
+        }
+        if let Some(due_to) = &self.due_to {
+            check_due_date(&mut errors, "due_to", due_to);
+        }
+        if let Some(sync_state) = &self.sync_state {
+            check_sync_state(&mut errors, "sync_state", sync_state);
+        }
+        errors
+    }
 }
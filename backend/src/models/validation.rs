@@ -0,0 +1,199 @@
+use chrono::NaiveDate;
+
+use crate::error::FieldError;
+use super::due_date::DueDate;
+
+/// リクエスト型の入力検証
+///
+/// `validate` はエラーを 1 件で打ち切らず、不正なフィールドをすべて返す。
+/// DB を参照する検証 (course_id の存在確認など) はハンドラー側で行う。
+pub trait Validate {
+    /// 検証前の正規化 (前後の空白の除去など)
+    fn normalize(&mut self) {}
+
+    fn validate(&self) -> Vec<FieldError>;
+}
+
+/// 時限の上限
+pub const MAX_PERIOD: i32 = 7;
+
+/// `sync_state` の値
+pub const SYNC_STATES: [&str; 2] = ["pending", "synced"];
+
+/// 空白のみのタイトルを弾く
+pub fn check_title(errors: &mut Vec<FieldError>, field: &str, title: &str) {
+    if title.trim().is_empty() {
+        errors.push(FieldError::new(field, "must not be empty"));
+    }
+}
+
+/// `YYYY-MM-DD` または タイムゾーン付きの RFC 3339 日時のみ受け付ける
+pub fn check_due_date(errors: &mut Vec<FieldError>, field: &str, value: &str) {
+    if !is_valid_due_date(value) {
+        errors.push(FieldError::new(
+            field,
+            format!("'{}' is not an ISO-8601 date (YYYY-MM-DD) or date-time with timezone", value),
+        ));
+    }
+}
+
+/// `YYYY-MM-DD` の日付のみ受け付ける
+pub fn check_date(errors: &mut Vec<FieldError>, field: &str, value: &str) {
+    if NaiveDate::parse_from_str(value, "%Y-%m-%d").is_err() {
+        errors.push(FieldError::new(field, format!("'{}' is not a date (YYYY-MM-DD)", value)));
+    }
+}
+
+/// 期間の終わりが始まりより前でないこと (どちらかが不正な場合は個別の検証に任せる)
+pub fn check_due_range(errors: &mut Vec<FieldError>, field: &str, start: &str, end: &str) {
+    if let (Ok(start), Ok(end)) = (start.parse::<DueDate>(), end.parse::<DueDate>())
+        && end < start
+    {
+        errors.push(FieldError::new(field, format!("must not be before {}", start)));
+    }
+}
+
+/// IANA のタイムゾーン名 (`Asia/Tokyo` など)
+pub fn check_timezone(errors: &mut Vec<FieldError>, field: &str, value: &str) {
+    if value.parse::<chrono_tz::Tz>().is_err() {
+        errors.push(FieldError::new(field, format!("'{}' is not an IANA time zone name", value)));
+    }
+}
+
+pub fn is_valid_due_date(value: &str) -> bool {
+    value.parse::<DueDate>().is_ok()
+}
+
+pub fn check_period(errors: &mut Vec<FieldError>, field: &str, period: i32) {
+    if !(1..=MAX_PERIOD).contains(&period) {
+        errors.push(FieldError::new(field, format!("must be between 1 and {}", MAX_PERIOD)));
+    }
+}
+
+/// マルチセレクトのオプション名の一覧 (空白のみ不可、カンマ不可)
+///
+/// Notion のセレクトのオプション名にはカンマを使えない。
+pub fn check_select_names(errors: &mut Vec<FieldError>, field: &str, names: &[String]) {
+    for name in names {
+        if name.trim().is_empty() {
+            errors.push(FieldError::new(field, "names must not be empty"));
+        } else if name.contains(',') {
+            errors.push(FieldError::new(field, format!("'{}' must not contain a comma", name)));
+        }
+    }
+}
+
+/// 前後の空白を除去し、重複を取り除く (順序は保つ)
+pub fn normalize_select_names(names: &mut Vec<String>) {
+    let mut seen = Vec::new();
+    for name in names.drain(..) {
+        let name = name.trim().to_string();
+        if !seen.contains(&name) {
+            seen.push(name);
+        }
+    }
+    *names = seen;
+}
+
+pub fn check_sync_state(errors: &mut Vec<FieldError>, field: &str, value: &str) {
+    if !SYNC_STATES.contains(&value) {
+        errors.push(FieldError::new(field, format!("must be one of {}", SYNC_STATES.join(", "))));
+    }
+}
+
+pub fn check_not_blank(errors: &mut Vec<FieldError>, field: &str, value: &str) {
+    if value.trim().is_empty() {
+        errors.push(FieldError::new(field, "must not be empty"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CourseMeeting, NewCourseRequest, NewTodoRequest, TodoListQuery, TodoStatus, Weekday};
+
+    #[test]
+    fn test_due_date_formats() {
+        assert!(is_valid_due_date("2026-10-20"));
+        assert!(is_valid_due_date("2026-10-20T23:59:00+09:00"));
+        assert!(is_valid_due_date("2026-10-20T14:59:00Z"));
+        assert!(!is_valid_due_date("tomorrow"));
+        assert!(!is_valid_due_date("2026-10-20T23:59:00"), "datetime without timezone is ambiguous");
+        assert!(!is_valid_due_date("2026-02-30"));
+    }
+
+    #[test]
+    fn test_reports_every_invalid_field() {
+        let todo = NewTodoRequest {
+            course_id: "".to_string(),
+            title: "   ".to_string(),
+            due_date: "tomorrow".to_string(),
+            due_end: None,
+            due_timezone: None,
+            status: TodoStatus::NotStarted,
+            priority: Some("urgent".to_string()),
+            tags: vec!["exam, group".to_string()],
+            notes: String::new(),
+        };
+        let fields: Vec<_> = todo.validate().into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec!["course_id", "title", "due_date", "priority", "tags"]);
+
+        let course = NewCourseRequest {
+            title: "Physics".to_string(),
+            semesters: Vec::new(),
+            meetings: vec![CourseMeeting::period(Weekday::Mon, 0)],
+            room: None,
+            instructors: Vec::new(),
+        };
+        let fields: Vec<_> = course.validate().into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec!["semesters", "meetings[0].period"]);
+
+        let query = TodoListQuery { due_to: Some("soon".to_string()), sync_state: Some("dirty".to_string()), ..Default::default() };
+        let fields: Vec<_> = query.validate().into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec!["due_to", "sync_state"]);
+    }
+
+    #[test]
+    fn test_top_level_checklist_in_notes() {
+        let todo = |notes: &str| NewTodoRequest {
+            course_id: "course-a".to_string(),
+            title: "Report".to_string(),
+            due_date: "2026-10-20".to_string(),
+            due_end: None,
+            due_timezone: None,
+            status: TodoStatus::NotStarted,
+            priority: None,
+            tags: Vec::new(),
+            notes: notes.to_string(),
+        };
+        for notes in ["- [ ] Read chapter 3", "Intro\n\n- [x] done", "## Steps\n  - [ ] under a heading"] {
+            let errors = todo(notes).validate();
+            assert_eq!(errors.len(), 1, "{:?}", notes);
+            assert_eq!(errors[0].field, "notes");
+        }
+        for notes in ["- Steps\n  - [ ] nested", "Intro\n  - [ ] nested", "```\n- [ ] code\n```", "\\- [ ] escaped"] {
+            assert!(todo(notes).validate().is_empty(), "{:?}", notes);
+        }
+    }
+
+    #[test]
+    fn test_due_range_and_timezone() {
+        let mut todo = NewTodoRequest {
+            course_id: "course-a".to_string(),
+            title: "Group project".to_string(),
+            due_date: "2026-10-20".to_string(),
+            due_end: Some("2026-10-19".to_string()),
+            due_timezone: Some("Mars/Olympus".to_string()),
+            status: TodoStatus::NotStarted,
+            priority: None,
+            tags: Vec::new(),
+            notes: String::new(),
+        };
+        let fields: Vec<_> = todo.validate().into_iter().map(|e| e.field).collect();
+        assert_eq!(fields, vec!["due_end", "due_timezone"]);
+
+        todo.due_end = Some("2026-10-20T10:00:00+09:00".to_string());
+        todo.due_timezone = Some("Asia/Tokyo".to_string());
+        assert!(todo.validate().is_empty());
+    }
+}
@@ -36,9 +36,25 @@ pub enum Property {
     Unknown,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct RichText {
     pub plain_text: String,
+    #[serde(default)]
+    pub href: Option<String>,
+    #[serde(default)]
+    pub annotations: Annotations,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Annotations {
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub italic: bool,
+    #[serde(default)]
+    pub strikethrough: bool,
+    #[serde(default)]
+    pub code: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -51,6 +67,8 @@ pub struct DateValue {
     pub start: String,
     #[serde(default)]
     pub end: Option<String>,
+    #[serde(default)]
+    pub time_zone: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -73,4 +91,75 @@ pub struct QueryDatabaseRequest {
 #[derive(Debug, Serialize)]
 pub struct UpdatePageRequest {
     pub properties: serde_json::Value,
-}
\ No newline at end of file
+}
+
+/// `POST /v1/pages` のリクエスト (データベースにページを作る)
+#[derive(Debug, Serialize)]
+pub struct CreatePageRequest {
+    pub parent: serde_json::Value,
+    pub properties: serde_json::Value,
+}
+
+/// `GET /v1/databases/{id}` のレスポンス (スキーマ取得用)
+#[derive(Debug, Deserialize)]
+pub struct DatabaseResponse {
+    pub properties: HashMap<String, DatabaseProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DatabaseProperty {
+    Status { status: StatusSchema },
+    Select { select: SelectSchema },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatusSchema {
+    pub options: Vec<StatusSchemaOption>,
+    pub groups: Vec<StatusSchemaGroup>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SelectSchema {
+    pub options: Vec<SelectOption>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatusSchemaOption {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatusSchemaGroup {
+    pub name: String,
+    pub option_ids: Vec<String>,
+}
+
+/// `GET /v1/blocks/{id}/children` のレスポンス
+#[derive(Debug, Deserialize)]
+pub struct BlockChildrenResponse {
+    pub results: Vec<Block>,
+    pub has_more: bool,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// ページ本文のブロック
+///
+/// 中身は種類ごとに `content[kind]` に入る (`{"type": "paragraph", "paragraph": {...}}`)。
+#[derive(Debug, Clone, Deserialize)]
+pub struct Block {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub has_children: bool,
+    #[serde(flatten)]
+    pub content: HashMap<String, serde_json::Value>,
+    /// 子ブロック (レスポンスには含まれないので取得後に埋める)
+    #[serde(skip)]
+    pub children: Vec<Block>,
+}
@@ -0,0 +1,747 @@
+//! todo の `notes` 用: Notion のページ本文 (ブロック) と Markdown の相互変換
+//!
+//! 対応するブロックは段落、見出し 1〜3、箇条書き、番号付きリスト、to-do、引用、コード、区切り線。
+//! インラインは `**太字**`, `*斜体*`, `~~取り消し線~~`, `` `コード` ``, `[リンク](url)`。
+//! 入れ子は 2 スペースのインデントで表す。
+//! それ以外のブロック (画像、埋め込み、トグル、子ページなど) は Markdown に含めず、push でも削除しない。
+//! ページ直下の to-do ブロックは `notes` ではなくサブタスクになる (`split_subtasks`)。
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+
+use crate::models::SubtaskItem;
+use super::dto::{Annotations, Block, RichText};
+
+/// 1 回の append で送れる入れ子の深さ (トップレベルの下に 2 段まで)
+pub const MAX_DEPTH: usize = 2;
+
+/// Notion の rich text 1 つあたりの最大文字数
+const MAX_TEXT_CHARS: usize = 2000;
+
+const LIST_KINDS: [&str; 3] = ["bulleted_list_item", "numbered_list_item", "to_do"];
+
+pub fn is_supported(kind: &str) -> bool {
+    matches!(
+        kind,
+        "paragraph"
+            | "heading_1"
+            | "heading_2"
+            | "heading_3"
+            | "bulleted_list_item"
+            | "numbered_list_item"
+            | "to_do"
+            | "quote"
+            | "code"
+            | "divider"
+    )
+}
+
+fn can_have_children(kind: &str) -> bool {
+    matches!(kind, "paragraph" | "bulleted_list_item" | "numbered_list_item" | "to_do" | "quote")
+}
+
+pub fn blocks_to_markdown(blocks: &[Block]) -> String {
+    let mut out = String::new();
+    render_blocks(&mut out, blocks, 0);
+    out.trim_end().to_string()
+}
+
+fn render_blocks(out: &mut String, blocks: &[Block], depth: usize) {
+    let indent = "  ".repeat(depth);
+    let mut previous: Option<&str> = None;
+    let mut number = 0;
+
+    for block in blocks.iter().filter(|b| is_supported(&b.kind)) {
+        let kind = block.kind.as_str();
+        let body = block.content.get(kind);
+        let text = body.map(rich_text_markdown).unwrap_or_default();
+        if kind == "paragraph" && text.is_empty() && block.children.is_empty() {
+            continue;
+        }
+        // lists stay tight, every other block is separated by a blank line
+        if let Some(previous) = previous
+            && !(LIST_KINDS.contains(&previous) && LIST_KINDS.contains(&kind))
+        {
+            out.push('\n');
+        }
+        number = if kind == "numbered_list_item" { number + 1 } else { 0 };
+
+        let (marker, text) = match kind {
+            "heading_1" => ("# ".to_string(), text),
+            "heading_2" => ("## ".to_string(), text),
+            "heading_3" => ("### ".to_string(), text),
+            "bulleted_list_item" => ("- ".to_string(), text),
+            "numbered_list_item" => (format!("{}. ", number), text),
+            "to_do" => {
+                let checked = body.and_then(|b| b["checked"].as_bool()).unwrap_or(false);
+                (format!("- [{}] ", if checked { "x" } else { " " }), text)
+            }
+            "quote" => {
+                let quoted = text.lines().map(|l| format!("> {}", l)).collect::<Vec<_>>().join("\n");
+                (String::new(), if quoted.is_empty() { ">".to_string() } else { quoted })
+            }
+            "code" => {
+                let language = body.and_then(|b| b["language"].as_str()).filter(|l| *l != "plain text");
+                let code = body.map(plain_text).unwrap_or_default();
+                (String::new(), format!("```{}\n{}\n```", language.unwrap_or_default(), code))
+            }
+            "divider" => (String::new(), "---".to_string()),
+            _ => (String::new(), text.lines().map(escape_block_marker).collect::<Vec<_>>().join("\n")),
+        };
+
+        // continuation lines of a list item are indented under its marker
+        let continuation = if LIST_KINDS.contains(&kind) { format!("{}  ", indent) } else { indent.clone() };
+        for (i, line) in text.split('\n').enumerate() {
+            if i == 0 {
+                out.push_str(&indent);
+                out.push_str(&marker);
+            } else if kind != "code" || !line.is_empty() {
+                out.push_str(&continuation);
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+
+        if !block.children.is_empty() {
+            // a non-list first child would otherwise read as a continuation line
+            if block.children.iter().find(|c| is_supported(&c.kind)).is_some_and(|c| !LIST_KINDS.contains(&c.kind.as_str())) {
+                out.push('\n');
+            }
+            render_blocks(out, &block.children, depth + 1);
+        }
+        previous = Some(kind);
+    }
+}
+
+fn rich_text_items(body: &Value) -> Vec<RichText> {
+    serde_json::from_value(body["rich_text"].clone()).unwrap_or_default()
+}
+
+fn plain_text(body: &Value) -> String {
+    rich_text_items(body).into_iter().map(|t| t.plain_text).collect()
+}
+
+fn rich_text_markdown(body: &Value) -> String {
+    let mut out = String::new();
+    for item in rich_text_items(body) {
+        let style = &item.annotations;
+        let mut text = if style.code { format!("`{}`", item.plain_text) } else { escape_inline(&item.plain_text) };
+        if style.italic {
+            text = format!("*{}*", text);
+        }
+        if style.bold {
+            text = format!("**{}**", text);
+        }
+        if style.strikethrough {
+            text = format!("~~{}~~", text);
+        }
+        if let Some(href) = &item.href {
+            text = format!("[{}]({})", text, href);
+        }
+        out.push_str(&text);
+    }
+    out
+}
+
+fn escape_inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '`' | '~' | '[') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// 段落の行が見出しやリストとして読まれないようにする
+fn escape_block_marker(line: &str) -> String {
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && line[digits..].starts_with(". ") {
+        return format!("{}\\{}", &line[..digits], &line[digits..]);
+    }
+    if line.starts_with('#') || line.starts_with("- ") || line.starts_with('>') || line == "---" {
+        return format!("\\{}", line);
+    }
+    line.to_string()
+}
+
+/// ページ直下の to-do ブロックをサブタスクとして取り出し、残りのブロックと分ける
+pub fn split_subtasks(blocks: Vec<Block>) -> (Vec<Block>, Vec<SubtaskItem>) {
+    let mut rest = Vec::new();
+    let mut subtasks = Vec::new();
+    for block in blocks {
+        match block.content.get("to_do").filter(|_| block.kind == "to_do") {
+            Some(body) => subtasks.push(SubtaskItem {
+                title: plain_text(body).trim().to_string(),
+                done: body["checked"].as_bool().unwrap_or(false),
+            }),
+            None => rest.push(block),
+        }
+    }
+    (rest, subtasks)
+}
+
+/// サブタスクを to-do ブロックにする (タイトルは装飾なしのテキスト)
+pub fn subtask_block(item: &SubtaskItem) -> Value {
+    let mut rich_text = Vec::new();
+    push_text(&mut rich_text, &item.title, None, &Annotations::default());
+    json!({
+        "object": "block",
+        "type": "to_do",
+        "to_do": { "rich_text": rich_text, "checked": item.done }
+    })
+}
+
+/// ページ本文の書き換え手順
+///
+/// `appends` はそれぞれ `after` のブロックの後ろ (`None` はページの末尾) に追加する。
+/// 追加がすべて成功してから `deletes` を削除するので、途中で失敗しても元の本文は残る。
+#[derive(Debug, Default, PartialEq)]
+pub struct BodyPatch {
+    pub appends: Vec<(Option<String>, Vec<Value>)>,
+    pub deletes: Vec<String>,
+}
+
+/// ページ直下の `blocks` を `notes` とサブタスクにするための差分
+///
+/// 内容が同じブロックはそのまま残し、対応しないブロック (画像など) には触れない。
+/// 追加するブロックは直前に残るブロックの後ろに入れるので、残るブロックの並びは変わらない。
+/// サブタスクと notes のブロックが交互に並んだページでも、その並びを保つ。
+pub fn diff_body(blocks: &[Block], notes: &str, subtasks: &[SubtaskItem]) -> BodyPatch {
+    let existing: Vec<&Block> = blocks.iter().filter(|b| is_supported(&b.kind)).collect();
+    let have: Vec<String> = existing.iter().map(|b| block_key(b)).collect();
+    let desired = interleave(
+        &have,
+        markdown_to_blocks(notes),
+        subtasks.iter().map(subtask_block).collect(),
+    );
+    let want: Vec<String> = desired.iter().map(|v| block_key(&response_block(v))).collect();
+    let mut kept = common_blocks(&have, &want);
+
+    // nothing can be inserted before the first block of the page, so move it instead
+    while let Some(first) = kept.iter().position(Option::is_some)
+        && first > 0
+        && kept[first].is_some_and(|e| existing[e].id == blocks[0].id)
+    {
+        kept[first] = None;
+    }
+
+    let mut patch = BodyPatch::default();
+    let mut previous: Option<&str> = None;
+    let mut run = Vec::new();
+    for (value, kept) in desired.into_iter().zip(&kept) {
+        let Some(e) = *kept else {
+            run.push(value);
+            continue;
+        };
+        if !run.is_empty() {
+            // right before the kept block: after whatever precedes it on the page
+            let after = previous.or_else(|| {
+                let position = blocks.iter().position(|b| b.id == existing[e].id)?;
+                position.checked_sub(1).map(|p| blocks[p].id.as_str())
+            });
+            patch.appends.push((after.map(str::to_string), std::mem::take(&mut run)));
+        }
+        previous = Some(existing[e].id.as_str());
+    }
+    if !run.is_empty() {
+        // with nothing kept the new body takes the place of the old one
+        let after = previous.or_else(|| existing.first().map(|b| b.id.as_str()));
+        patch.appends.push((after.map(str::to_string), run));
+    }
+
+    let kept: Vec<usize> = kept.into_iter().flatten().collect();
+    patch.deletes = existing
+        .iter()
+        .enumerate()
+        .filter(|(e, _)| !kept.contains(e))
+        .map(|(_, b)| b.id.clone())
+        .collect();
+    patch
+}
+
+/// notes のブロックとサブタスクを 1 列に並べる
+///
+/// 今のページにあるブロックはその位置の順に、新しいブロックは同じ側の直前のブロックに続けて並べる。
+/// どちらも今のページに無ければ notes が先になる。
+fn interleave(have: &[String], notes: Vec<Value>, subtasks: Vec<Value>) -> Vec<Value> {
+    // position on the page of the last block at or before each one that is already there
+    let positions = |blocks: &[Value]| -> Vec<Option<usize>> {
+        let keys: Vec<String> = blocks.iter().map(|v| block_key(&response_block(v))).collect();
+        let mut last = None;
+        common_blocks(have, &keys)
+            .into_iter()
+            .map(|kept| {
+                last = kept.or(last);
+                last
+            })
+            .collect()
+    };
+    let (notes_at, subtasks_at) = (positions(&notes), positions(&subtasks));
+    let mut notes = notes.into_iter().zip(notes_at).peekable();
+    let mut subtasks = subtasks.into_iter().zip(subtasks_at).peekable();
+
+    let mut out = Vec::new();
+    loop {
+        let next = match (notes.peek(), subtasks.peek()) {
+            (Some((_, n)), Some((_, s))) if n <= s => notes.next(),
+            (_, Some(_)) => subtasks.next(),
+            (Some(_), None) => notes.next(),
+            (None, None) => break,
+        };
+        out.extend(next.map(|(value, _)| value));
+    }
+    out
+}
+
+/// ブロックの内容の比較用の文字列 (ページ直下の to-do はサブタスクとして、それ以外は Markdown で比べる)
+fn block_key(block: &Block) -> String {
+    match block.content.get("to_do").filter(|_| block.kind == "to_do") {
+        Some(body) => format!(
+            "- [{}] {}",
+            if body["checked"].as_bool().unwrap_or(false) { "x" } else { " " },
+            plain_text(body).trim()
+        ),
+        None => blocks_to_markdown(std::slice::from_ref(block)),
+    }
+}
+
+/// 最長共通部分列: `want` の各要素に対応する `have` の位置
+fn common_blocks(have: &[String], want: &[String]) -> Vec<Option<usize>> {
+    let mut lengths = vec![vec![0usize; want.len() + 1]; have.len() + 1];
+    for i in (0..have.len()).rev() {
+        for j in (0..want.len()).rev() {
+            lengths[i][j] = if have[i] == want[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let mut kept = vec![None; want.len()];
+    let (mut i, mut j) = (0, 0);
+    while i < have.len() && j < want.len() {
+        if have[i] == want[j] {
+            kept[j] = Some(i);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    kept
+}
+
+/// push する形のブロックを Notion が返す形 (`plain_text` / `href` 付き) にする
+fn response_block(block: &Value) -> Block {
+    let kind = block["type"].as_str().unwrap_or_default().to_string();
+    let mut body = block[kind.as_str()].clone();
+    let children = body["children"].as_array().map(|c| c.iter().map(response_block).collect()).unwrap_or_default();
+    if let Some(items) = body["rich_text"].as_array_mut() {
+        for item in items {
+            item["plain_text"] = item["text"]["content"].clone();
+            item["href"] = item["text"]["link"]["url"].clone();
+        }
+    }
+    let mut content = HashMap::new();
+    content.insert(kind.clone(), body);
+    Block { id: String::new(), kind, has_children: false, content, children }
+}
+
+/// Markdown を `PATCH /v1/blocks/{id}/children` の `children` に変換する
+pub fn markdown_to_blocks(markdown: &str) -> Vec<Value> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut flat: Vec<(usize, Value)> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let indent = indent_width(line);
+        let level = indent / 2;
+        let content = line.trim_start();
+
+        if let Some(language) = content.strip_prefix("```") {
+            let mut code = Vec::new();
+            while i < lines.len() && lines[i].trim() != "```" {
+                code.push(strip_indent(lines[i], indent));
+                i += 1;
+            }
+            i += 1;
+            flat.push((level, code_block(language.trim(), &code.join("\n"))));
+            continue;
+        }
+        if content == "---" {
+            flat.push((level, json!({ "object": "block", "type": "divider", "divider": {} })));
+            continue;
+        }
+
+        let (kind, mut text, checked) = classify(content);
+        match kind {
+            "paragraph" => {
+                while let Some(next) = lines.get(i)
+                    && !next.trim().is_empty()
+                    && indent_width(next) == indent
+                    && !starts_block(next.trim_start())
+                {
+                    text = format!("{}\n{}", text, next.trim_start());
+                    i += 1;
+                }
+            }
+            "quote" => {
+                while let Some(next) = lines.get(i)
+                    && indent_width(next) == indent
+                    && next.trim_start().starts_with('>')
+                {
+                    let (_, more, _) = classify(next.trim_start());
+                    text = format!("{}\n{}", text, more);
+                    i += 1;
+                }
+            }
+            _ if LIST_KINDS.contains(&kind) => {
+                while let Some(next) = lines.get(i)
+                    && !next.trim().is_empty()
+                    && indent_width(next) == indent + 2
+                    && !starts_block(next.trim_start())
+                {
+                    text = format!("{}\n{}", text, next.trim_start());
+                    i += 1;
+                }
+            }
+            _ => {}
+        }
+
+        let mut block = text_block(kind, &text);
+        if let Some(checked) = checked {
+            block[kind]["checked"] = json!(checked);
+        }
+        flat.push((level, block));
+    }
+
+    nest(flat)
+}
+
+/// 行の種類と本文 (`to_do` はチェック状態も)
+fn classify(content: &str) -> (&'static str, String, Option<bool>) {
+    for (prefix, kind) in [("### ", "heading_3"), ("## ", "heading_2"), ("# ", "heading_1")] {
+        if let Some(rest) = content.strip_prefix(prefix) {
+            return (kind, rest.to_string(), None);
+        }
+    }
+    for (prefix, checked) in [("- [ ] ", false), ("- [x] ", true), ("- [X] ", true)] {
+        if let Some(rest) = content.strip_prefix(prefix) {
+            return ("to_do", rest.to_string(), Some(checked));
+        }
+    }
+    if let Some(rest) = content.strip_prefix("- ").or_else(|| content.strip_prefix("* ")) {
+        return ("bulleted_list_item", rest.to_string(), None);
+    }
+    let digits = content.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0
+        && let Some(rest) = content[digits..].strip_prefix(". ")
+    {
+        return ("numbered_list_item", rest.to_string(), None);
+    }
+    if let Some(rest) = content.strip_prefix('>') {
+        return ("quote", rest.strip_prefix(' ').unwrap_or(rest).to_string(), None);
+    }
+    ("paragraph", content.to_string(), None)
+}
+
+fn starts_block(content: &str) -> bool {
+    content.starts_with("```") || content == "---" || classify(content).0 != "paragraph"
+}
+
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 2 } else { 1 })
+        .sum()
+}
+
+fn strip_indent(line: &str, width: usize) -> String {
+    let skip = line.chars().take(width).take_while(|c| *c == ' ').count();
+    line[skip..].to_string()
+}
+
+/// インデントの深さに従って子ブロックに入れる (入れ子にできないブロックの下は兄弟にする)
+fn nest(flat: Vec<(usize, Value)>) -> Vec<Value> {
+    let mut roots = Vec::new();
+    let mut stack: Vec<(usize, Value)> = Vec::new();
+
+    for (level, block) in flat {
+        let max_level = match stack.last() {
+            Some((top, parent)) if can_have_children(parent["type"].as_str().unwrap_or_default()) => top + 1,
+            Some((top, _)) => *top,
+            None => 0,
+        };
+        let level = level.min(max_level).min(MAX_DEPTH);
+        while stack.last().is_some_and(|(top, _)| *top >= level) {
+            close(&mut stack, &mut roots);
+        }
+        stack.push((level, block));
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
+    }
+    roots
+}
+
+fn close(stack: &mut Vec<(usize, Value)>, roots: &mut Vec<Value>) {
+    let Some((_, block)) = stack.pop() else {
+        return;
+    };
+    match stack.last_mut() {
+        Some((_, parent)) => {
+            let kind = parent["type"].as_str().unwrap_or_default().to_string();
+            let children = &mut parent[kind.as_str()]["children"];
+            if !children.is_array() {
+                *children = json!([]);
+            }
+            if let Some(children) = children.as_array_mut() {
+                children.push(block);
+            }
+        }
+        None => roots.push(block),
+    }
+}
+
+fn text_block(kind: &str, text: &str) -> Value {
+    let mut block = json!({ "object": "block", "type": kind });
+    block[kind] = json!({ "rich_text": parse_inline(text) });
+    block
+}
+
+fn code_block(language: &str, code: &str) -> Value {
+    let language = if language.is_empty() { "plain text".to_string() } else { language.to_lowercase() };
+    let mut rich_text = Vec::new();
+    push_text(&mut rich_text, code, None, &Annotations::default());
+    json!({
+        "object": "block",
+        "type": "code",
+        "code": { "rich_text": rich_text, "language": language }
+    })
+}
+
+/// インラインの Markdown を rich text に変換する
+fn parse_inline(text: &str) -> Vec<Value> {
+    let mut out = Vec::new();
+    parse_inline_into(text, None, &mut Annotations::default(), &mut out);
+    out
+}
+
+fn parse_inline_into(text: &str, href: Option<&str>, style: &mut Annotations, out: &mut Vec<Value>) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut buf = String::new();
+    let mut i = 0;
+    // a delimiter only opens a span when it is closed later on
+    let closes_later = |from: usize, delimiter: &str| chars[from..].iter().collect::<String>().contains(delimiter);
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if style.code {
+            if c == '`' {
+                push_text(out, &std::mem::take(&mut buf), href, style);
+                style.code = false;
+            } else {
+                buf.push(c);
+            }
+            i += 1;
+            continue;
+        }
+        match c {
+            '\\' if next.is_some_and(|n| n.is_ascii_punctuation()) => {
+                buf.push(chars[i + 1]);
+                i += 2;
+            }
+            '`' if closes_later(i + 1, "`") => {
+                push_text(out, &std::mem::take(&mut buf), href, style);
+                style.code = true;
+                i += 1;
+            }
+            '*' if next == Some('*') && (style.bold || closes_later(i + 2, "**")) => {
+                push_text(out, &std::mem::take(&mut buf), href, style);
+                style.bold = !style.bold;
+                i += 2;
+            }
+            '*' if next != Some('*') && (style.italic || closes_later(i + 1, "*")) => {
+                push_text(out, &std::mem::take(&mut buf), href, style);
+                style.italic = !style.italic;
+                i += 1;
+            }
+            '~' if next == Some('~') && (style.strikethrough || closes_later(i + 2, "~~")) => {
+                push_text(out, &std::mem::take(&mut buf), href, style);
+                style.strikethrough = !style.strikethrough;
+                i += 2;
+            }
+            '[' if href.is_none() && find_link(&chars, i).is_some() => {
+                let (label_end, url_end) = find_link(&chars, i).unwrap_or_default();
+                push_text(out, &std::mem::take(&mut buf), href, style);
+                let label: String = chars[i + 1..label_end].iter().collect();
+                let url: String = chars[label_end + 2..url_end].iter().collect();
+                parse_inline_into(&label, Some(&url), style, out);
+                i = url_end + 1;
+            }
+            _ => {
+                buf.push(c);
+                i += 1;
+            }
+        }
+    }
+    push_text(out, &buf, href, style);
+}
+
+/// `[label](url)` の `]` と `)` の位置
+fn find_link(chars: &[char], open: usize) -> Option<(usize, usize)> {
+    let mut i = open + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            ']' => break,
+            _ => i += 1,
+        }
+    }
+    if chars.get(i + 1) != Some(&'(') {
+        return None;
+    }
+    let url_end = (i + 2..chars.len()).find(|&j| chars[j] == ')')?;
+    Some((i, url_end))
+}
+
+fn push_text(out: &mut Vec<Value>, text: &str, href: Option<&str>, style: &Annotations) {
+    let chars: Vec<char> = text.chars().collect();
+    for chunk in chars.chunks(MAX_TEXT_CHARS) {
+        let content: String = chunk.iter().collect();
+        out.push(json!({
+            "type": "text",
+            "text": { "content": content, "link": href.map(|url| json!({ "url": url })) },
+            "annotations": {
+                "bold": style.bold,
+                "italic": style.italic,
+                "strikethrough": style.strikethrough,
+                "code": style.code
+            }
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn as_response(block: &Value) -> Block {
+        Block { id: "block".to_string(), ..response_block(block) }
+    }
+
+    fn round_trip(markdown: &str) -> String {
+        let blocks: Vec<Block> = markdown_to_blocks(markdown).iter().map(as_response).collect();
+        blocks_to_markdown(&blocks)
+    }
+
+    #[test]
+    fn test_markdown_round_trip() {
+        let notes = "# Report\n\n\
+            Submit a **PDF** via [the LMS](https://lms.example.com/a_1) by *Friday*.\n\
+            Use `pandoc` if you like.\n\n\
+            - Introduction\n\
+            - Results\n  \
+              - Figures\n  \
+              - ~~Tables~~\n\
+            1. Draft\n\
+            2. Review\n\
+            - [x] Read chapter 3\n\
+            - [ ] Write summary\n  \
+              at least two pages\n\n\
+            > Late submissions\n\
+            > are not accepted\n\n\
+            ```rust\nfn main() {}\n```\n\n\
+            ---\n\n\
+            Price: 5 \\* 3 \\[approx]";
+        assert_eq!(round_trip(notes), notes);
+    }
+
+    #[test]
+    fn test_inline_styles() {
+        let items = parse_inline("a **b *c*** [d](https://x.test) 2 * 3");
+        let texts: Vec<_> = items.iter().map(|i| i["text"]["content"].as_str().unwrap()).collect();
+        assert_eq!(texts, vec!["a ", "b ", "c", " ", "d", " 2 * 3"]);
+        assert_eq!(items[2]["annotations"]["bold"], true);
+        assert_eq!(items[2]["annotations"]["italic"], true);
+        assert_eq!(items[4]["text"]["link"]["url"], "https://x.test");
+        assert_eq!(items[5]["annotations"]["italic"], false, "an unclosed * is literal");
+    }
+
+    #[test]
+    fn test_paragraph_that_looks_like_a_list() {
+        let blocks = markdown_to_blocks("\\- not a list\n2026\\. is a year");
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0]["type"], "paragraph");
+        assert_eq!(round_trip("\\- not a list\n2026\\. is a year"), "\\- not a list\n2026\\. is a year");
+    }
+
+    #[test]
+    fn test_nesting_is_capped() {
+        let blocks = markdown_to_blocks("- a\n  - b\n    - c\n      - d\n## heading\n  - e");
+        assert_eq!(blocks.len(), 3);
+        let b = &blocks[0]["bulleted_list_item"]["children"][0];
+        let c = &b["bulleted_list_item"]["children"];
+        assert_eq!(c.as_array().unwrap().len(), 2, "d is attached next to c");
+        assert_eq!(blocks[2]["type"], "bulleted_list_item", "headings have no children");
+    }
+
+    #[test]
+    fn test_top_level_to_dos_are_subtasks() {
+        let notes = "Intro\n\n- Steps\n  - [ ] nested to-dos stay in notes";
+        let item = SubtaskItem { title: "Read *chapter* 3".to_string(), done: true };
+        let mut blocks: Vec<Block> = markdown_to_blocks(notes).iter().map(as_response).collect();
+        blocks.push(as_response(&subtask_block(&item)));
+
+        let (rest, subtasks) = split_subtasks(blocks);
+        assert_eq!(subtasks, vec![item]);
+        assert_eq!(blocks_to_markdown(&rest), notes, "only the top-level to-dos are taken out");
+    }
+
+    #[test]
+    fn test_diff_body_keeps_unchanged_blocks_in_place() {
+        let page = |items: &[(&str, Value)]| -> Vec<Block> {
+            items.iter().map(|(id, v)| Block { id: id.to_string(), ..response_block(v) }).collect()
+        };
+        let image = json!({ "type": "image", "image": {} });
+        let task = SubtaskItem { title: "Read".to_string(), done: false };
+        let blocks = page(&[
+            ("img", image.clone()),
+            ("intro", text_block("paragraph", "Intro")),
+            ("task", subtask_block(&task)),
+            ("old", text_block("paragraph", "Old")),
+        ]);
+
+        let patch = diff_body(&blocks, "Intro\n\nNew", std::slice::from_ref(&task));
+        assert_eq!(patch.appends.len(), 1);
+        assert_eq!(patch.appends[0].0.as_deref(), Some("intro"), "new notes go right after the kept paragraph");
+        assert_eq!(patch.appends[0].1, vec![text_block("paragraph", "New")]);
+        assert_eq!(patch.deletes, vec!["old"], "the image is never touched");
+
+        // the first block of the page cannot have anything inserted before it, so it is moved
+        let blocks = page(&[("b", text_block("paragraph", "B"))]);
+        let patch = diff_body(&blocks, "A\n\nB", &[]);
+        assert_eq!(patch.appends, vec![(Some("b".to_string()), markdown_to_blocks("A\n\nB"))]);
+        assert_eq!(patch.deletes, vec!["b"]);
+
+        // subtasks above the notes stay above them
+        let blocks = page(&[("task", subtask_block(&task)), ("x", text_block("paragraph", "X"))]);
+        let patch = diff_body(&blocks, "X\n\nZ", std::slice::from_ref(&task));
+        assert_eq!(patch.appends, vec![(Some("x".to_string()), vec![text_block("paragraph", "Z")])]);
+        assert!(patch.deletes.is_empty());
+
+        assert_eq!(diff_body(&[], "A", &[]).appends, vec![(None, markdown_to_blocks("A"))]);
+        assert_eq!(diff_body(&page(&[("img", image)]), "", &[]), BodyPatch::default());
+    }
+}
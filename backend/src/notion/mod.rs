@@ -1,12 +1,19 @@
 pub mod dto;
+pub mod markdown;
 
+use std::collections::HashMap;
 use std::env;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::RwLock;
 
 use async_trait::async_trait;
-use chrono::Utc;
+use chrono::{SecondsFormat, Utc};
 use reqwest::Client;
 
 use crate::error::AppError;
+use crate::models::meeting::{format_meetings, parse_meetings};
+use crate::models::{parse_notion_datetime, CourseMeeting, DueDate, Priority, StatusGroup, StatusMapping, SubtaskItem, TodoStatus, Weekday};
 
 #[derive(Clone, Debug)]
 pub struct NotionConfig {
@@ -38,11 +45,48 @@ pub trait NotionClient: Send + Sync {
     async fn fetch_todos(&self) -> Result<Vec<crate::models::Todo>, AppError>;
     async fn push_course(&self, course: &crate::models::Course) -> Result<(), AppError>;
     async fn push_todo(&self, todo: &crate::models::Todo) -> Result<(), AppError>;
+    /// Notion にまだ無い授業 (ローカルで作った授業) のページを作り、ページの id を返す
+    ///
+    /// 授業の id はそのまま使い、ページの "course_id" プロパティに書く
+    async fn create_course(&self, course: &crate::models::Course) -> Result<String, AppError>;
+    /// Notion にまだ無い todo (ローカルで作った todo や繰り返しの回) のページを作り、ページの id を返す
+    ///
+    /// todo の id はそのまま使い、ページの "todo_id" プロパティに書く。
+    /// Course のリレーションは授業のページ (`Course::page_id()`) を指す
+    async fn create_todo(&self, todo: &crate::models::Todo, course_page_id: &str) -> Result<String, AppError>;
+    /// Todos データベースの Status オプションから `StatusMapping` を構築する
+    async fn fetch_status_mapping(&self) -> Result<StatusMapping, AppError>;
+    /// todo ページ (`Todo::page_id()`) の本文を取得する (Markdown の `notes` と直下の to-do ブロックのサブタスク)
+    async fn fetch_todo_body(&self, page_id: &str) -> Result<TodoBody, AppError>;
+    /// todo ページ (`Todo::page_id()`) の本文を置き換える (対応外のブロックは残す)
+    async fn push_todo_body(&self, page_id: &str, body: &TodoBody) -> Result<(), AppError>;
 }
 
+/// todo ページの本文。サブタスクは to-do ブロックとして `notes` の後ろに並べる (既存のページでは今の並びを保つ)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TodoBody {
+    pub notes: String,
+    pub subtasks: Vec<SubtaskItem>,
+}
+
+impl TodoBody {
+    fn from_blocks(blocks: Vec<dto::Block>) -> Self {
+        let (rest, subtasks) = markdown::split_subtasks(blocks);
+        Self { notes: markdown::blocks_to_markdown(&rest), subtasks }
+    }
+}
+
+/// プロパティ名 → セレクトのオプション名 (セレクト以外は空)
+type DatabaseSchema = HashMap<String, Vec<String>>;
+
+type BlocksFuture<'a> = Pin<Box<dyn Future<Output = Result<Vec<dto::Block>, AppError>> + Send + 'a>>;
+
 pub struct NotionHttpClient {
     client: Client,
     config: NotionConfig,
+    status_mapping: RwLock<StatusMapping>,
+    /// データベース ID ごとのプロパティ名とセレクトのオプション名 (存在しないプロパティには書き込まない)
+    schemas: RwLock<HashMap<String, DatabaseSchema>>,
 }
 
 impl NotionHttpClient {
@@ -50,7 +94,73 @@ impl NotionHttpClient {
         let client = Client::builder()
             .build()
             .map_err(|e| AppError::BadRequest(format!("Failed to build http client: {}", e)))?;
-        Ok(Self { client, config })
+        Ok(Self {
+            client,
+            config,
+            status_mapping: RwLock::new(StatusMapping::new_from_env()),
+            schemas: RwLock::new(HashMap::new()),
+        })
+    }
+
+    fn status_mapping(&self) -> StatusMapping {
+        self.status_mapping
+            .read()
+            .map(|m| m.clone())
+            .unwrap_or_else(|e| e.into_inner().clone())
+    }
+
+    async fn fetch_database(&self, database_id: &str) -> Result<dto::DatabaseResponse, AppError> {
+        let url = format!("https://api.notion.com/v1/databases/{}", database_id);
+
+        let response = self.client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", self.config.api_token))
+            .header("Notion-Version", "2022-06-28")
+            .send()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::BadRequest(format!("Notion API error {}: {}", status, body)));
+        }
+
+        let database = response
+            .json::<dto::DatabaseResponse>()
+            .await
+            .map_err(|e| AppError::BadRequest(format!("Failed to parse Notion database: {}", e)))?;
+        let schema: DatabaseSchema = database.properties
+            .iter()
+            .map(|(key, prop)| {
+                let options = match prop {
+                    dto::DatabaseProperty::Select { select } => select.options.iter().map(|o| o.name.clone()).collect(),
+                    _ => Vec::new(),
+                };
+                (key.clone(), options)
+            })
+            .collect();
+        if let Ok(mut schemas) = self.schemas.write() {
+            schemas.insert(database_id.to_string(), schema);
+        }
+        Ok(database)
+    }
+
+    /// データベースのプロパティ (初回だけスキーマを取得する)
+    async fn database_schema(&self, database_id: &str) -> Result<DatabaseSchema, AppError> {
+        let cached = self.schemas
+            .read()
+            .ok()
+            .and_then(|schemas| schemas.get(database_id).cloned());
+        if let Some(schema) = cached {
+            return Ok(schema);
+        }
+        self.fetch_database(database_id).await?;
+        Ok(self.schemas
+            .read()
+            .ok()
+            .and_then(|schemas| schemas.get(database_id).cloned())
+            .unwrap_or_default())
     }
 
     async fn query_database(&self, database_id: &str) -> Result<dto::QueryDatabaseResponse, AppError> {
@@ -70,7 +180,7 @@ impl NotionHttpClient {
             .json(&request_body)
             .send()
             .await
-            .map_err(|e| AppError::InternalServerError)?;
+            .map_err(|_| AppError::InternalServerError)?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -79,7 +189,7 @@ impl NotionHttpClient {
         }
 
         let body_text = response.text().await.unwrap_or_default();
-        let filename = if database_id == &self.config.courses_db_id {
+        let filename = if database_id == self.config.courses_db_id {
             "notion_courses_response.json"
         } else {
             "notion_todos_response.json"
@@ -96,61 +206,343 @@ impl NotionHttpClient {
         })
     }
 
+    async fn fetch_block_children(&self, block_id: &str) -> Result<Vec<dto::Block>, AppError> {
+        let url = format!("https://api.notion.com/v1/blocks/{}/children", block_id);
+        let mut blocks = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let page_url = match &cursor {
+                Some(cursor) => format!("{}?page_size=100&start_cursor={}", url, cursor),
+                None => format!("{}?page_size=100", url),
+            };
+            let response = self.client
+                .get(&page_url)
+                .header("Authorization", format!("Bearer {}", self.config.api_token))
+                .header("Notion-Version", "2022-06-28")
+                .send()
+                .await
+                .map_err(|_| AppError::InternalServerError)?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(AppError::BadRequest(format!("Notion API error {}: {}", status, body)));
+            }
+
+            let page = response
+                .json::<dto::BlockChildrenResponse>()
+                .await
+                .map_err(|e| AppError::BadRequest(format!("Failed to parse Notion blocks: {}", e)))?;
+            blocks.extend(page.results);
+            match page.next_cursor {
+                Some(next) if page.has_more => cursor = Some(next),
+                _ => break,
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// `after` のブロックの後ろ (`None` は末尾) に追加し、追加したブロックの ID を `appended` に積む
+    async fn append_blocks(
+        &self,
+        block_id: &str,
+        mut after: Option<String>,
+        children: &[serde_json::Value],
+        appended: &mut Vec<String>,
+    ) -> Result<(), AppError> {
+        // append accepts at most 100 blocks per request
+        let url = format!("https://api.notion.com/v1/blocks/{}/children", block_id);
+        for children in children.chunks(100) {
+            let mut request_body = serde_json::json!({ "children": children });
+            if let Some(after) = &after {
+                request_body["after"] = serde_json::json!(after);
+            }
+            let response = self.client
+                .patch(&url)
+                .header("Authorization", format!("Bearer {}", self.config.api_token))
+                .header("Notion-Version", "2022-06-28")
+                .json(&request_body)
+                .send()
+                .await
+                .map_err(|_| AppError::InternalServerError)?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(AppError::BadRequest(format!("Failed to push todo body to Notion: {} {}", status, body)));
+            }
+
+            let added = response
+                .json::<dto::BlockChildrenResponse>()
+                .await
+                .map_err(|e| AppError::BadRequest(format!("Failed to parse Notion blocks: {}", e)))?;
+            // the next chunk goes after the last block of this one
+            if after.is_some() {
+                after = added.results.last().map(|b| b.id.clone());
+            }
+            appended.extend(added.results.into_iter().map(|b| b.id));
+        }
+        Ok(())
+    }
+
+    async fn delete_block(&self, block_id: &str) -> Result<(), AppError> {
+        let url = format!("https://api.notion.com/v1/blocks/{}", block_id);
+        let response = self.client
+            .delete(&url)
+            .header("Authorization", format!("Bearer {}", self.config.api_token))
+            .header("Notion-Version", "2022-06-28")
+            .send()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::BadRequest(format!("Failed to delete Notion block: {} {}", status, body)));
+        }
+        Ok(())
+    }
+
+    /// 子ブロックも `markdown::MAX_DEPTH` 段まで辿って取得する
+    fn fetch_blocks<'a>(&'a self, block_id: &'a str, depth: usize) -> BlocksFuture<'a> {
+        Box::pin(async move {
+            let mut blocks = self.fetch_block_children(block_id).await?;
+            if depth < markdown::MAX_DEPTH {
+                for block in blocks.iter_mut().filter(|b| b.has_children && markdown::is_supported(&b.kind)) {
+                    block.children = self.fetch_blocks(&block.id, depth + 1).await?;
+                }
+            }
+            Ok(blocks)
+        })
+    }
+
+    /// 授業のプロパティ (更新と作成で共通)
+    async fn course_properties(&self, course: &crate::models::Course) -> Result<serde_json::Value, AppError> {
+        let mut properties = serde_json::json!({});
+
+        properties["Name"] = serde_json::json!({
+            "title": [{
+                "text": {
+                    "content": course.title
+                }
+            }]
+        });
+
+        let semester_items: Vec<serde_json::Value> = course.semesters
+            .iter()
+            .map(|name| serde_json::json!({ "name": name }))
+            .collect();
+        properties["Semester"] = serde_json::json!({
+            "multi_select": semester_items
+        });
+
+        // Meetings / Day / Period は任意の列なので、データベースにあるものだけ書き込む
+        let schema = self.database_schema(&self.config.courses_db_id).await?;
+        if schema.contains_key("Meetings") {
+            properties["Meetings"] = serde_json::json!({
+                "rich_text": [{
+                    "text": { "content": format_meetings(&course.meetings) }
+                }]
+            });
+        }
+
+        // Day / Period keep showing the first day's periods for views built on them
+        let first_day = course.meetings.first().map(|m| m.day_of_week);
+        let period_items: Vec<serde_json::Value> = course.meetings
+            .iter()
+            .filter(|m| Some(m.day_of_week) == first_day)
+            .filter_map(|m| m.period)
+            .map(|period| serde_json::json!({ "name": period.to_string() }))
+            .collect();
+        if schema.contains_key("Day") {
+            properties["Day"] = serde_json::json!({
+                "select": first_day.map(|day| serde_json::json!({ "name": day.as_str() }))
+            });
+        }
+        if schema.contains_key("Period") {
+            properties["Period"] = serde_json::json!({
+                "multi_select": period_items
+            });
+        }
+
+        if let Some(room) = &course.room {
+            properties["Room"] = serde_json::json!({
+                "rich_text": [{
+                    "text": { "content": room }
+                }]
+            });
+        }
+
+        let instructor_items: Vec<serde_json::Value> = course.instructors
+            .iter()
+            .map(|name| serde_json::json!({ "name": name }))
+            .collect();
+        properties["Instructor"] = serde_json::json!({
+            "multi_select": instructor_items
+        });
+
+        properties["is_archived"] = serde_json::json!({
+            "checkbox": course.is_archived
+        });
+
+        Ok(properties)
+    }
+
+    /// todo のプロパティ (更新と作成で共通。Course のリレーションは作成時だけ書く)
+    async fn todo_properties(&self, todo: &crate::models::Todo) -> Result<serde_json::Value, AppError> {
+        let mut properties = serde_json::json!({});
+
+        properties["Title"] = serde_json::json!({
+            "title": [{
+                "text": {
+                    "content": todo.title
+                }
+            }]
+        });
+
+        // time_zone is only meaningful for timed dates, and then Notion wants local times without offset
+        let time_zone = match todo.due_date {
+            DueDate::Timed(_) => todo.due_timezone.as_deref(),
+            DueDate::AllDay(_) => None,
+        };
+        properties["Due Date"] = serde_json::json!({
+            "date": {
+                "start": todo.due_date.to_notion(time_zone),
+                "end": todo.due_end.map(|end| end.to_notion(time_zone)),
+                "time_zone": time_zone
+            }
+        });
+
+        let mapping = self.status_mapping();
+        properties["Status"] = serde_json::json!({
+            "status": { "name": mapping.to_notion(&todo.status) }
+        });
+
+        // Priority / Tags は任意の列なので、データベースにあるものだけ書き込む
+        let schema = self.database_schema(&self.config.todos_db_id).await?;
+        if let Some(options) = schema.get("Priority") {
+            properties["Priority"] = serde_json::json!({
+                "select": todo.priority.map(|p| serde_json::json!({ "name": p.to_notion_in(options) }))
+            });
+        }
+
+        if schema.contains_key("Tags") {
+            let tag_items: Vec<serde_json::Value> = todo.tags
+                .iter()
+                .map(|name| serde_json::json!({ "name": name }))
+                .collect();
+            properties["Tags"] = serde_json::json!({
+                "multi_select": tag_items
+            });
+        }
+
+        properties["completed_at"] = match &todo.completed_at {
+            Some(completed_at) => serde_json::json!({
+                "date": { "start": completed_at.to_rfc3339_opts(SecondsFormat::Secs, true) }
+            }),
+            None => serde_json::json!({ "date": null }),
+        };
+
+        properties["is_archived"] = serde_json::json!({
+            "checkbox": todo.is_archived
+        });
+
+        Ok(properties)
+    }
+
     async fn parse_coourse_from_page(&self, page: &dto::Page) -> Result<crate::models::Course, AppError> {
-        let id = page.id.clone();
+        // ローカルで作った授業のページは "course_id" にローカルの id を持つ
+        let id = self.get_property_text(page, "course_id")
+            .ok()
+            .filter(|id| !id.trim().is_empty())
+            .unwrap_or_else(|| page.id.clone());
         let title = self.get_property_text(page, "Name")?;
-        let semester = self.get_property_multi_select(page, "Semester")
-            .map(|items| items.join(", "))
-            .unwrap_or_else(|_| "".to_string());
-        let day_of_week = self.get_property_select(page, "Day").unwrap_or_else(|_| "".to_string());
-        let period = self.get_property_multi_select(page, "Period")
+        let semesters = self.get_property_multi_select(page, "Semester")
+            .unwrap_or_default();
+        let meetings = self.get_property_text(page, "Meetings")
             .ok()
-            .and_then(|items| items.first().cloned())
-            .and_then(|s| s.parse::<i32>().ok())
-            .unwrap_or(0);
+            .filter(|text| !text.trim().is_empty())
+            .and_then(|text| {
+                parse_meetings(&text)
+                    .map_err(|e| tracing::warn!("Ignoring Meetings of course {}: {}", page.id, e))
+                    .ok()
+            })
+            .unwrap_or_else(|| self.meetings_from_day_and_period(page));
         let room = self.get_property_text(page, "Room").ok();
-        let instructor = self.get_property_multi_select(page, "Instructor")
-            .map(|items| items.join(", "))
-            .ok();
+        let instructors = self.get_property_multi_select(page, "Instructor")
+            .unwrap_or_default();
+        let is_archived = self.get_property_checkbox(page, "is_archived")
+            .unwrap_or(page.archived);
 
         Ok(crate::models::Course {
-            id: id,
+            id,
             title,
-            semester,
-            day_of_week,
-            period,
+            semesters,
+            meetings,
             room,
-            instructor,
-            is_archived: page.archived,
-            updated_at: page.last_edited_time.clone(),
+            instructors,
+            version: 0,
+            is_archived,
+            updated_at: parse_notion_datetime(&page.last_edited_time).unwrap_or_else(Utc::now),
             sync_state: "synced".to_string(),
-            last_synced_at: Some(Utc::now().to_rfc3339()),
+            last_synced_at: Some(Utc::now()),
+            notion_page_id: Some(page.id.clone()),
         })
     }
 
+    /// "Meetings" が無いページ用: "Day" の曜日に "Period" の各時限の枠を作る
+    fn meetings_from_day_and_period(&self, page: &dto::Page) -> Vec<CourseMeeting> {
+        let Some(day) = self.get_property_select(page, "Day").ok().and_then(|d| d.parse::<Weekday>().ok()) else {
+            return Vec::new();
+        };
+        self.get_property_multi_select(page, "Period")
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|p| p.parse::<i32>().ok())
+            .map(|period| CourseMeeting::period(day, period))
+            .collect()
+    }
+
     async fn parse_todo_from_page(&self, page: &dto::Page) -> Result<crate::models::Todo, AppError> {
+        // ローカルで作った todo のページは "todo_id" にローカルの id を持つ
         let id = self.get_property_text(page, "todo_id")
-            .unwrap_or_else(|_| page.id.clone());
+            .ok()
+            .filter(|id| !id.trim().is_empty())
+            .unwrap_or_else(|| page.id.clone());
         
         let title = self.get_property_text(page, "Title")?;
         
-        let due_date = self.get_property_date(page, "Due Date")
-            .unwrap_or_else(|_| chrono::Local::now().format("%Y-%m-%d").to_string());
+        let due = self.get_property_date_value(page, "Due Date").ok();
+        let due_timezone = due.and_then(|d| d.time_zone.clone());
+        let due_date = due
+            .and_then(|d| DueDate::from_notion(&d.start, d.time_zone.as_deref()))
+            .unwrap_or_else(|| DueDate::AllDay(chrono::Local::now().date_naive()));
+        let due_end = due
+            .and_then(|d| d.end.as_deref().and_then(|end| DueDate::from_notion(end, d.time_zone.as_deref())));
         
         let status = self.get_property_status(page, "Status")
-            .unwrap_or_else(|_| "未着手".to_string());
+            .map(|name| self.status_mapping().from_notion(&name))
+            .unwrap_or(TodoStatus::NotStarted);
         
+        let priority = self.get_property_select(page, "Priority")
+            .ok()
+            .and_then(|name| {
+                name.parse::<Priority>()
+                    .map_err(|e| tracing::warn!("Ignoring Priority of todo {}: {}", page.id, e))
+                    .ok()
+            });
+        let tags = self.get_property_multi_select(page, "Tags")
+            .unwrap_or_default();
+
         let course_id = self.get_property_relation(page, "Course")
             .unwrap_or_else(|_| "".to_string());
         
-        let completed_at = self.get_property_date(page, "completed_at").ok();
+        let completed_at = self.get_property_date(page, "completed_at")
+            .ok()
+            .and_then(|start| parse_notion_datetime(&start));
         
-        let is_archived = page.properties
-            .get("is_archived")
-            .and_then(|prop| match prop {
-                dto::Property::Checkbox { checkbox } => Some(*checkbox),
-                _ => None,
-            })
+        let is_archived = self.get_property_checkbox(page, "is_archived")
             .unwrap_or(page.archived);
 
         Ok(crate::models::Todo {
@@ -158,22 +550,34 @@ impl NotionHttpClient {
             course_id,
             title,
             due_date,
+            due_end,
+            due_timezone,
             status,
+            priority,
+            tags,
             completed_at,
             is_archived,
-            updated_at: page.last_edited_time.clone(),
+            // 本文は別の API なので SyncService が必要なときだけ取得する
+            notes: String::new(),
+            progress: Default::default(),
+            series_id: None,
+            version: 0,
+            updated_at: parse_notion_datetime(&page.last_edited_time).unwrap_or_else(Utc::now),
             sync_state: "synced".to_string(),
-            last_synced_at: Some(Utc::now().to_rfc3339()),
+            last_synced_at: Some(Utc::now()),
+            notion_page_id: Some(page.id.clone()),
         })
     }
 
     fn get_property_date(&self, page: &dto::Page, key: &str) -> Result<String, AppError> {
+        self.get_property_date_value(page, key).map(|d| d.start.clone())
+    }
+
+    fn get_property_date_value<'a>(&self, page: &'a dto::Page, key: &str) -> Result<&'a dto::DateValue, AppError> {
         page.properties
             .get(key)
             .and_then(|prop| match prop {
-                dto::Property::Date { date } => {
-                    date.as_ref().map(|d| d.start.clone())
-                }
+                dto::Property::Date { date } => date.as_ref(),
                 _ => None,
             })
             .ok_or_else(|| AppError::BadRequest(format!("Missing date property: {}", key)))
@@ -206,11 +610,11 @@ impl NotionHttpClient {
             .ok_or_else(|| AppError::BadRequest(format!("Missing property: {}", key)))
     }
 
-    fn get_property_number(&self, page: &dto::Page, key: &str) -> Option<f64> {
+    fn get_property_checkbox(&self, page: &dto::Page, key: &str) -> Option<bool> {
         page.properties
             .get(key)
             .and_then(|prop| match prop {
-                dto::Property::Number { number } => *number,
+                dto::Property::Checkbox { checkbox } => Some(*checkbox),
                 _ => None,
             })
     }
@@ -285,56 +689,8 @@ impl NotionClient for NotionHttpClient {
     }
 
     async fn push_course(&self, course: &crate::models::Course) -> Result<(), AppError> {
-        let url = format!("https://api.notion.com/v1/pages/{}", course.id);
-
-        let mut properties = serde_json::json!({});
-
-        properties["Name"] = serde_json::json!({
-            "title": [{
-                "text": {
-                    "content": course.title
-                }
-            }]
-        });
-
-        let semester_items: Vec<serde_json::Value> = course.semester
-            .split(", ")
-            .map(|s| serde_json::json!({ "name": s.trim() }))
-            .collect();
-        properties["Semester"] = serde_json::json!({
-            "multi_select": semester_items
-        });
-
-        if !course.day_of_week.is_empty() {
-            properties["Day"] = serde_json::json!({
-                "select": { "name": course.day_of_week }
-            });
-        }
-
-        if course.period > 0 {
-            properties["Period"] = serde_json::json!({
-                "multi_select": [{ "name": course.period.to_string() }]
-            });
-        }
-
-        if let Some(room) = &course.room {
-            properties["Room"] = serde_json::json!({
-                "rich_text": [{
-                    "text": { "content": room }
-                }]
-            });
-        }
-
-        if let Some(instructor) = &course.instructor {
-            let instructor_items: Vec<serde_json::Value> = instructor
-                .split(", ")
-                .map(|s| serde_json::json!({ "name": s.trim() }))
-                .collect();
-            properties["Instructor"] = serde_json::json!({
-                "multi_select": instructor_items
-            });
-        }
-
+        let url = format!("https://api.notion.com/v1/pages/{}", course.page_id());
+        let properties = self.course_properties(course).await?;
         let request_body = dto::UpdatePageRequest { properties };
 
         let response = self.client
@@ -344,7 +700,7 @@ impl NotionClient for NotionHttpClient {
             .json(&request_body)
             .send()
             .await
-            .map_err(|e| AppError::InternalServerError)?;
+            .map_err(|_| AppError::InternalServerError)?;
 
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
@@ -359,43 +715,161 @@ impl NotionClient for NotionHttpClient {
     }
 
     async fn push_todo(&self, todo: &crate::models::Todo) -> Result<(), AppError> {
-        let url = format!("https://api.notion.com/v1/pages/{}", todo.id);
+        let url = format!("https://api.notion.com/v1/pages/{}", todo.page_id());
+        let properties = self.todo_properties(todo).await?;
+        let request_body = dto::UpdatePageRequest { properties };
 
-        let mut properties = serde_json::json!({});
+        let response = self.client
+            .patch(&url)
+            .header("Authorization", format!("Bearer {}", self.config.api_token))
+            .header("Notion-Version", "2022-06-28")
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
 
-        properties["Title"] = serde_json::json!({
-            "title": [{
-                "text": {
-                    "content": todo.title
-                }
-            }]
-        });
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::BadRequest(format!("Failed to push todo to Notion: {} {}", status, body)));
+        }
 
-        properties["Due Date"] = serde_json::json!({
-            "date": {
-                "start": todo.due_date
-            }
-        });
+        Ok(())
+    }
 
-        properties["Status"] = serde_json::json!({
-            "status": { "name": todo.status }
-        });
+    async fn create_course(&self, course: &crate::models::Course) -> Result<String, AppError> {
+        let url = "https://api.notion.com/v1/pages";
+        let mut properties = self.course_properties(course).await?;
+        if self.database_schema(&self.config.courses_db_id).await?.contains_key("course_id") {
+            properties["course_id"] = serde_json::json!({
+                "rich_text": [{ "text": { "content": course.id } }]
+            });
+        }
+        let request_body = dto::CreatePageRequest {
+            parent: serde_json::json!({ "database_id": self.config.courses_db_id }),
+            properties,
+        };
 
-        let request_body = dto::UpdatePageRequest { properties };
+        let response = self.client
+            .post(url)
+            .header("Authorization", format!("Bearer {}", self.config.api_token))
+            .header("Notion-Version", "2022-06-28")
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|_| AppError::InternalServerError)?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::BadRequest(format!("Failed to create course in Notion: {} {}", status, body)));
+        }
+
+        let page = response
+            .json::<dto::Page>()
+            .await
+            .map_err(|e| AppError::BadRequest(format!("Failed to parse Notion response: {}", e)))?;
+        Ok(page.id)
+    }
+
+    async fn create_todo(&self, todo: &crate::models::Todo, course_page_id: &str) -> Result<String, AppError> {
+        let url = "https://api.notion.com/v1/pages";
+        let mut properties = self.todo_properties(todo).await?;
+        properties["Course"] = serde_json::json!({
+            "relation": [{ "id": course_page_id }]
+        });
+        if self.database_schema(&self.config.todos_db_id).await?.contains_key("todo_id") {
+            properties["todo_id"] = serde_json::json!({
+                "rich_text": [{ "text": { "content": todo.id } }]
+            });
+        }
+        let request_body = dto::CreatePageRequest {
+            parent: serde_json::json!({ "database_id": self.config.todos_db_id }),
+            properties,
+        };
 
         let response = self.client
-            .patch(&url)
+            .post(url)
             .header("Authorization", format!("Bearer {}", self.config.api_token))
             .header("Notion-Version", "2022-06-28")
             .json(&request_body)
             .send()
             .await
-            .map_err(|e| AppError::InternalServerError)?;
+            .map_err(|_| AppError::InternalServerError)?;
 
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            return Err(AppError::BadRequest(format!("Failed to push todo to Notion: {} {}", status, body)));
+            return Err(AppError::BadRequest(format!("Failed to create todo in Notion: {} {}", status, body)));
+        }
+
+        let page = response
+            .json::<dto::Page>()
+            .await
+            .map_err(|e| AppError::BadRequest(format!("Failed to parse Notion response: {}", e)))?;
+        Ok(page.id)
+    }
+
+    async fn fetch_status_mapping(&self) -> Result<StatusMapping, AppError> {
+        let database = self.fetch_database(&self.config.todos_db_id).await?;
+
+        let schema = database.properties
+            .into_iter()
+            .find_map(|(key, prop)| match prop {
+                dto::DatabaseProperty::Status { status } if key == "Status" => Some(status),
+                _ => None,
+            })
+            .ok_or_else(|| AppError::BadRequest("Missing status property: Status".to_string()))?;
+
+        // Notion の Status グループは常に To-do / In progress / Complete の順
+        let groups = [StatusGroup::NotStarted, StatusGroup::InProgress, StatusGroup::Done];
+        let mut options = Vec::new();
+        for (group, schema_group) in groups.into_iter().zip(&schema.groups) {
+            for option_id in &schema_group.option_ids {
+                if let Some(option) = schema.options.iter().find(|o| &o.id == option_id) {
+                    options.push((option.name.clone(), group));
+                }
+            }
+        }
+
+        let mapping = StatusMapping::from_options(options);
+        tracing::info!(
+            "Loaded Notion status mapping: not_started={}, in_progress={}, done={}",
+            mapping.not_started, mapping.in_progress, mapping.done
+        );
+        if let Ok(mut current) = self.status_mapping.write() {
+            *current = mapping.clone();
+        }
+        Ok(mapping)
+    }
+
+    async fn fetch_todo_body(&self, page_id: &str) -> Result<TodoBody, AppError> {
+        let blocks = self.fetch_blocks(page_id, 0).await?;
+        Ok(TodoBody::from_blocks(blocks))
+    }
+
+    async fn push_todo_body(&self, page_id: &str, body: &TodoBody) -> Result<(), AppError> {
+        let blocks = self.fetch_blocks(page_id, 0).await?;
+        let current = TodoBody::from_blocks(blocks.clone());
+        if current.notes == body.notes.trim_end() && current.subtasks == body.subtasks {
+            return Ok(());
+        }
+
+        let patch = markdown::diff_body(&blocks, &body.notes, &body.subtasks);
+        let mut appended = Vec::new();
+        for (after, children) in &patch.appends {
+            if let Err(e) = self.append_blocks(page_id, after.clone(), children, &mut appended).await {
+                // leave the page as it was: drop what was already added, the old blocks are still there
+                for id in &appended {
+                    if let Err(e) = self.delete_block(id).await {
+                        tracing::warn!("Failed to roll back Notion block {}: {}", id, e);
+                    }
+                }
+                return Err(e);
+            }
+        }
+        for id in &patch.deletes {
+            self.delete_block(id).await?;
         }
 
         Ok(())
@@ -421,4 +895,24 @@ impl NotionClient for NoopNotionClient {
     async fn push_todo(&self, _todo: &crate::models::Todo) -> Result<(), AppError> {
         Ok(())
     }
+
+    async fn create_course(&self, course: &crate::models::Course) -> Result<String, AppError> {
+        Ok(course.id.clone())
+    }
+
+    async fn create_todo(&self, todo: &crate::models::Todo, _course_page_id: &str) -> Result<String, AppError> {
+        Ok(todo.id.clone())
+    }
+
+    async fn fetch_status_mapping(&self) -> Result<StatusMapping, AppError> {
+        Ok(StatusMapping::new_from_env())
+    }
+
+    async fn fetch_todo_body(&self, _page_id: &str) -> Result<TodoBody, AppError> {
+        Ok(TodoBody::default())
+    }
+
+    async fn push_todo_body(&self, _page_id: &str, _body: &TodoBody) -> Result<(), AppError> {
+        Ok(())
+    }
 }
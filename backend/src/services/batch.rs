@@ -0,0 +1,274 @@
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use sqlx::{SqliteConnection, SqlitePool};
+use uuid::Uuid;
+
+use crate::db::repository;
+use crate::error::{AppError, FieldError};
+use crate::models::{BatchOperation, BatchResponse, BatchResult, NewTodoRequest, StatusMapping, TodoStatus};
+use crate::services::events::{AppEvent, EventBus};
+
+/// `POST /batch` の操作を 1 つのトランザクションで適用する
+///
+/// 検証・存在確認は個別の API と同じ。どれかが失敗したらそこで止めてすべて取り消し、
+/// イベントはコミットした後にまとめて流す。
+pub struct BatchService {
+    db: SqlitePool,
+    statuses: Arc<StatusMapping>,
+    events: EventBus,
+}
+
+/// 成功した操作 1 件分 (`BatchResult` と、コミット後に流すイベント)
+struct Applied {
+    result: BatchResult,
+    event: Option<AppEvent>,
+}
+
+impl BatchService {
+    pub fn new(db: SqlitePool, statuses: Arc<StatusMapping>, events: EventBus) -> Self {
+        Self { db, statuses, events }
+    }
+
+    /// 成功した操作はそれぞれ `client_id` の操作ログに記録する (1 件ずつ取り消せる)
+    pub async fn apply(&self, client_id: &str, operations: Vec<BatchOperation>) -> Result<BatchResponse, AppError> {
+        let mut tx = self.db.begin().await?;
+        let mut results = Vec::with_capacity(operations.len());
+        let mut events = Vec::new();
+
+        for (index, operation) in operations.into_iter().enumerate() {
+            let id = operation.id().map(str::to_string);
+            let (action, entity) = (operation.name(), operation.entity());
+            let before = match &id {
+                Some(id) => repository::snapshot_in(&mut tx, entity, id).await?,
+                None => None,
+            };
+            match self.apply_one(&mut tx, index, operation).await {
+                Ok(applied) => {
+                    if let Some(id) = &applied.result.id {
+                        repository::record_operation_in(&mut tx, client_id, action, entity, id, before).await?;
+                    }
+                    results.push(applied.result);
+                    events.extend(applied.event);
+                }
+                // DB の障害は操作の失敗ではないので 500 にする
+                Err(AppError::Database(e)) => return Err(AppError::Database(e)),
+                Err(e) => {
+                    let (status, body) = e.into_parts();
+                    results.push(BatchResult {
+                        index,
+                        status: status.as_u16(),
+                        id,
+                        course: None,
+                        todo: None,
+                        error: Some(body),
+                    });
+                    // tx は drop でロールバックされる
+                    return Ok(BatchResponse { applied: false, results });
+                }
+            }
+        }
+
+        tx.commit().await?;
+        for event in events {
+            self.events.publish(event);
+        }
+        Ok(BatchResponse { applied: true, results })
+    }
+
+    async fn apply_one(
+        &self,
+        conn: &mut SqliteConnection,
+        index: usize,
+        operation: BatchOperation,
+    ) -> Result<Applied, AppError> {
+        let ok = |status: StatusCode, id: &str| BatchResult {
+            index,
+            status: status.as_u16(),
+            id: Some(id.to_string()),
+            course: None,
+            todo: None,
+            error: None,
+        };
+
+        match operation {
+            BatchOperation::CreateCourse { id, data } => {
+                let id = id.unwrap_or_else(|| Uuid::new_v4().to_string());
+                if repository::find_course_in(conn, &id).await?.is_some() {
+                    return Err(AppError::Conflict(format!("Course '{}' already exists", id)));
+                }
+                let course = repository::insert_course_in(conn, id, data).await?;
+                Ok(Applied {
+                    result: BatchResult { course: Some(course.clone()), ..ok(StatusCode::OK, &course.id) },
+                    event: Some(AppEvent::CourseChanged { course }),
+                })
+            }
+            BatchOperation::UpdateCourse { id, data } => {
+                let course = repository::update_course_in(conn, &id, data).await?.ok_or(AppError::NotFound)?;
+                Ok(Applied {
+                    result: BatchResult { course: Some(course.clone()), ..ok(StatusCode::OK, &id) },
+                    event: Some(AppEvent::CourseChanged { course }),
+                })
+            }
+            BatchOperation::ArchiveCourse { id } => {
+                if !repository::archive_course_in(conn, &id).await? {
+                    return Err(AppError::NotFound);
+                }
+                let course = repository::find_course_in(conn, &id).await?;
+                Ok(Applied {
+                    result: ok(StatusCode::NO_CONTENT, &id),
+                    event: course.map(|course| AppEvent::CourseChanged { course }),
+                })
+            }
+            BatchOperation::CreateTodo { id, data } => {
+                let id = id.unwrap_or_else(|| Uuid::new_v4().to_string());
+                if repository::find_todo_in(conn, &id).await?.is_some() {
+                    return Err(AppError::Conflict(format!("Todo '{}' already exists", id)));
+                }
+                let mut errors = Vec::new();
+                if repository::find_course_in(conn, &data.course_id).await?.is_none() {
+                    errors.push(FieldError::new("course_id", format!("course '{}' does not exist", data.course_id)));
+                }
+                self.check_status(&mut errors, Some(&data.status));
+                if !errors.is_empty() {
+                    return Err(AppError::Validation(errors));
+                }
+                let data = NewTodoRequest { status: self.statuses.normalize(data.status), ..data };
+                let todo = repository::insert_todo_in(conn, id, data).await?;
+                Ok(Applied {
+                    result: BatchResult { todo: Some(todo.clone()), ..ok(StatusCode::OK, &todo.id) },
+                    event: Some(AppEvent::TodoCreated { todo }),
+                })
+            }
+            BatchOperation::UpdateTodo { id, data } => {
+                let current = repository::find_todo_in(conn, &id).await?.ok_or(AppError::NotFound)?;
+                let mut errors = data.check_merged_due_range(&current);
+                self.check_status(&mut errors, data.status.as_ref());
+                if !errors.is_empty() {
+                    return Err(AppError::Validation(errors));
+                }
+                let todo = repository::update_todo_in(conn, &id, data, &self.statuses)
+                    .await?
+                    .ok_or(AppError::NotFound)?;
+                Ok(Applied {
+                    result: BatchResult { todo: Some(todo.clone()), ..ok(StatusCode::OK, &id) },
+                    event: Some(AppEvent::TodoUpdated { todo }),
+                })
+            }
+            BatchOperation::CompleteTodo { id } => self.set_completed(conn, index, &id, true).await,
+            BatchOperation::UncompleteTodo { id } => self.set_completed(conn, index, &id, false).await,
+            BatchOperation::ArchiveTodo { id } => {
+                if !repository::archive_todo_in(conn, &id).await? {
+                    return Err(AppError::NotFound);
+                }
+                Ok(Applied {
+                    result: ok(StatusCode::NO_CONTENT, &id),
+                    event: Some(AppEvent::TodoArchived { id }),
+                })
+            }
+        }
+    }
+
+    async fn set_completed(
+        &self,
+        conn: &mut SqliteConnection,
+        index: usize,
+        id: &str,
+        completed: bool,
+    ) -> Result<Applied, AppError> {
+        let todo = repository::set_todo_completed_in(conn, id, completed, &self.statuses)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        Ok(Applied {
+            result: BatchResult {
+                index,
+                status: StatusCode::OK.as_u16(),
+                id: Some(id.to_string()),
+                course: None,
+                todo: Some(todo.clone()),
+                error: None,
+            },
+            event: Some(AppEvent::TodoUpdated { todo }),
+        })
+    }
+
+    fn check_status(&self, errors: &mut Vec<FieldError>, status: Option<&TodoStatus>) {
+        if let Some(status) = status
+            && !self.statuses.is_known(status)
+        {
+            errors.push(FieldError::new("status", format!("unknown status '{}'", status)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::BatchRequest;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_db() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        pool
+    }
+
+    fn operations(value: serde_json::Value) -> Vec<BatchOperation> {
+        serde_json::from_value::<BatchRequest>(value).unwrap().operations
+    }
+
+    #[tokio::test]
+    async fn test_batch_applies_in_order_or_not_at_all() {
+        let db = setup_db().await;
+        let events = EventBus::default();
+        let mut receiver = events.subscribe();
+        let service = BatchService::new(db.clone(), Arc::new(StatusMapping::default()), events);
+        let course_id = "6b1f0c9e-3d7a-4c55-9a57-0f2d5f0e1a01";
+        let todo_id = "6b1f0c9e-3d7a-4c55-9a57-0f2d5f0e1a02";
+
+        let response = service
+            .apply("watch", operations(serde_json::json!({ "operations": [
+                { "op": "create_course", "id": course_id, "data": { "title": "Optics", "semesters": ["2A1"] } },
+                { "op": "create_todo", "id": todo_id,
+                  "data": { "course_id": course_id, "title": "Lab", "due_date": "2026-10-20" } },
+                { "op": "update_todo", "id": todo_id, "data": { "title": "Lab 1" } },
+                { "op": "complete_todo", "id": todo_id }
+            ]})))
+            .await
+            .unwrap();
+        assert!(response.applied);
+        assert_eq!(response.results.iter().map(|r| r.status).collect::<Vec<_>>(), vec![200, 200, 200, 200]);
+        let todo = repository::find_todo_by_id(&db, todo_id).await.unwrap().unwrap();
+        assert_eq!((todo.title.as_str(), todo.status), ("Lab 1", TodoStatus::Done));
+        let mut names = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            names.push(event.name());
+        }
+        assert_eq!(names, vec!["course_changed", "todo_created", "todo_updated", "todo_updated"]);
+
+        // the second operation fails, so the archive is rolled back and nothing is published
+        let response = service
+            .apply("watch", operations(serde_json::json!({ "operations": [
+                { "op": "archive_todo", "id": todo_id },
+                { "op": "create_todo", "id": todo_id,
+                  "data": { "course_id": course_id, "title": "Replayed", "due_date": "2026-10-20" } },
+                { "op": "archive_course", "id": course_id }
+            ]})))
+            .await
+            .unwrap();
+        assert!(!response.applied);
+        assert_eq!(response.results.len(), 2);
+        assert_eq!((response.results[1].index, response.results[1].status), (1, 409));
+        assert!(!repository::find_todo_by_id(&db, todo_id).await.unwrap().unwrap().is_archived);
+        assert!(receiver.try_recv().is_err());
+    }
+}
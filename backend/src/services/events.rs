@@ -0,0 +1,97 @@
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::models::{Course, ReminderSettings, Semester, Todo};
+use crate::services::SyncStats;
+
+/// `GET /events` の購読者ごとに溜められるイベント数。超えた分は `lagged` で知らせる
+pub const EVENT_CAPACITY: usize = 256;
+
+/// `GET /events` で送るデータ変更のイベント
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AppEvent {
+    TodoCreated { todo: Todo },
+    /// 内容・完了状態・サブタスクの変更
+    TodoUpdated { todo: Todo },
+    TodoArchived { id: String },
+    /// 未同期の下書きの削除
+    TodoDeleted { id: String },
+    /// 作成・変更・アーカイブ
+    CourseChanged { course: Course },
+    CourseDeleted { id: String },
+    /// 日付・current の変更 (`is_current` を付け替えたときは外れた学期も送る)
+    SemesterChanged { semester: Semester },
+    /// リマインダーのオフセットの変更
+    RemindersChanged { entity: EventEntity, id: String, settings: ReminderSettings },
+    SyncStarted,
+    SyncFinished { stats: SyncStats },
+    SyncFailed { error: String },
+    /// Pull でローカルの変更を優先して Notion 側の変更を取り込まなかった
+    ConflictDetected { entity: EventEntity, id: String, title: String, reason: ConflictReason },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventEntity {
+    Course,
+    Todo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictReason {
+    /// ローカルに未同期の変更がある
+    LocalPending,
+    /// ローカルの方が新しい
+    LocalNewer,
+}
+
+impl AppEvent {
+    /// SSE の `event:` 名 (JSON の `type` と同じ)
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppEvent::TodoCreated { .. } => "todo_created",
+            AppEvent::TodoUpdated { .. } => "todo_updated",
+            AppEvent::TodoArchived { .. } => "todo_archived",
+            AppEvent::TodoDeleted { .. } => "todo_deleted",
+            AppEvent::CourseChanged { .. } => "course_changed",
+            AppEvent::CourseDeleted { .. } => "course_deleted",
+            AppEvent::SemesterChanged { .. } => "semester_changed",
+            AppEvent::RemindersChanged { .. } => "reminders_changed",
+            AppEvent::SyncStarted => "sync_started",
+            AppEvent::SyncFinished { .. } => "sync_finished",
+            AppEvent::SyncFailed { .. } => "sync_failed",
+            AppEvent::ConflictDetected { .. } => "conflict_detected",
+        }
+    }
+}
+
+/// データ変更のイベントを購読者に配る
+///
+/// 購読者がいなければ何もしない。書き込みの後に呼ぶ。
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<AppEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: AppEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<AppEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(EVENT_CAPACITY)
+    }
+}
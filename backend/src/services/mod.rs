@@ -1,5 +1,15 @@
+pub mod batch;
+pub mod events;
 pub mod sync_service;
 pub mod scheduler;
+pub mod recurrence;
+pub mod reminder;
+pub mod undo;
 
+pub use batch::BatchService;
+pub use events::{AppEvent, EventBus, EventEntity};
 pub use sync_service::{SyncService, SyncStats};
 pub use scheduler::SyncScheduler;
+pub use recurrence::{RecurrenceScheduler, RecurrenceService};
+pub use reminder::{pending_then_live, ReminderScheduler, ReminderService};
+pub use undo::UndoService;
@@ -0,0 +1,215 @@
+use std::time::Duration;
+
+use chrono::{NaiveDate, Utc};
+use chrono_tz::Tz;
+use sqlx::{SqliteConnection, SqlitePool};
+use tracing::{info, warn};
+
+use crate::db::repository;
+use crate::error::AppError;
+use crate::models::{ChangeEntity, HistoryContext, HistorySource, RecurrenceRule, Todo, TodoSeries};
+use crate::services::events::{AppEvent, EventBus};
+
+/// 何日先までの回を作っておくかのデフォルト (`RECURRENCE_HORIZON_DAYS`)
+pub const DEFAULT_HORIZON_DAYS: i64 = 14;
+
+/// 繰り返しの系列から先の回を作るジョブ
+///
+/// 作った回は通常の pending な todo なので、次の同期で Notion に送られる。
+pub struct RecurrenceService {
+    db: SqlitePool,
+    timezone: Tz,
+    horizon_days: i64,
+    events: EventBus,
+}
+
+impl RecurrenceService {
+    pub fn new(db: SqlitePool, timezone: Tz, horizon_days: i64) -> Self {
+        Self { db, timezone, horizon_days, events: EventBus::default() }
+    }
+
+    /// 作った回を `todo_created` として流す
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// 現地の今日
+    pub fn today(&self) -> NaiveDate {
+        Utc::now().with_timezone(&self.timezone).date_naive()
+    }
+
+    /// 有効なすべての系列について、今日から `horizon_days` 日先までの回を作る
+    pub async fn materialize_all(&self, today: NaiveDate) -> Result<usize, AppError> {
+        let mut created = 0;
+        for series in repository::fetch_active_series(&self.db).await? {
+            match self.materialize(&series, today).await {
+                Ok(count) => created += count,
+                Err(e) => warn!("Failed to materialize series {}: {:?}", series.id, e),
+            }
+        }
+        Ok(created)
+    }
+
+    /// 1 つの系列の回を作り、履歴に記録して `todo_created` を流す
+    pub async fn materialize(&self, series: &TodoSeries, today: NaiveDate) -> Result<usize, AppError> {
+        let mut tx = self.db.begin().await?;
+        let created = self.materialize_in(&mut tx, series, today).await?;
+        let context = HistoryContext::new(HistorySource::Recurrence, "create");
+        for todo in &created {
+            let after = repository::snapshot_in(&mut tx, ChangeEntity::Todo, &todo.id).await?;
+            repository::record_history_in(&mut tx, ChangeEntity::Todo, &todo.id, &context, None, after.as_ref()).await?;
+        }
+        tx.commit().await?;
+        let count = created.len();
+        for todo in created {
+            self.events.publish(AppEvent::TodoCreated { todo });
+        }
+        Ok(count)
+    }
+
+    /// 1 つの系列の回を呼び出し側のトランザクションで作り、作った回を返す (履歴とイベントは呼び出し側)
+    ///
+    /// 作成済みの範囲 (`generated_until`) より後の日付だけを対象にする。
+    pub async fn materialize_in(
+        &self,
+        conn: &mut SqliteConnection,
+        series: &TodoSeries,
+        today: NaiveDate,
+    ) -> Result<Vec<Todo>, AppError> {
+        if !series.is_active {
+            return Ok(Vec::new());
+        }
+        let rule = series
+            .rule
+            .parse::<RecurrenceRule>()
+            .map_err(|e| AppError::BadRequest(format!("Invalid rule of series {}: {}", series.id, e)))?;
+        let Some(template) = repository::find_todo_in(conn, &series.template_todo_id).await? else {
+            return Ok(Vec::new());
+        };
+        let meeting_days = repository::fetch_course_meeting_days_in(conn, &template.course_id).await?;
+
+        let from = series
+            .generated_until
+            .and_then(|d| d.succ_opt())
+            .unwrap_or(series.start_date)
+            .max(today);
+        let to = today + chrono::Duration::days(self.horizon_days);
+        let template_date = template.due_date.local_date(&self.timezone);
+
+        let mut created = Vec::new();
+        for date in rule.occurrences(series.start_date, &meeting_days, from, to) {
+            let days = (date - template_date).num_days();
+            let due_date = template.due_date.shift_days(days, &self.timezone);
+            let due_end = template.due_end.map(|end| end.shift_days(days, &self.timezone));
+            if let Some(todo) =
+                repository::insert_series_instance_in(conn, &template, &series.id, date, due_date, due_end).await?
+            {
+                created.push(todo);
+            }
+        }
+        if series.generated_until.is_none_or(|until| until < to) {
+            repository::set_series_generated_until_in(conn, &series.id, to).await?;
+        }
+        Ok(created)
+    }
+}
+
+/// 繰り返しの回を定期的に作るスケジューラー (起動時に 1 回、以降 1 時間ごと)
+pub struct RecurrenceScheduler {
+    service: RecurrenceService,
+    interval: Duration,
+}
+
+impl RecurrenceScheduler {
+    pub fn new(service: RecurrenceService) -> Self {
+        Self { service, interval: Duration::from_secs(60 * 60) }
+    }
+
+    pub async fn start(self) {
+        info!("Starting recurrence scheduler (interval: {:?})", self.interval);
+
+        loop {
+            match self.service.materialize_all(self.service.today()).await {
+                Ok(0) => {}
+                Ok(created) => info!("Created {} recurring todos", created),
+                Err(e) => warn!("Recurrence job failed: {:?}", e),
+            }
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{CourseMeeting, NewCourseRequest, NewSubtaskRequest, NewTodoRequest, TodoListQuery, TodoStatus, Weekday};
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_db() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_weekly_series_on_course_meeting_days() {
+        let db = setup_db().await;
+        let tz = chrono_tz::Asia::Tokyo;
+        let course = NewCourseRequest {
+            title: "Physics".to_string(),
+            semesters: vec!["2A1".to_string()],
+            meetings: vec![CourseMeeting::period(Weekday::Mon, 2), CourseMeeting::period(Weekday::Thu, 1)],
+            room: None,
+            instructors: Vec::new(),
+        };
+        let course = repository::insert_course(&db, course).await.unwrap();
+        let template = NewTodoRequest {
+            course_id: course.id.clone(),
+            title: "Reading quiz".to_string(),
+            due_date: "2026-10-19T10:00:00+09:00".to_string(),
+            due_end: None,
+            due_timezone: Some("Asia/Tokyo".to_string()),
+            status: TodoStatus::Done,
+            priority: Some("high".to_string()),
+            tags: vec!["quiz".to_string()],
+            notes: String::new(),
+        };
+        let template = repository::insert_todo(&db, template).await.unwrap();
+        let step = NewSubtaskRequest { title: "Read chapter".to_string(), done: true, position: None };
+        repository::insert_subtask(&db, &template.id, step).await.unwrap();
+
+        let start = "2026-10-19".parse().unwrap();
+        let series = repository::insert_series(&db, &template, "FREQ=WEEKLY;UNTIL=20261031", start).await.unwrap();
+        let service = RecurrenceService::new(db.clone(), tz, 14);
+        assert_eq!(service.materialize(&series, start).await.unwrap(), 3);
+
+        let series = repository::find_series(&db, &series.id).await.unwrap().unwrap();
+        assert_eq!(service.materialize(&series, start).await.unwrap(), 0, "already generated");
+
+        let query = TodoListQuery { series_id: Some(series.id.clone()), sort: crate::models::TodoSortField::DueDate, order: crate::models::SortOrder::Asc, ..Default::default() };
+        let todos = repository::query_todos(&db, &query, None, &tz).await.unwrap();
+        let dues: Vec<_> = todos.iter().map(|t| t.due_date.to_string()).collect();
+        assert_eq!(dues, vec!["2026-10-19T01:00:00Z", "2026-10-22T01:00:00Z", "2026-10-26T01:00:00Z", "2026-10-29T01:00:00Z"]);
+        let instance = &todos[1];
+        assert_eq!(instance.status, TodoStatus::NotStarted);
+        assert_eq!(instance.sync_state, "pending");
+        assert_eq!(instance.tags, vec!["quiz"]);
+        assert_eq!((instance.progress.done, instance.progress.total), (0, 1));
+
+        // stopping after the 26th drops the later drafts but keeps the template
+        let removed = repository::remove_future_series_instances(&db, &series.id, "2026-10-26".parse().unwrap()).await.unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(!removed[0].2, "an unsynced draft is deleted, not archived");
+        assert_eq!(repository::query_todos(&db, &query, None, &tz).await.unwrap().len(), 3);
+    }
+}
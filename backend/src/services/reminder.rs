@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use sqlx::SqlitePool;
+use tokio::sync::broadcast;
+use tokio_stream::{Stream, StreamExt};
+use tracing::{debug, info, warn};
+
+use crate::db::repository;
+use crate::error::AppError;
+use crate::models::reminder::plan_reminders;
+use crate::models::{Reminder, ReminderEvent, StatusMapping};
+
+/// `GET /reminders/stream` の購読者ごとに溜められるイベント数
+pub const REMINDER_EVENT_CAPACITY: usize = 64;
+
+/// todo の締め切りとオフセットからリマインダーを作る
+pub struct ReminderService {
+    db: SqlitePool,
+    statuses: Arc<StatusMapping>,
+    timezone: Tz,
+}
+
+impl ReminderService {
+    pub fn new(db: SqlitePool, statuses: Arc<StatusMapping>, timezone: Tz) -> Self {
+        Self { db, statuses, timezone }
+    }
+
+    /// 未完了の todo のリマインダーを作り直す。締め切りやオフセットが変わった未確認のリマインダーは削除する
+    pub async fn refresh(&self, now: DateTime<Utc>) -> Result<u64, AppError> {
+        let targets = repository::fetch_reminder_targets(&self.db, &self.statuses.done_statuses()).await?;
+        let planned: Vec<_> = targets
+            .iter()
+            .flat_map(|(todo, offsets)| plan_reminders(todo, offsets, &self.timezone, now))
+            .collect();
+        Ok(repository::sync_reminders(&self.db, &planned).await?)
+    }
+
+    /// 時刻になった未確認のリマインダー
+    pub async fn due(&self, now: DateTime<Utc>) -> Result<Vec<Reminder>, AppError> {
+        Ok(repository::fetch_due_reminders(&self.db, now).await?)
+    }
+}
+
+/// 時刻になった未確認のリマインダー `pending` を先に流し、続けて配信されるイベントを流す
+///
+/// `live` は `pending` の取得前に購読しているので、同じリマインダーが両方に入ることがある。
+/// `pending` で送った id の `Due` は `live` からは送らない。
+pub fn pending_then_live<S>(pending: Vec<Reminder>, live: S) -> impl Stream<Item = ReminderEvent>
+where
+    S: Stream<Item = ReminderEvent>,
+{
+    let sent: HashSet<String> = pending.iter().map(|reminder| reminder.id.clone()).collect();
+    let live = live.filter(move |event| {
+        !matches!(event, ReminderEvent::Due { reminder } if sent.contains(&reminder.id))
+    });
+    tokio_stream::iter(pending.into_iter().map(|reminder| ReminderEvent::Due { reminder })).chain(live)
+}
+
+/// リマインダーを定期的に作り、時刻になったものを `GET /reminders/stream` に流すスケジューラー
+pub struct ReminderScheduler {
+    service: ReminderService,
+    events: broadcast::Sender<ReminderEvent>,
+    interval: Duration,
+}
+
+impl ReminderScheduler {
+    pub fn new(service: ReminderService, events: broadcast::Sender<ReminderEvent>, interval_secs: u64) -> Self {
+        Self { service, events, interval: Duration::from_secs(interval_secs) }
+    }
+
+    pub async fn start(self) {
+        info!("Starting reminder scheduler (interval: {:?})", self.interval);
+
+        loop {
+            if let Err(e) = self.tick(Utc::now()).await {
+                warn!("Reminder job failed: {:?}", e);
+            }
+            tokio::time::sleep(self.interval).await;
+        }
+    }
+
+    async fn tick(&self, now: DateTime<Utc>) -> Result<(), AppError> {
+        let created = self.service.refresh(now).await?;
+        if created > 0 {
+            debug!("Planned {} reminders", created);
+        }
+        for reminder in repository::take_unnotified_reminders(&self.service.db, now).await? {
+            // 購読者がいなくてもよい (端末は GET /reminders/due で取得できる)
+            let _ = self.events.send(ReminderEvent::Due { reminder });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reminder(id: &str) -> Reminder {
+        let at = Utc::now();
+        Reminder {
+            id: id.to_string(),
+            todo_id: "todo".to_string(),
+            course_id: "course".to_string(),
+            title: "Report".to_string(),
+            offset_minutes: 60,
+            remind_at: at,
+            due_at: at,
+            acknowledged_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pending_reminders_are_not_sent_twice() {
+        let live = tokio_stream::iter(vec![
+            ReminderEvent::Due { reminder: reminder("a") },
+            ReminderEvent::Due { reminder: reminder("c") },
+            ReminderEvent::Acknowledged { id: "a".to_string() },
+        ]);
+        let events: Vec<ReminderEvent> = pending_then_live(vec![reminder("a"), reminder("b")], live).collect().await;
+        let names: Vec<String> = events
+            .iter()
+            .map(|event| match event {
+                ReminderEvent::Due { reminder } => format!("due {}", reminder.id),
+                ReminderEvent::Acknowledged { id } => format!("ack {}", id),
+            })
+            .collect();
+        assert_eq!(names, vec!["due a", "due b", "due c", "ack a"]);
+    }
+}
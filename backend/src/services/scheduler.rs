@@ -4,6 +4,7 @@ use sqlx::SqlitePool;
 use tracing::info;
 
 use crate::notion::NotionClient;
+use crate::services::events::EventBus;
 use crate::services::sync_service::SyncService;
 
 /// Auto-sync スケジューラー
@@ -12,6 +13,7 @@ pub struct SyncScheduler {
     db: SqlitePool,
     notion: Arc<dyn NotionClient>,
     interval: Duration,
+    events: EventBus,
 }
 
 impl SyncScheduler {
@@ -24,9 +26,16 @@ impl SyncScheduler {
             db,
             notion,
             interval: Duration::from_secs(interval_secs),
+            events: EventBus::default(),
         }
     }
 
+    /// 自動同期の開始・終了と取り込んだ変更を `events` に流す
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
     /// 同期を無限ループで定期実行
     pub async fn start(self) {
         info!("Starting auto-sync scheduler (interval: {:?})", self.interval);
@@ -56,7 +65,7 @@ impl SyncScheduler {
 
     /// 同期を実行
     async fn run_sync(&self) -> Result<crate::services::SyncStats, crate::error::AppError> {
-        let service = SyncService::new(self.db.clone(), self.notion.clone());
+        let service = SyncService::new(self.db.clone(), self.notion.clone()).with_events(self.events.clone());
         service.sync_all().await
     }
 }
@@ -4,15 +4,18 @@ use serde::Serialize;
 use sqlx::SqlitePool;
 use tracing::{info, warn};
 
-use crate::{error::AppError, notion::NotionClient};
+use crate::{error::AppError, notion::{NotionClient, TodoBody}};
+use crate::models::{ChangeEntity, HistoryContext, HistorySource, Snapshot, SubtaskItem, Todo};
 use crate::db::repository;
+use crate::services::events::{AppEvent, ConflictReason, EventBus, EventEntity};
 
 pub struct SyncService {
     db: SqlitePool,
     notion: Arc<dyn NotionClient>,
+    events: EventBus,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SyncStats {
     pub courses_pushed: usize,
     pub courses_pulled: usize,
@@ -24,10 +27,26 @@ pub struct SyncStats {
 
 impl SyncService {
     pub fn new(db: SqlitePool, notion: Arc<dyn NotionClient>) -> Self {
-        Self { db, notion }
+        Self { db, notion, events: EventBus::default() }
+    }
+
+    /// 同期の開始・終了と取り込んだ変更を `events` に流す
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
     }
 
     pub async fn sync_all(&self) -> Result<SyncStats, AppError> {
+        self.events.publish(AppEvent::SyncStarted);
+        let result = self.run_sync().await;
+        match &result {
+            Ok(stats) => self.events.publish(AppEvent::SyncFinished { stats: stats.clone() }),
+            Err(e) => self.events.publish(AppEvent::SyncFailed { error: e.to_string() }),
+        }
+        result
+    }
+
+    async fn run_sync(&self) -> Result<SyncStats, AppError> {
         info!("Starting sync...");
         let mut stats = SyncStats {
             courses_pushed: 0,
@@ -61,8 +80,7 @@ impl SyncService {
     }
 
     async fn sync_courses_from_notion(&self) -> Result<(usize, usize), AppError> {
-        let notion_courses = self.notion.fetch_courses().await?;
-        let notion_ids: Vec<String> = notion_courses.iter().map(|c| c.id.clone()).collect();
+        let mut notion_courses = self.notion.fetch_courses().await?;
         
         let mut pulled = 0;
         let mut skipped = 0;
@@ -75,42 +93,70 @@ impl SyncService {
                 .map(|c| (c.id.clone(), c))
                 .collect();
 
+        // pages made for local courses come back under the page id when the database has no "course_id"
+        let local_ids_by_page = repository::fetch_course_ids_by_page(&self.db).await?;
+        for course in &mut notion_courses {
+            if let Some(id) = local_ids_by_page.get(&course.id) {
+                course.id = id.clone();
+            }
+        }
+        let notion_ids: Vec<String> = notion_courses.iter().map(|c| c.id.clone()).collect();
+
         // Upsert from Notion with conflict detection
         for course in notion_courses {
             if let Some(existing) = local_courses_map.get(&course.id) {
                 if existing.sync_state == "pending" {
                     warn!("Skipping course (local pending): {}", course.title);
+                    self.conflict(Snapshot::course(course), ConflictReason::LocalPending).await?;
                     skipped += 1;
                     continue;
                 }
                 // Check if local is newer (avoid overwriting recent local changes)
-                if let (Some(local_updated), Some(notion_updated)) = 
-                    (parse_timestamp(&existing.updated_at), parse_timestamp(&course.updated_at)) {
-                    if local_updated > notion_updated {
-                        warn!("Skipping course (local newer): {} local={:?} notion={:?}", 
-                              course.title, local_updated, notion_updated);
-                        skipped += 1;
-                        continue;
-                    }
+                if existing.updated_at > course.updated_at {
+                    warn!("Skipping course (local newer): {} local={} notion={}",
+                          course.title, existing.updated_at, course.updated_at);
+                    self.conflict(Snapshot::course(course), ConflictReason::LocalNewer).await?;
+                    skipped += 1;
+                    continue;
+                }
+                // nothing new: only record the sync time
+                let local = Snapshot::course(existing.clone());
+                if local.same_content(&Snapshot::course(course.clone())) {
+                    repository::mark_pulled_unchanged(&self.db, ChangeEntity::Course, &course.id, course.updated_at).await?;
+                    continue;
                 }
             }
-            
-            repository::upsert_course(&self.db, &course).await?;
+
+            let before = repository::snapshot(&self.db, ChangeEntity::Course, &course.id).await?;
+            let course = repository::upsert_course(&self.db, &course).await?;
+            let context = HistoryContext::new(HistorySource::SyncPull, "pull");
+            let after = repository::record_history(&self.db, ChangeEntity::Course, &course.id, &context, before.as_ref()).await?;
+            if !repository::same_state(before.as_ref(), after.as_ref()) {
+                self.events.publish(AppEvent::CourseChanged { course });
+            }
             pulled += 1;
         }
 
         // Archive courses not in Notion (batch update)
         let courses_to_archive: Vec<String> = local_courses_map
-            .keys()
-            .filter(|id| !notion_ids.contains(id))
-            .cloned()
+            .values()
+            // courses that could not be created in Notion yet are not missing from it
+            .filter(|c| !c.is_archived && c.last_synced_at.is_some() && !notion_ids.contains(&c.id))
+            .map(|c| c.id.clone())
             .collect();
 
         if !courses_to_archive.is_empty() {
             for id in courses_to_archive {
+                let before = repository::snapshot(&self.db, ChangeEntity::Course, &id).await?;
                 sqlx::query!("UPDATE courses SET is_archived = 1 WHERE id = ?", id)
                     .execute(&self.db)
                     .await?;
+                let context = HistoryContext::new(HistorySource::SyncPull, "archive");
+                if let Some(Snapshot::Course { course, .. }) =
+                    repository::record_history(&self.db, ChangeEntity::Course, &id, &context, before.as_ref()).await?
+                {
+                    self.events.publish(AppEvent::CourseChanged { course });
+                }
             }
         }
 
@@ -118,9 +164,8 @@ impl SyncService {
     }
 
     async fn sync_todos_from_notion(&self) -> Result<(usize, usize), AppError> {
-        let notion_todos = self.notion.fetch_todos().await?;
-        let notion_ids: Vec<String> = notion_todos.iter().map(|t| t.id.clone()).collect();
-        
+        let mut notion_todos = self.notion.fetch_todos().await?;
+
         let mut pulled = 0;
         let mut skipped = 0;
 
@@ -132,155 +177,557 @@ impl SyncService {
                 .map(|t| (t.id.clone(), t))
                 .collect();
 
+        // pages made for local todos come back under the page id when the database has no "todo_id"
+        let local_ids_by_page: std::collections::HashMap<&str, &str> = local_todos_map
+            .values()
+            .filter_map(|t| t.notion_page_id.as_deref().map(|page_id| (page_id, t.id.as_str())))
+            .collect();
+        for todo in &mut notion_todos {
+            if let Some(id) = local_ids_by_page.get(todo.id.as_str()) {
+                todo.id = id.to_string();
+            }
+        }
+        // the Course relation points at the course's page
+        let course_ids_by_page = repository::fetch_course_ids_by_page(&self.db).await?;
+        for todo in &mut notion_todos {
+            if let Some(course_id) = course_ids_by_page.get(&todo.course_id) {
+                todo.course_id = course_id.clone();
+            }
+        }
+        let notion_ids: Vec<String> = notion_todos.iter().map(|t| t.id.clone()).collect();
+
         // Upsert from Notion with conflict detection
-        for todo in notion_todos {
-            if let Some(existing) = local_todos_map.get(&todo.id) {
+        for mut todo in notion_todos {
+            let existing = local_todos_map.get(&todo.id);
+            if let Some(existing) = existing {
                 if existing.sync_state == "pending" {
                     warn!("Skipping todo (local pending): {}", todo.title);
+                    self.conflict(Snapshot::todo(todo, Vec::new()), ConflictReason::LocalPending).await?;
                     skipped += 1;
                     continue;
                 }
                 // Check if local is newer
-                if let (Some(local_updated), Some(notion_updated)) = 
-                    (parse_timestamp(&existing.updated_at), parse_timestamp(&todo.updated_at)) {
-                    if local_updated > notion_updated {
-                        warn!("Skipping todo (local newer): {}", todo.title);
-                        skipped += 1;
-                        continue;
-                    }
+                if existing.updated_at > todo.updated_at {
+                    warn!("Skipping todo (local newer): {}", todo.title);
+                    self.conflict(Snapshot::todo(todo, Vec::new()), ConflictReason::LocalNewer).await?;
+                    skipped += 1;
+                    continue;
                 }
             }
-            
+
+            // the page body is a separate request per page, so only fetch it when the page changed
+            let body = match existing {
+                Some(existing) if existing.updated_at >= todo.updated_at => None,
+                _ => match self.notion.fetch_todo_body(todo.page_id()).await {
+                    Ok(body) => Some(body),
+                    Err(e) => {
+                        warn!("Failed to fetch page body for todo {}: {}", todo.title, e);
+                        None
+                    }
+                },
+            };
+            todo.notes = match &body {
+                Some(body) => body.notes.clone(),
+                None => existing.map(|t| t.notes.clone()).unwrap_or_default(),
+            };
+            if let Some(existing) = existing
+                && self.unchanged_todo(existing, &todo, body.as_ref()).await?
+            {
+                repository::mark_pulled_unchanged(&self.db, ChangeEntity::Todo, &todo.id, todo.updated_at).await?;
+                continue;
+            }
+
+            let before = repository::snapshot(&self.db, ChangeEntity::Todo, &todo.id).await?;
             repository::upsert_todo(&self.db, &todo).await?;
+            if let Some(body) = body {
+                repository::replace_subtasks(&self.db, &todo.id, &body.subtasks).await?;
+            }
+            let context = HistoryContext::new(HistorySource::SyncPull, "pull");
+            let after = repository::record_history(&self.db, ChangeEntity::Todo, &todo.id, &context, before.as_ref()).await?;
+            if !repository::same_state(before.as_ref(), after.as_ref())
+                && let Some(todo) = repository::find_todo_by_id(&self.db, &todo.id).await?
+            {
+                self.events.publish(match before {
+                    Some(_) => AppEvent::TodoUpdated { todo },
+                    None => AppEvent::TodoCreated { todo },
+                });
+            }
             pulled += 1;
         }
 
         // Archive todos not in Notion (batch update)
         let todos_to_archive: Vec<String> = local_todos_map
-            .keys()
-            .filter(|id| !notion_ids.contains(id))
-            .cloned()
+            .values()
+            // drafts that could not be created in Notion yet are not missing from it
+            .filter(|t| !t.is_archived && t.last_synced_at.is_some() && !notion_ids.contains(&t.id))
+            .map(|t| t.id.clone())
             .collect();
 
         if !todos_to_archive.is_empty() {
             for id in todos_to_archive {
+                let before = repository::snapshot(&self.db, ChangeEntity::Todo, &id).await?;
                 sqlx::query!("UPDATE todos SET is_archived = 1 WHERE id = ?", id)
                     .execute(&self.db)
                     .await?;
+                let context = HistoryContext::new(HistorySource::SyncPull, "archive");
+                repository::record_history(&self.db, ChangeEntity::Todo, &id, &context, before.as_ref()).await?;
+                self.events.publish(AppEvent::TodoArchived { id });
             }
         }
 
         Ok((pulled, skipped))
     }
 
+    /// Notion の todo がローカルと同じ内容か
+    ///
+    /// ローカルだけの項目 (系列、進捗) と同期の記録は比べない。本文を取得したときはサブタスクも比べる。
+    async fn unchanged_todo(&self, local: &Todo, remote: &Todo, body: Option<&TodoBody>) -> Result<bool, AppError> {
+        let mut remote = remote.clone();
+        remote.series_id = local.series_id.clone();
+        remote.progress = local.progress;
+        let local_snapshot = Snapshot::todo(local.clone(), Vec::new());
+        if !local_snapshot.same_content(&Snapshot::todo(remote, Vec::new())) {
+            return Ok(false);
+        }
+        let Some(body) = body else {
+            return Ok(true);
+        };
+        let subtasks = repository::fetch_subtasks(&self.db, &local.id).await?;
+        Ok(subtasks.iter().map(|s| (&s.title, s.done)).eq(body.subtasks.iter().map(|s| (&s.title, s.done))))
+    }
+
+    /// 取り込まなかった Notion の値 `remote` を通知し、残したローカルの値との差分を履歴に記録する
+    ///
+    /// 同期状態と、Notion から取得していない本文・サブタスク・系列の日付・リマインダーはローカルの値を使う。
+    async fn conflict(&self, mut remote: Snapshot, reason: ConflictReason) -> Result<(), AppError> {
+        let (entity, event_entity, id, title) = match &remote {
+            Snapshot::Course { course, .. } => (ChangeEntity::Course, EventEntity::Course, course.id.clone(), course.title.clone()),
+            Snapshot::Todo { todo, .. } => (ChangeEntity::Todo, EventEntity::Todo, todo.id.clone(), todo.title.clone()),
+            // semesters are not synced
+            Snapshot::Semester { .. } => return Ok(()),
+        };
+        self.events.publish(AppEvent::ConflictDetected {
+            entity: event_entity,
+            id: id.clone(),
+            title,
+            reason,
+        });
+
+        let local = repository::snapshot(&self.db, entity, &id).await?;
+        match (&mut remote, &local) {
+            (
+                Snapshot::Course { course, reminder_offsets },
+                Some(Snapshot::Course { course: local, reminder_offsets: local_offsets }),
+            ) => {
+                course.sync_state = local.sync_state.clone();
+                course.last_synced_at = local.last_synced_at;
+                *reminder_offsets = local_offsets.clone();
+            }
+            (
+                Snapshot::Todo { todo, subtasks, series_date, reminder_offsets, series },
+                Some(Snapshot::Todo {
+                    todo: local,
+                    subtasks: local_subtasks,
+                    series_date: local_series_date,
+                    reminder_offsets: local_offsets,
+                    series: local_series,
+                }),
+            ) => {
+                todo.notes = local.notes.clone();
+                todo.sync_state = local.sync_state.clone();
+                todo.last_synced_at = local.last_synced_at;
+                *subtasks = local_subtasks.clone();
+                *series_date = *local_series_date;
+                *reminder_offsets = local_offsets.clone();
+                *series = local_series.clone();
+            }
+            _ => {}
+        }
+        let action = match reason {
+            ConflictReason::LocalPending => "local_pending",
+            ConflictReason::LocalNewer => "local_newer",
+        };
+        let context = HistoryContext::new(HistorySource::ConflictResolution, action);
+        let mut conn = self.db.acquire().await?;
+        repository::record_history_in(&mut conn, entity, &id, &context, Some(&remote), local.as_ref()).await?;
+        Ok(())
+    }
+
     async fn push_local_changes_to_notion(&self) -> Result<(usize, usize), AppError> {
-        let courses = repository::fetch_courses(&self.db).await?;
+        // Only push courses with sync_state != 'synced' (archives included)
+        let courses = repository::fetch_pending_courses(&self.db).await?;
         let mut pushed_count = 0;
 
-        // Only push courses with sync_state != 'synced'
         for course in courses {
-            if course.sync_state != "synced" {
-                self.notion.push_course(&course).await?;
-                let now = chrono::Utc::now().to_rfc3339();
-                sqlx::query!(
-                    "UPDATE courses SET sync_state = 'synced', last_synced_at = ? WHERE id = ?",
-                    now,
-                    course.id
-                )
-                .execute(&self.db)
-                .await?;
-                pushed_count += 1;
+            // one course Notion rejects must not keep the others (and the todos) from syncing;
+            // it stays pending and is retried on the next sync
+            let pushed = if course.last_synced_at.is_none() {
+                // the course keeps its id; the page carries it in "course_id"
+                self.notion.create_course(&course).await.map(Some)
+            } else {
+                self.notion.push_course(&course).await.map(|()| None)
+            };
+            match pushed {
+                Ok(Some(page_id)) => {
+                    repository::set_course_notion_page(&self.db, &course.id, &page_id).await?;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("Failed to push course {}: {}", course.title, e);
+                    continue;
+                }
             }
+            let before = repository::snapshot(&self.db, ChangeEntity::Course, &course.id).await?;
+            let now = repository::timestamp(chrono::Utc::now());
+            sqlx::query!(
+                "UPDATE courses SET sync_state = 'synced', last_synced_at = ? WHERE id = ?",
+                now,
+                course.id
+            )
+            .execute(&self.db)
+            .await?;
+            let context = HistoryContext::new(HistorySource::SyncPush, "push");
+            repository::record_history(&self.db, ChangeEntity::Course, &course.id, &context, before.as_ref()).await?;
+            pushed_count += 1;
         }
 
-        let todos = repository::fetch_todos(&self.db).await?;
+        let todos = repository::fetch_pending_todos(&self.db).await?;
         let mut todo_count = 0;
-        
-        for todo in todos {
-            if todo.sync_state != "synced" {
+
+        for mut todo in todos {
+            if todo.last_synced_at.is_none() {
+                // a page in Notion needs the course's page for its relation
+                let course = repository::find_course_by_id(&self.db, &todo.course_id)
+                    .await?
+                    .filter(|course| course.last_synced_at.is_some());
+                let Some(course) = course else {
+                    warn!("Skipping todo (course not in Notion yet): {}", todo.title);
+                    continue;
+                };
+                // the todo keeps its id; the page carries it in "todo_id". The page is recorded
+                // before the body push, so a failure from here on does not create a second page
+                let page_id = self.notion.create_todo(&todo, course.page_id()).await?;
+                repository::set_todo_notion_page(&self.db, &todo.id, &page_id).await?;
+                todo.notion_page_id = Some(page_id);
+            } else {
                 self.notion.push_todo(&todo).await?;
-                let now = chrono::Utc::now().to_rfc3339();
-                sqlx::query!(
-                    "UPDATE todos SET sync_state = 'synced', last_synced_at = ? WHERE id = ?",
-                    now,
-                    todo.id
-                )
-                .execute(&self.db)
-                .await?;
-                todo_count += 1;
             }
+            let subtasks = repository::fetch_subtasks(&self.db, &todo.id).await?;
+            let body = TodoBody {
+                notes: todo.notes.clone(),
+                subtasks: subtasks.into_iter().map(|s| SubtaskItem { title: s.title, done: s.done }).collect(),
+            };
+            self.notion.push_todo_body(todo.page_id(), &body).await?;
+            let before = repository::snapshot(&self.db, ChangeEntity::Todo, &todo.id).await?;
+            let now = repository::timestamp(chrono::Utc::now());
+            sqlx::query!(
+                "UPDATE todos SET sync_state = 'synced', last_synced_at = ? WHERE id = ?",
+                now,
+                todo.id
+            )
+            .execute(&self.db)
+            .await?;
+            let context = HistoryContext::new(HistorySource::SyncPush, "push");
+            repository::record_history(&self.db, ChangeEntity::Todo, &todo.id, &context, before.as_ref()).await?;
+            todo_count += 1;
         }
 
         Ok((pushed_count, todo_count))
     }
 }
 
-/// Parse RFC3339 timestamp to comparable format
-fn parse_timestamp(ts: &str) -> Option<chrono::DateTime<chrono::Utc>> {
-    chrono::DateTime::parse_from_rfc3339(ts)
-        .ok()
-        .map(|dt| dt.with_timezone(&chrono::Utc))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        models::{NewCourseRequest},
+        models::{Course, CourseMeeting, DueDate, NewCourseRequest, NewTodoRequest, StatusMapping, TodoStatus, Weekday},
         notion::NoopNotionClient,
     };
+    use async_trait::async_trait;
     use sqlx::SqlitePool;
+    use sqlx::sqlite::SqlitePoolOptions;
 
     async fn setup_db() -> SqlitePool {
-        let pool = SqlitePool::connect("sqlite::memory:")
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
             .await
             .expect("Failed to create in-memory database");
 
-        sqlx::query(
-            r#"
-            CREATE TABLE courses (
-                id TEXT PRIMARY KEY,
-                title TEXT NOT NULL,
-                semester TEXT NOT NULL,
-                day_of_week TEXT NOT NULL,
-                period INTEGER NOT NULL,
-                room TEXT,
-                instructor TEXT,
-                is_archived INTEGER NOT NULL DEFAULT 0,
-                updated_at TEXT NOT NULL,
-                sync_state TEXT NOT NULL CHECK(sync_state IN ('pending', 'synced')) DEFAULT 'pending',
-                last_synced_at TEXT
-            )
-            "#,
-        )
-        .execute(&pool)
-        .await
-        .expect("Failed to create courses table");
-
-        sqlx::query(
-            r#"
-            CREATE TABLE todos (
-                id TEXT PRIMARY KEY,
-                course_id TEXT NOT NULL,
-                title TEXT NOT NULL,
-                due_date TEXT NOT NULL,
-                status TEXT NOT NULL,
-                completed_at TEXT,
-                is_archived INTEGER NOT NULL DEFAULT 0,
-                updated_at TEXT NOT NULL,
-                sync_state TEXT NOT NULL CHECK(sync_state IN ('pending', 'synced')) DEFAULT 'pending',
-                last_synced_at TEXT,
-                FOREIGN KEY (course_id) REFERENCES courses(id) ON DELETE CASCADE
-            )
-            "#,
-        )
-        .execute(&pool)
-        .await
-        .expect("Failed to create todos table");
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("Failed to run migrations");
 
         pool
     }
 
+    /// 毎回同じページを返す Notion (作ったページは "course_id" / "todo_id" の無いページとして加わる)
+    struct FixedNotionClient {
+        courses: std::sync::Mutex<Vec<Course>>,
+        todos: std::sync::Mutex<Vec<Todo>>,
+        /// 授業の Push を失敗させる
+        fail_course: std::sync::atomic::AtomicBool,
+        /// 本文の Push を失敗させる
+        fail_body: std::sync::atomic::AtomicBool,
+    }
+
+    #[async_trait]
+    impl NotionClient for FixedNotionClient {
+        async fn fetch_courses(&self) -> Result<Vec<Course>, AppError> {
+            Ok(self.courses.lock().unwrap().clone())
+        }
+
+        async fn fetch_todos(&self) -> Result<Vec<Todo>, AppError> {
+            Ok(self.todos.lock().unwrap().clone())
+        }
+
+        async fn push_course(&self, _course: &Course) -> Result<(), AppError> {
+            if self.fail_course.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(AppError::BadRequest("Failed to push course".to_string()));
+            }
+            Ok(())
+        }
+
+        async fn push_todo(&self, _todo: &Todo) -> Result<(), AppError> {
+            Ok(())
+        }
+
+        async fn create_course(&self, course: &Course) -> Result<String, AppError> {
+            if self.fail_course.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(AppError::BadRequest("Failed to create course".to_string()));
+            }
+            let page_id = format!("page-{}", course.id);
+            self.courses.lock().unwrap().push(Course {
+                id: page_id.clone(),
+                version: 0,
+                updated_at: chrono::Utc::now(),
+                sync_state: "synced".to_string(),
+                last_synced_at: Some(chrono::Utc::now()),
+                notion_page_id: Some(page_id.clone()),
+                ..course.clone()
+            });
+            Ok(page_id)
+        }
+
+        async fn create_todo(&self, todo: &Todo, course_page_id: &str) -> Result<String, AppError> {
+            let page_id = format!("page-{}", todo.id);
+            self.todos.lock().unwrap().push(Todo {
+                id: page_id.clone(),
+                course_id: course_page_id.to_string(),
+                notes: String::new(),
+                progress: Default::default(),
+                version: 0,
+                updated_at: chrono::Utc::now(),
+                sync_state: "synced".to_string(),
+                last_synced_at: Some(chrono::Utc::now()),
+                notion_page_id: Some(page_id.clone()),
+                ..todo.clone()
+            });
+            Ok(page_id)
+        }
+
+        async fn fetch_status_mapping(&self) -> Result<StatusMapping, AppError> {
+            Ok(StatusMapping::default())
+        }
+
+        async fn fetch_todo_body(&self, _page_id: &str) -> Result<TodoBody, AppError> {
+            Ok(TodoBody { notes: "Chapter 3".to_string(), subtasks: Vec::new() })
+        }
+
+        async fn push_todo_body(&self, _page_id: &str, _body: &TodoBody) -> Result<(), AppError> {
+            if self.fail_body.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(AppError::BadRequest("Failed to push todo body".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    fn notion_pages() -> FixedNotionClient {
+        let edited = chrono::DateTime::parse_from_rfc3339("2026-10-18T09:00:00Z").unwrap().to_utc();
+        let course = Course {
+            id: "course-page".to_string(),
+            title: "Physics".to_string(),
+            semesters: vec!["2A1".to_string()],
+            meetings: vec![CourseMeeting::period(Weekday::Mon, 2)],
+            room: Some("E21".to_string()),
+            instructors: vec!["Prof. Maxwell".to_string()],
+            version: 0,
+            is_archived: false,
+            updated_at: edited,
+            sync_state: "synced".to_string(),
+            last_synced_at: Some(edited),
+            notion_page_id: None,
+        };
+        let todo = Todo {
+            id: "todo-page".to_string(),
+            course_id: course.id.clone(),
+            title: "Lab report".to_string(),
+            due_date: DueDate::AllDay(chrono::NaiveDate::from_ymd_opt(2026, 10, 20).unwrap()),
+            due_end: None,
+            due_timezone: None,
+            status: TodoStatus::NotStarted,
+            priority: None,
+            tags: vec!["lab".to_string()],
+            notes: String::new(),
+            progress: Default::default(),
+            series_id: None,
+            version: 0,
+            completed_at: None,
+            is_archived: false,
+            updated_at: edited,
+            sync_state: "synced".to_string(),
+            last_synced_at: Some(edited),
+            notion_page_id: Some("todo-page".to_string()),
+        };
+        FixedNotionClient {
+            courses: std::sync::Mutex::new(vec![course]),
+            todos: std::sync::Mutex::new(vec![todo]),
+            fail_course: Default::default(),
+            fail_body: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_without_notion_edits_logs_no_changes() {
+        let db = setup_db().await;
+        let sync = SyncService::new(db.clone(), Arc::new(notion_pages()));
+
+        sync.sync_all().await.expect("Failed to sync");
+        let first = repository::fetch_changes(&db, 0, 100).await.expect("Failed to fetch changes");
+        assert_eq!((first.courses.len(), first.todos.len()), (1, 1));
+        let version = first.todos[0].version;
+
+        sync.sync_all().await.expect("Failed to sync");
+        let second = repository::fetch_changes(&db, first.cursor, 100).await.expect("Failed to fetch changes");
+        assert!(second.courses.is_empty() && second.todos.is_empty() && second.removed.is_empty(), "{:?}", second);
+        let todo = repository::find_todo_by_id(&db, "todo-page").await.unwrap().unwrap();
+        assert_eq!(todo.version, version);
+    }
+
+    #[tokio::test]
+    async fn test_todo_created_locally_keeps_its_id() {
+        let db = setup_db().await;
+        let sync = SyncService::new(db.clone(), Arc::new(notion_pages()));
+        sync.sync_all().await.expect("Failed to sync");
+
+        let req = NewTodoRequest {
+            course_id: "course-page".to_string(),
+            title: "Problem set".to_string(),
+            due_date: "2026-10-22".to_string(),
+            due_end: None,
+            due_timezone: None,
+            status: TodoStatus::NotStarted,
+            priority: None,
+            tags: Vec::new(),
+            notes: String::new(),
+        };
+        let draft = repository::insert_todo(&db, req).await.expect("Failed to insert todo");
+        sync.sync_all().await.expect("Failed to sync");
+
+        let todo = repository::find_todo_by_id(&db, &draft.id).await.unwrap().expect("the local id is kept");
+        assert_eq!(todo.sync_state, "synced");
+        assert!(!todo.is_archived, "the page is matched to the todo through its page id");
+        assert_eq!(todo.notion_page_id, Some(format!("page-{}", draft.id)));
+        assert_eq!(repository::fetch_todos(&db).await.unwrap().len(), 2, "no second todo under the page id");
+    }
+
+    #[tokio::test]
+    async fn test_course_created_locally_keeps_its_id() {
+        let db = setup_db().await;
+        let notion = Arc::new(notion_pages());
+        let sync = SyncService::new(db.clone(), notion.clone());
+
+        let req = NewCourseRequest {
+            title: "Chemistry".to_string(),
+            semesters: vec!["2A1".to_string()],
+            meetings: vec![CourseMeeting::period(Weekday::Tue, 3)],
+            room: None,
+            instructors: Vec::new(),
+        };
+        let draft = repository::insert_course(&db, req).await.expect("Failed to insert course");
+        let todo_req = NewTodoRequest {
+            course_id: draft.id.clone(),
+            title: "Lab safety quiz".to_string(),
+            due_date: "2026-10-22".to_string(),
+            due_end: None,
+            due_timezone: None,
+            status: TodoStatus::NotStarted,
+            priority: None,
+            tags: Vec::new(),
+            notes: String::new(),
+        };
+        let todo = repository::insert_todo(&db, todo_req).await.expect("Failed to insert todo");
+        sync.sync_all().await.expect("Failed to sync");
+
+        let course = repository::find_course_by_id(&db, &draft.id).await.unwrap().expect("the local id is kept");
+        assert_eq!(course.sync_state, "synced");
+        assert!(!course.is_archived, "the page is matched to the course through its page id");
+        assert_eq!(course.notion_page_id, Some(format!("page-{}", draft.id)));
+        assert_eq!(repository::fetch_courses(&db).await.unwrap().len(), 2, "no second course under the page id");
+
+        let page = notion.todos.lock().unwrap().iter().find(|t| t.id == format!("page-{}", todo.id)).cloned();
+        assert_eq!(page.expect("the todo page is created").course_id, format!("page-{}", draft.id));
+        let todo = repository::find_todo_by_id(&db, &todo.id).await.unwrap().unwrap();
+        assert_eq!(todo.course_id, draft.id, "the relation is mapped back to the local course");
+    }
+
+    #[tokio::test]
+    async fn test_failed_course_push_does_not_abort_sync() {
+        let db = setup_db().await;
+        let notion = Arc::new(notion_pages());
+        notion.fail_course.store(true, std::sync::atomic::Ordering::SeqCst);
+        let sync = SyncService::new(db.clone(), notion.clone());
+
+        let req = NewCourseRequest {
+            title: "Chemistry".to_string(),
+            semesters: vec!["2A1".to_string()],
+            meetings: Vec::new(),
+            room: None,
+            instructors: Vec::new(),
+        };
+        let draft = repository::insert_course(&db, req).await.expect("Failed to insert course");
+        let stats = sync.sync_all().await.expect("a rejected course does not fail the sync");
+        assert_eq!((stats.courses_pushed, stats.courses_pulled, stats.todos_pulled), (0, 1, 1));
+
+        let course = repository::find_course_by_id(&db, &draft.id).await.unwrap().unwrap();
+        assert_eq!(course.sync_state, "pending", "retried on the next sync");
+        assert!(!course.is_archived, "never in Notion, so not archived as missing from it");
+    }
+
+    #[tokio::test]
+    async fn test_failed_body_push_does_not_create_a_second_page() {
+        let db = setup_db().await;
+        let notion = Arc::new(notion_pages());
+        let sync = SyncService::new(db.clone(), notion.clone());
+        sync.sync_all().await.expect("Failed to sync");
+
+        let req = NewTodoRequest {
+            course_id: "course-page".to_string(),
+            title: "Problem set".to_string(),
+            due_date: "2026-10-22".to_string(),
+            due_end: None,
+            due_timezone: None,
+            status: TodoStatus::NotStarted,
+            priority: None,
+            tags: Vec::new(),
+            notes: "Exercises 1-4".to_string(),
+        };
+        let draft = repository::insert_todo(&db, req).await.expect("Failed to insert todo");
+        notion.fail_body.store(true, std::sync::atomic::Ordering::SeqCst);
+        assert!(sync.sync_all().await.is_err());
+
+        let todo = repository::find_todo_by_id(&db, &draft.id).await.unwrap().unwrap();
+        assert_eq!(todo.sync_state, "pending");
+        assert!(todo.last_synced_at.is_some(), "the page is recorded before the body push");
+
+        notion.fail_body.store(false, std::sync::atomic::Ordering::SeqCst);
+        sync.sync_all().await.expect("Failed to sync");
+        assert_eq!(notion.todos.lock().unwrap().len(), 2, "the retry updates the page it created");
+        let todo = repository::find_todo_by_id(&db, &draft.id).await.unwrap().unwrap();
+        assert_eq!(todo.sync_state, "synced");
+    }
+
     #[tokio::test]
     async fn test_push_local_pending_course() {
         let db = setup_db().await;
@@ -289,11 +736,10 @@ mod tests {
 
         let req = NewCourseRequest {
             title: "Rust Programming".to_string(),
-            semester: "Spring".to_string(),
-            day_of_week: "Monday".to_string(),
-            period: 1,
+            semesters: vec!["Spring".to_string()],
+            meetings: vec![CourseMeeting::period(Weekday::Mon, 1)],
             room: Some("A101".to_string()),
-            instructor: Some("Prof. Smith".to_string()),
+            instructors: vec!["Prof. Smith".to_string()],
         };
 
         repository::insert_course(&db, req)
@@ -326,17 +772,13 @@ mod tests {
         let now = chrono::Utc::now().to_rfc3339();
         sqlx::query(
             r#"
-            INSERT INTO courses (id, title, semester, day_of_week, period, room, instructor, is_archived, updated_at, sync_state)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO courses (id, title, room, is_archived, updated_at, sync_state)
+            VALUES (?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind("course-1")
         .bind("Local Course")
-        .bind("Spring")
-        .bind("Monday")
-        .bind(1)
         .bind("A101")
-        .bind("Prof. Smith")
         .bind(false)
         .bind(&now)
         .bind("pending")
@@ -372,11 +814,10 @@ mod tests {
 
         let req = NewCourseRequest {
             title: "Rust Programming".to_string(),
-            semester: "Spring".to_string(),
-            day_of_week: "Monday".to_string(),
-            period: 1,
+            semesters: vec!["Spring".to_string()],
+            meetings: vec![CourseMeeting::period(Weekday::Mon, 1)],
             room: Some("A101".to_string()),
-            instructor: Some("Prof. Smith".to_string()),
+            instructors: vec!["Prof. Smith".to_string()],
         };
 
         let course = repository::insert_course(&db, req)
@@ -416,11 +857,10 @@ mod tests {
 
         let req = NewCourseRequest {
             title: "Initial Title".to_string(),
-            semester: "Spring".to_string(),
-            day_of_week: "Monday".to_string(),
-            period: 1,
+            semesters: vec!["Spring".to_string()],
+            meetings: vec![CourseMeeting::period(Weekday::Mon, 1)],
             room: Some("A101".to_string()),
-            instructor: Some("Prof. Smith".to_string()),
+            instructors: vec!["Prof. Smith".to_string()],
         };
 
         let course = repository::insert_course(&db, req)
@@ -443,6 +883,43 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_sync_all_publishes_events() {
+        let db = setup_db().await;
+        let events = EventBus::default();
+        let mut receiver = events.subscribe();
+        let sync = SyncService::new(db.clone(), Arc::new(NoopNotionClient)).with_events(events);
+
+        let req = NewCourseRequest {
+            title: "Removed in Notion".to_string(),
+            semesters: Vec::new(),
+            meetings: Vec::new(),
+            room: None,
+            instructors: Vec::new(),
+        };
+        let course = repository::insert_course(&db, req).await.expect("Failed to insert course");
+
+        sync.sync_all().await.expect("Failed to sync");
+
+        let mut names = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            if let AppEvent::CourseChanged { course: changed } = &event {
+                assert_eq!(changed.id, course.id);
+                assert!(changed.is_archived, "not in Notion, so archived");
+            }
+            names.push(event.name());
+        }
+        assert_eq!(names, vec!["sync_started", "course_changed", "sync_finished"]);
+
+        // nothing changed since, so the next sync only reports itself
+        sync.sync_all().await.expect("Failed to sync");
+        let mut names = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            names.push(event.name());
+        }
+        assert_eq!(names, vec!["sync_started", "sync_finished"]);
+    }
+
     #[tokio::test]
     async fn test_archive_course_not_in_notion() {
         let db = setup_db().await;
@@ -452,11 +929,10 @@ mod tests {
         // Insert a course
         let req = NewCourseRequest {
             title: "To Be Archived".to_string(),
-            semester: "Spring".to_string(),
-            day_of_week: "Monday".to_string(),
-            period: 1,
+            semesters: vec!["Spring".to_string()],
+            meetings: vec![CourseMeeting::period(Weekday::Mon, 1)],
             room: Some("A101".to_string()),
-            instructor: Some("Prof. Smith".to_string()),
+            instructors: vec!["Prof. Smith".to_string()],
         };
 
         let course = repository::insert_course(&db, req)
@@ -483,9 +959,45 @@ mod tests {
             .expect("Failed to fetch course")
             .expect("Course not found");
 
-        assert_eq!(
-            archived.is_archived, true,
+        assert!(
+            archived.is_archived,
             "Course not in Notion should be archived"
         );
     }
+
+    #[tokio::test]
+    async fn test_sync_records_history() {
+        let db = setup_db().await;
+        let sync = SyncService::new(db.clone(), Arc::new(NoopNotionClient));
+
+        let req = NewCourseRequest {
+            title: "Removed in Notion".to_string(),
+            semesters: Vec::new(),
+            meetings: Vec::new(),
+            room: None,
+            instructors: Vec::new(),
+        };
+        let course = repository::insert_course(&db, req).await.expect("Failed to insert course");
+        repository::record_operation(&db, "laptop", "create_course", ChangeEntity::Course, &course.id, None)
+            .await
+            .expect("Failed to record operation");
+
+        sync.sync_all().await.expect("Failed to sync");
+
+        let history = repository::fetch_history(&db, ChangeEntity::Course, &course.id, 10)
+            .await
+            .expect("Failed to fetch history");
+        let sources: Vec<_> = history.iter().map(|e| (e.source, e.action.as_str())).collect();
+        assert_eq!(
+            sources,
+            vec![
+                (HistorySource::SyncPull, "archive"),
+                (HistorySource::SyncPush, "push"),
+                (HistorySource::Api, "create_course"),
+            ]
+        );
+        assert_eq!(history[2].client_id.as_deref(), Some("laptop"));
+        assert_eq!(history[1].changes["sync_state"].to, "synced");
+        assert_eq!(history[0].changes.keys().collect::<Vec<_>>(), vec!["is_archived"]);
+    }
 }
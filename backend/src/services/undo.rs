@@ -0,0 +1,348 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Sqlite, SqliteConnection, SqlitePool, Transaction};
+
+use crate::db::repository;
+use crate::error::AppError;
+use crate::models::{ChangeEntity, HistoryContext, Operation, Snapshot, UndoResponse};
+use crate::services::events::{AppEvent, EventBus};
+
+/// 端末ごとの操作ログで取り消し・やり直しをする
+///
+/// 戻した行は `pending` になり、次の同期で Notion にも反映される。
+/// 操作の後に他の端末や同期で内容が変わっていれば `409` (上書きしない)。
+/// 1 回のリクエストでまとめて記録した操作 (系列の変更) は、まとめて戻す。
+pub struct UndoService {
+    db: SqlitePool,
+    events: EventBus,
+}
+
+impl UndoService {
+    pub fn new(db: SqlitePool, events: EventBus) -> Self {
+        Self { db, events }
+    }
+
+    /// 最後の操作を取り消す (操作の前の状態に戻す)
+    pub async fn undo(&self, client_id: &str) -> Result<UndoResponse, AppError> {
+        let mut tx = self.db.begin_with("BEGIN IMMEDIATE").await?;
+        let logged = repository::last_undoable_operations_in(&mut tx, client_id).await?;
+        if logged.is_empty() {
+            return Err(AppError::Conflict("Nothing to undo".to_string()));
+        }
+        let undone_at = Utc::now();
+        let mut operations = Vec::with_capacity(logged.len());
+        for (operation, before, after) in logged {
+            operations.push(self.restore(&mut tx, operation, after.as_ref(), before.as_ref(), Some(undone_at)).await?);
+        }
+        // 記録した順に並べる (先頭が最初の操作)
+        operations.reverse();
+        self.finish(tx, operations).await
+    }
+
+    /// 最後に取り消した操作をやり直す
+    pub async fn redo(&self, client_id: &str) -> Result<UndoResponse, AppError> {
+        let mut tx = self.db.begin_with("BEGIN IMMEDIATE").await?;
+        let logged = repository::next_redoable_operations_in(&mut tx, client_id).await?;
+        if logged.is_empty() {
+            return Err(AppError::Conflict("Nothing to redo".to_string()));
+        }
+        let mut operations = Vec::with_capacity(logged.len());
+        for (operation, before, after) in logged {
+            operations.push(self.restore(&mut tx, operation, before.as_ref(), after.as_ref(), None).await?);
+        }
+        self.finish(tx, operations).await
+    }
+
+    /// 今の状態が `expected` のままなら `target` に戻す
+    async fn restore(
+        &self,
+        conn: &mut SqliteConnection,
+        operation: Operation,
+        expected: Option<&Snapshot>,
+        target: Option<&Snapshot>,
+        undone_at: Option<DateTime<Utc>>,
+    ) -> Result<Operation, AppError> {
+        let current = repository::snapshot_in(conn, operation.entity, &operation.entity_id).await?;
+        if !repository::same_state(current.as_ref(), expected) {
+            return Err(AppError::Conflict(format!(
+                "The {} was changed after '{}'",
+                operation.entity.as_str(),
+                operation.action
+            )));
+        }
+        repository::restore_snapshot_in(conn, operation.entity, &operation.entity_id, target).await?;
+
+        let action = if undone_at.is_some() { "undo" } else { "redo" };
+        let context = HistoryContext::api(&operation.client_id, format!("{}_{}", action, operation.action));
+        let restored = repository::snapshot_in(conn, operation.entity, &operation.entity_id).await?;
+        repository::record_history_in(conn, operation.entity, &operation.entity_id, &context, current.as_ref(), restored.as_ref())
+            .await?;
+        Ok(repository::set_operation_undone_in(conn, operation.id, undone_at).await?)
+    }
+
+    /// `operations` は記録した順。先頭の操作のエンティティを応答に入れ、すべての変更をイベントで流す
+    async fn finish(
+        &self,
+        mut tx: Transaction<'static, Sqlite>,
+        operations: Vec<Operation>,
+    ) -> Result<UndoResponse, AppError> {
+        let mut states = Vec::with_capacity(operations.len());
+        for operation in &operations {
+            states.push(repository::snapshot_in(&mut tx, operation.entity, &operation.entity_id).await?);
+        }
+        tx.commit().await?;
+
+        let mut operations = operations.into_iter();
+        let Some(operation) = operations.next() else {
+            return Err(AppError::Conflict("Nothing to undo".to_string()));
+        };
+        let mut response = UndoResponse { operation, course: None, todo: None, semester: None, related: operations.collect() };
+        let entities: Vec<(ChangeEntity, String)> = std::iter::once(&response.operation)
+            .chain(&response.related)
+            .map(|operation| (operation.entity, operation.entity_id.clone()))
+            .collect();
+        for (index, ((entity, id), state)) in entities.into_iter().zip(states).enumerate() {
+            match state {
+                Some(Snapshot::Course { course, .. }) => {
+                    self.events.publish(AppEvent::CourseChanged { course: course.clone() });
+                    if index == 0 {
+                        response.course = Some(course);
+                    }
+                }
+                Some(Snapshot::Todo { todo, .. }) => {
+                    self.events.publish(if todo.is_archived {
+                        AppEvent::TodoArchived { id }
+                    } else {
+                        AppEvent::TodoUpdated { todo: todo.clone() }
+                    });
+                    if index == 0 {
+                        response.todo = Some(todo);
+                    }
+                }
+                Some(Snapshot::Semester { semester }) => {
+                    self.events.publish(AppEvent::SemesterChanged { semester: semester.clone() });
+                    if index == 0 {
+                        response.semester = Some(semester);
+                    }
+                }
+                None => match entity {
+                    ChangeEntity::Course => self.events.publish(AppEvent::CourseDeleted { id }),
+                    ChangeEntity::Todo => self.events.publish(AppEvent::TodoDeleted { id }),
+                    ChangeEntity::Semester => {}
+                },
+            }
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{NewCourseRequest, NewTodoRequest, TodoStatus, UpdateSemesterRequest};
+    use crate::services::RecurrenceService;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    async fn setup_db() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to create in-memory database");
+
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("Failed to run migrations");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_undo_and_redo_per_client() {
+        let db = setup_db().await;
+        let service = UndoService::new(db.clone(), EventBus::default());
+        let course = NewCourseRequest {
+            title: "Optics".to_string(),
+            semesters: vec!["2A1".to_string()],
+            meetings: Vec::new(),
+            room: None,
+            instructors: Vec::new(),
+        };
+        let course = repository::insert_course(&db, course).await.unwrap();
+        let todo = NewTodoRequest {
+            course_id: course.id.clone(),
+            title: "Lab".to_string(),
+            due_date: "2026-10-20".to_string(),
+            due_end: None,
+            due_timezone: None,
+            status: TodoStatus::NotStarted,
+            priority: None,
+            tags: Vec::new(),
+            notes: String::new(),
+        };
+        let todo = repository::insert_todo(&db, todo).await.unwrap();
+        sqlx::query("UPDATE todos SET sync_state = 'synced', last_synced_at = ? WHERE id = ?")
+            .bind(Utc::now())
+            .bind(&todo.id)
+            .execute(&db)
+            .await
+            .unwrap();
+
+        // the watch archives the todo by accident
+        let before = repository::snapshot(&db, ChangeEntity::Todo, &todo.id).await.unwrap();
+        repository::archive_todo(&db, &todo.id).await.unwrap();
+        repository::record_operation(&db, "watch", "archive_todo", ChangeEntity::Todo, &todo.id, before)
+            .await
+            .unwrap();
+        assert!(matches!(service.undo("mac").await, Err(AppError::Conflict(_))), "other clients have their own stack");
+
+        let undone = service.undo("watch").await.unwrap();
+        assert_eq!(undone.operation.action, "archive_todo");
+        assert!(undone.operation.undone_at.is_some());
+        let restored = undone.todo.unwrap();
+        assert!(!restored.is_archived);
+        assert_eq!(restored.sync_state, "pending", "pushed to Notion on the next sync");
+
+        let redone = service.redo("watch").await.unwrap();
+        assert!(redone.todo.unwrap().is_archived);
+        assert!(matches!(service.redo("watch").await, Err(AppError::Conflict(_))));
+
+        // a change made after the operation is not overwritten
+        sqlx::query("UPDATE todos SET title = 'Lab (pulled)' WHERE id = ?")
+            .bind(&todo.id)
+            .execute(&db)
+            .await
+            .unwrap();
+        assert!(matches!(service.undo("watch").await, Err(AppError::Conflict(_))));
+        assert!(repository::find_todo_by_id(&db, &todo.id).await.unwrap().unwrap().is_archived);
+
+        // local-only columns come back too
+        sqlx::query("UPDATE todos SET series_date = '2026-10-20' WHERE id = ?")
+            .bind(&todo.id)
+            .execute(&db)
+            .await
+            .unwrap();
+        let before = repository::snapshot(&db, ChangeEntity::Todo, &todo.id).await.unwrap();
+        repository::set_todo_reminder_offsets(&db, &todo.id, Some(&[60])).await.unwrap();
+        sqlx::query("UPDATE todos SET series_date = NULL WHERE id = ?")
+            .bind(&todo.id)
+            .execute(&db)
+            .await
+            .unwrap();
+        repository::record_operation(&db, "watch", "update_todo_reminders", ChangeEntity::Todo, &todo.id, before)
+            .await
+            .unwrap();
+        service.undo("watch").await.unwrap();
+        match repository::snapshot(&db, ChangeEntity::Todo, &todo.id).await.unwrap() {
+            Some(Snapshot::Todo { series_date, reminder_offsets, .. }) => {
+                assert_eq!(series_date, "2026-10-20".parse().ok());
+                assert_eq!(reminder_offsets, None);
+            }
+            other => panic!("unexpected snapshot: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_undo_series_change_as_one_step() {
+        let db = setup_db().await;
+        let service = UndoService::new(db.clone(), EventBus::default());
+        let course = NewCourseRequest {
+            title: "Optics".to_string(),
+            semesters: vec!["2A1".to_string()],
+            meetings: Vec::new(),
+            room: None,
+            instructors: Vec::new(),
+        };
+        let course = repository::insert_course(&db, course).await.unwrap();
+        let todo = NewTodoRequest {
+            course_id: course.id.clone(),
+            title: "Weekly report".to_string(),
+            due_date: "2026-10-19T10:00:00+09:00".to_string(),
+            due_end: None,
+            due_timezone: Some("Asia/Tokyo".to_string()),
+            status: TodoStatus::NotStarted,
+            priority: None,
+            tags: Vec::new(),
+            notes: String::new(),
+        };
+        let template = repository::insert_todo(&db, todo).await.unwrap();
+
+        // what `POST /todos/{id}/series` does
+        let start = "2026-10-19".parse().unwrap();
+        let recurrence = RecurrenceService::new(db.clone(), chrono_tz::Asia::Tokyo, 14);
+        let mut tx = db.begin().await.unwrap();
+        let before = repository::snapshot_in(&mut tx, ChangeEntity::Todo, &template.id).await.unwrap();
+        let series = repository::insert_series_in(&mut tx, &template, "FREQ=WEEKLY", start).await.unwrap();
+        let created = recurrence.materialize_in(&mut tx, &series, start).await.unwrap();
+        assert_eq!(created.len(), 2);
+        let mut entries = vec![(ChangeEntity::Todo, template.id.clone(), before)];
+        entries.extend(created.iter().map(|todo| (ChangeEntity::Todo, todo.id.clone(), None)));
+        repository::record_operation_group_in(&mut tx, "mac", "create_series", entries).await.unwrap();
+        tx.commit().await.unwrap();
+
+        let undone = service.undo("mac").await.unwrap();
+        assert_eq!(undone.operation.entity_id, template.id, "the first operation of the group");
+        assert_eq!(undone.related.len(), 2);
+        assert_eq!(undone.todo.unwrap().series_id, None);
+        assert!(repository::find_series(&db, &series.id).await.unwrap().is_none());
+        for todo in &created {
+            assert!(repository::find_todo_by_id(&db, &todo.id).await.unwrap().is_none());
+        }
+
+        let redone = service.redo("mac").await.unwrap();
+        assert_eq!(redone.todo.unwrap().series_id, Some(series.id.clone()));
+        assert!(repository::find_series(&db, &series.id).await.unwrap().unwrap().is_active);
+        for todo in &created {
+            let instance = repository::find_todo_by_id(&db, &todo.id).await.unwrap().unwrap();
+            assert_eq!(instance.series_id, Some(series.id.clone()));
+        }
+        assert!(matches!(service.redo("mac").await, Err(AppError::Conflict(_))));
+    }
+
+    #[tokio::test]
+    async fn test_undo_semester_change_with_the_previous_current() {
+        let db = setup_db().await;
+        let service = UndoService::new(db.clone(), EventBus::default());
+        for semester in ["2A1", "2A2"] {
+            let course = NewCourseRequest {
+                title: format!("Optics {}", semester),
+                semesters: vec![semester.to_string()],
+                meetings: Vec::new(),
+                room: None,
+                instructors: Vec::new(),
+            };
+            repository::insert_course(&db, course).await.unwrap();
+        }
+        let current = UpdateSemesterRequest { is_current: Some(true), ..Default::default() };
+        repository::update_semester(&db, "2A1", current).await.unwrap();
+
+        let mut tx = db.begin().await.unwrap();
+        let mut entries = Vec::new();
+        for name in ["2A2", "2A1"] {
+            let before = repository::snapshot_in(&mut tx, ChangeEntity::Semester, name).await.unwrap();
+            entries.push((ChangeEntity::Semester, name.to_string(), before));
+        }
+        let req = UpdateSemesterRequest {
+            start_date: Some("2026-10-01".to_string()),
+            end_date: None,
+            is_current: Some(true),
+        };
+        repository::update_semester_in(&mut tx, "2A2", req).await.unwrap();
+        repository::record_operation_group_in(&mut tx, "mac", "update_semester", entries).await.unwrap();
+        tx.commit().await.unwrap();
+
+        let undone = service.undo("mac").await.unwrap();
+        assert_eq!(undone.related.len(), 1, "the previous current semester is put back too");
+        let semester = undone.semester.unwrap();
+        assert_eq!((semester.start_date, semester.is_current), (None, false));
+        assert!(repository::find_semester(&db, "2A1").await.unwrap().unwrap().is_current);
+
+        service.redo("mac").await.unwrap();
+        assert!(repository::find_semester(&db, "2A2").await.unwrap().unwrap().is_current);
+        assert!(!repository::find_semester(&db, "2A1").await.unwrap().unwrap().is_current);
+
+        let history = repository::fetch_history(&db, ChangeEntity::Semester, "2A2", 10).await.unwrap();
+        let actions: Vec<_> = history.iter().map(|entry| entry.action.as_str()).collect();
+        assert_eq!(actions, vec!["redo_update_semester", "undo_update_semester", "update_semester"]);
+    }
+}
@@ -1,11 +1,28 @@
 use std::sync::Arc;
 
+use chrono_tz::Tz;
 use sqlx::SqlitePool;
+use tokio::sync::broadcast;
 
+use crate::models::{PeriodSchedule, ReminderEvent, StatusMapping};
 use crate::notion::NotionClient;
+use crate::services::EventBus;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: SqlitePool,
     pub notion: Arc<dyn NotionClient>,
+    pub statuses: Arc<StatusMapping>,
+    /// アジェンダの日付の区切りに使う現地タイムゾーン (`APP_TIMEZONE`)
+    pub timezone: Tz,
+    /// 時間割の時限ごとの時刻 (`PERIOD_TIMES`)
+    pub periods: Arc<PeriodSchedule>,
+    /// 繰り返しの todo を何日先まで作るか (`RECURRENCE_HORIZON_DAYS`)
+    pub recurrence_horizon_days: i64,
+    /// `GET /reminders/stream` に流すリマインダーのイベント
+    pub reminder_events: broadcast::Sender<ReminderEvent>,
+    /// `GET /events` に流すデータ変更のイベント
+    pub events: EventBus,
+    /// `Idempotency-Key` のレスポンスを保存しておく秒数 (`IDEMPOTENCY_TTL_SECS`)
+    pub idempotency_ttl_secs: i64,
 }
\ No newline at end of file
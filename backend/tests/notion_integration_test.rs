@@ -1,6 +1,6 @@
 use std::sync::Arc;
 use backend::{
-    models::{Course, NewCourseRequest},
+    models::{Course, CourseMeeting, Weekday},
     notion::{NotionHttpClient, NotionConfig, NotionClient},
 };
 use sqlx::SqlitePool;
@@ -22,11 +22,7 @@ async fn test_push_course_to_notion() {
         CREATE TABLE courses (
             id TEXT PRIMARY KEY,
             title TEXT NOT NULL,
-            semester TEXT NOT NULL,
-            day_of_week TEXT NOT NULL,
-            period INTEGER NOT NULL,
             room TEXT,
-            instructor TEXT,
             is_archived INTEGER NOT NULL DEFAULT 0,
             updated_at TEXT NOT NULL,
             sync_state TEXT NOT NULL CHECK(sync_state IN ('pending', 'synced')) DEFAULT 'pending',
@@ -47,33 +43,30 @@ async fn test_push_course_to_notion() {
     let course = Course {
         id: test_course_id.to_string(),
         title: format!("Integration Test Course - {}", chrono::Utc::now().timestamp()),
-        semester: "Spring".to_string(),
-        day_of_week: "Monday".to_string(),
-        period: 1,
+        semesters: vec!["Spring".to_string()],
+        meetings: vec![CourseMeeting::period(Weekday::Mon, 1), CourseMeeting::period(Weekday::Thu, 3)],
         room: Some("Test Room 101".to_string()),
-        instructor: Some("Test Instructor".to_string()),
+        instructors: vec!["Test Instructor".to_string()],
+        version: 0,
         is_archived: false,
-        updated_at: chrono::Utc::now().to_rfc3339(),
+        updated_at: chrono::Utc::now(),
         sync_state: "pending".to_string(),
         last_synced_at: None,
+        notion_page_id: None,
     };
 
     // Insert into local DB
     sqlx::query(
         r#"
-        INSERT INTO courses (id, title, semester, day_of_week, period, room, instructor, is_archived, updated_at, sync_state)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO courses (id, title, room, is_archived, updated_at, sync_state)
+        VALUES (?, ?, ?, ?, ?, ?)
         "#,
     )
     .bind(&course.id)
     .bind(&course.title)
-    .bind(&course.semester)
-    .bind(&course.day_of_week)
-    .bind(course.period)
     .bind(&course.room)
-    .bind(&course.instructor)
     .bind(course.is_archived)
-    .bind(&course.updated_at)
+    .bind(course.updated_at)
     .bind(&course.sync_state)
     .execute(&db)
     .await
@@ -94,8 +87,8 @@ async fn test_push_course_to_notion() {
         .expect("Pushed course not found in Notion");
 
     assert_eq!(pushed_course.title, course.title, "Title mismatch");
-    assert_eq!(pushed_course.semester, course.semester, "Semester mismatch");
-    assert_eq!(pushed_course.day_of_week, course.day_of_week, "Day of week mismatch");
+    assert_eq!(pushed_course.semesters, course.semesters, "Semester mismatch");
+    assert_eq!(pushed_course.meetings, course.meetings, "Meetings mismatch");
     println!("✓ Course successfully pushed and verified in Notion!");
 }
 
@@ -114,15 +107,16 @@ async fn test_push_course_title_update() {
     let updated_course = Course {
         id: test_page_id.to_string(),
         title: format!("Updated Title - {}", chrono::Utc::now().timestamp()),
-        semester: "Summer".to_string(),
-        day_of_week: "Wednesday".to_string(),
-        period: 3,
+        semesters: vec!["Summer".to_string()],
+        meetings: vec![CourseMeeting::period(Weekday::Wed, 3)],
         room: Some("Updated Room 202".to_string()),
-        instructor: Some("Updated Professor".to_string()),
+        instructors: vec!["Updated Professor".to_string()],
+        version: 0,
         is_archived: false,
-        updated_at: chrono::Utc::now().to_rfc3339(),
+        updated_at: chrono::Utc::now(),
         sync_state: "pending".to_string(),
         last_synced_at: None,
+        notion_page_id: None,
     };
 
     // Push update to Notion
@@ -138,7 +132,7 @@ async fn test_push_course_title_update() {
         .expect("Updated course not found");
 
     assert_eq!(fetched.title, updated_course.title, "Title not updated");
-    assert_eq!(fetched.semester, updated_course.semester, "Semester not updated");
+    assert_eq!(fetched.semesters, updated_course.semesters, "Semester not updated");
     println!("✓ Course update successfully verified!");
 }
 
@@ -157,14 +151,13 @@ async fn test_fetch_and_verify_courses_from_notion() {
     // Print all courses for inspection
     for course in &courses {
         println!(
-            "ID: {}, Title: {}, Semester: {}, Day: {}, Period: {}, Room: {}, Instructor: {}",
+            "ID: {}, Title: {}, Semesters: {:?}, Meetings: {}, Room: {}, Instructors: {}",
             course.id,
             course.title,
-            course.semester,
-            course.day_of_week,
-            course.period,
+            course.semesters,
+            backend::models::meeting::format_meetings(&course.meetings),
             course.room.as_deref().unwrap_or("N/A"),
-            course.instructor.as_deref().unwrap_or("N/A")
+            course.instructors.join(", ")
         );
     }
 
@@ -175,8 +168,8 @@ async fn test_fetch_and_verify_courses_from_notion() {
         assert!(!course.id.is_empty(), "Course ID should not be empty");
         // Skip courses with empty titles (they may be drafts or test pages)
         if !course.title.is_empty() {
-            assert!(!course.semester.is_empty(), "Course semester should not be empty");
-            assert!(!course.day_of_week.is_empty(), "Course day_of_week should not be empty");
+            assert!(!course.semesters.is_empty(), "Course semesters should not be empty");
+            assert!(!course.meetings.is_empty(), "Course meetings should not be empty");
         }
     }
 
@@ -197,11 +190,7 @@ async fn test_push_and_pull_roundtrip() {
         CREATE TABLE courses (
             id TEXT PRIMARY KEY,
             title TEXT NOT NULL,
-            semester TEXT NOT NULL,
-            day_of_week TEXT NOT NULL,
-            period INTEGER NOT NULL,
             room TEXT,
-            instructor TEXT,
             is_archived INTEGER NOT NULL DEFAULT 0,
             updated_at TEXT NOT NULL,
             sync_state TEXT NOT NULL CHECK(sync_state IN ('pending', 'synced')) DEFAULT 'pending',
@@ -224,19 +213,15 @@ async fn test_push_and_pull_roundtrip() {
     for course in &courses {
         sqlx::query(
             r#"
-            INSERT INTO courses (id, title, semester, day_of_week, period, room, instructor, is_archived, updated_at, sync_state)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO courses (id, title, room, is_archived, updated_at, sync_state)
+            VALUES (?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&course.id)
         .bind(&course.title)
-        .bind(&course.semester)
-        .bind(&course.day_of_week)
-        .bind(course.period)
         .bind(&course.room)
-        .bind(&course.instructor)
         .bind(course.is_archived)
-        .bind(&course.updated_at)
+        .bind(course.updated_at)
         .bind("synced")
         .execute(&db)
         .await
@@ -248,7 +233,7 @@ async fn test_push_and_pull_roundtrip() {
     if !courses.is_empty() {
         let mut modified = courses[0].clone();
         modified.title = format!("Modified - {}", chrono::Utc::now().timestamp());
-        modified.instructor = Some("New Instructor".to_string());
+        modified.instructors = vec!["New Instructor".to_string()];
 
         // Step 4: Push back to Notion
         let result = notion.push_course(&modified).await;
@@ -263,9 +248,9 @@ async fn test_push_and_pull_roundtrip() {
             .expect("Modified course not found after push");
 
         println!(
-            "Step 5: Verified - Title: {}, Instructor: {}",
+            "Step 5: Verified - Title: {}, Instructors: {:?}",
             verified.title,
-            verified.instructor.as_deref().unwrap_or("N/A")
+            verified.instructors
         );
 
         assert_eq!(verified.title, modified.title, "Title not persisted");
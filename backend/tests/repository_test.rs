@@ -0,0 +1,920 @@
+use backend::db::repository;
+use backend::models::{
+    ChangeEntity, CourseListQuery, CourseMeeting, HistorySource, IdempotencyState, InstructorQuery, RemovalReason, NewCourseRequest, NewSubtaskRequest, NewTodoRequest, Priority, SortOrder, StatusMapping, TodoListQuery, TodoSortField,
+    StoredResponse, SubtaskItem, TodoStatus, UpdateCourseRequest, UpdateSemesterRequest, UpdateSubtaskRequest, UpdateTodoRequest, Weekday,
+};
+use backend::services::ReminderService;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Asia::Tokyo;
+use chrono_tz::UTC;
+use sqlx::SqlitePool;
+use sqlx::sqlite::SqlitePoolOptions;
+
+async fn setup_db() -> SqlitePool {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("Failed to create in-memory database");
+
+    sqlx::migrate!("./migrations")
+        .run(&pool)
+        .await
+        .expect("Failed to run migrations");
+
+    pool
+}
+
+fn new_course(title: &str) -> NewCourseRequest {
+    NewCourseRequest {
+        title: title.to_string(),
+        semesters: vec!["2A1".to_string()],
+        meetings: vec![CourseMeeting::period(Weekday::Mon, 2)],
+        room: Some("E21".to_string()),
+        instructors: vec!["Prof. Maxwell".to_string()],
+    }
+}
+
+async fn insert_todo(db: &SqlitePool, course_id: &str, title: &str, due_date: &str, status: TodoStatus) -> String {
+    repository::insert_todo(
+        db,
+        NewTodoRequest {
+            course_id: course_id.to_string(),
+            title: title.to_string(),
+            due_date: due_date.to_string(),
+            due_end: None,
+            due_timezone: None,
+            status,
+            priority: None,
+            tags: Vec::new(),
+            notes: String::new(),
+        },
+    )
+    .await
+    .expect("Failed to insert todo")
+    .id
+}
+
+#[tokio::test]
+async fn test_query_todos_filters() {
+    let db = setup_db().await;
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&db).await.unwrap();
+
+    insert_todo(&db, "course-a", "Lab report 1", "2026-10-20", TodoStatus::NotStarted).await;
+    insert_todo(&db, "course-a", "Reading quiz", "2026-10-22", TodoStatus::Done).await;
+    insert_todo(&db, "course-b", "Lab report 2", "2026-10-30", TodoStatus::InProgress).await;
+    let archived = insert_todo(&db, "course-a", "Old report", "2026-10-21", TodoStatus::NotStarted).await;
+    repository::archive_todo(&db, &archived).await.unwrap();
+
+    let by_course = TodoListQuery { course_id: Some("course-a".to_string()), ..Default::default() };
+    let todos = repository::query_todos(&db, &by_course, None, &UTC).await.unwrap();
+    assert_eq!(todos.len(), 2, "archived todos are excluded by default");
+
+    let by_status = TodoListQuery { status: Some("not_started, in_progress".to_string()), ..Default::default() };
+    let todos = repository::query_todos(&db, &by_status, None, &UTC).await.unwrap();
+    assert_eq!(todos.len(), 2);
+
+    let this_week = TodoListQuery {
+        due_from: Some("2026-10-19".to_string()),
+        due_to: Some("2026-10-25".to_string()),
+        sort: TodoSortField::DueDate,
+        order: SortOrder::Asc,
+        ..Default::default()
+    };
+    let todos = repository::query_todos(&db, &this_week, None, &UTC).await.unwrap();
+    let titles: Vec<_> = todos.iter().map(|t| t.title.as_str()).collect();
+    assert_eq!(titles, vec!["Lab report 1", "Reading quiz"]);
+
+    let by_title = TodoListQuery { q: Some("lab".to_string()), ..Default::default() };
+    let todos = repository::query_todos(&db, &by_title, None, &UTC).await.unwrap();
+    assert_eq!(todos.len(), 2, "title match is case-insensitive for ASCII");
+
+    let only_archived = TodoListQuery { archived: Some(true), ..Default::default() };
+    let todos = repository::query_todos(&db, &only_archived, None, &UTC).await.unwrap();
+    assert_eq!(todos.len(), 1);
+    assert_eq!(todos[0].id, archived);
+}
+
+#[tokio::test]
+async fn test_timed_due_dates_are_stored_in_utc() {
+    let db = setup_db().await;
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&db).await.unwrap();
+
+    let timed = insert_todo(&db, "course-a", "Submit essay", "2026-10-25T23:59:00+09:00", TodoStatus::NotStarted).await;
+    insert_todo(&db, "course-a", "Next week", "2026-10-26", TodoStatus::NotStarted).await;
+
+    let todo = repository::find_todo_by_id(&db, &timed).await.unwrap().unwrap();
+    assert_eq!(todo.due_date.to_string(), "2026-10-25T14:59:00Z");
+
+    let until_sunday = TodoListQuery { due_to: Some("2026-10-25".to_string()), ..Default::default() };
+    let todos = repository::query_todos(&db, &until_sunday, None, &UTC).await.unwrap();
+    assert_eq!(todos.len(), 1, "an all-day upper bound covers timed due dates on that day");
+
+    let until_noon = TodoListQuery { due_to: Some("2026-10-25T12:00:00Z".to_string()), ..Default::default() };
+    let todos = repository::query_todos(&db, &until_noon, None, &UTC).await.unwrap();
+    assert!(todos.is_empty());
+}
+
+#[tokio::test]
+async fn test_all_day_due_range_is_a_local_day() {
+    let db = setup_db().await;
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&db).await.unwrap();
+
+    let after_midnight = insert_todo(&db, "course-a", "Early lab", "2026-10-21T08:30:00+09:00", TodoStatus::NotStarted).await;
+    let morning = insert_todo(&db, "course-a", "Quiz", "2026-10-20T08:00:00+09:00", TodoStatus::NotStarted).await;
+    let todo = repository::find_todo_by_id(&db, &after_midnight).await.unwrap().unwrap();
+    assert_eq!(todo.due_date.to_string(), "2026-10-20T23:30:00Z");
+
+    let until_tuesday = TodoListQuery { due_to: Some("2026-10-20".to_string()), ..Default::default() };
+    let todos = repository::query_todos(&db, &until_tuesday, None, &Tokyo).await.unwrap();
+    let ids: Vec<_> = todos.iter().map(|t| t.id.as_str()).collect();
+    assert_eq!(ids, vec![morning.as_str()], "in Tokyo the early lab is due on the 21st");
+    let todos = repository::query_todos(&db, &until_tuesday, None, &UTC).await.unwrap();
+    assert_eq!(todos.len(), 2);
+
+    let from_wednesday = TodoListQuery { due_from: Some("2026-10-21".to_string()), ..Default::default() };
+    let todos = repository::query_todos(&db, &from_wednesday, None, &Tokyo).await.unwrap();
+    let ids: Vec<_> = todos.iter().map(|t| t.id.as_str()).collect();
+    assert_eq!(ids, vec![after_midnight.as_str()]);
+}
+
+#[tokio::test]
+async fn test_due_range_overlaps_filter() {
+    let db = setup_db().await;
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&db).await.unwrap();
+
+    let project = repository::insert_todo(
+        &db,
+        NewTodoRequest {
+            course_id: "course-a".to_string(),
+            title: "Group project".to_string(),
+            due_date: "2026-10-12".to_string(),
+            due_end: Some("2026-10-21".to_string()),
+            due_timezone: None,
+            status: TodoStatus::NotStarted,
+            priority: None,
+            tags: Vec::new(),
+            notes: String::new(),
+        },
+    )
+    .await
+    .unwrap();
+    let exam = repository::insert_todo(
+        &db,
+        NewTodoRequest {
+            course_id: "course-a".to_string(),
+            title: "Midterm".to_string(),
+            due_date: "2026-10-22T10:00:00+09:00".to_string(),
+            due_end: Some("2026-10-22T11:30:00+09:00".to_string()),
+            due_timezone: Some("Asia/Tokyo".to_string()),
+            status: TodoStatus::NotStarted,
+            priority: None,
+            tags: Vec::new(),
+            notes: String::new(),
+        },
+    )
+    .await
+    .unwrap();
+    insert_todo(&db, "course-a", "Last week", "2026-10-16", TodoStatus::NotStarted).await;
+
+    let stored = repository::find_todo_by_id(&db, &exam.id).await.unwrap().unwrap();
+    assert_eq!(stored.due_end.unwrap().to_string(), "2026-10-22T02:30:00Z");
+    assert_eq!(stored.due_timezone.as_deref(), Some("Asia/Tokyo"));
+
+    let this_week = TodoListQuery {
+        due_from: Some("2026-10-19".to_string()),
+        due_to: Some("2026-10-25".to_string()),
+        sort: TodoSortField::DueDate,
+        order: SortOrder::Asc,
+        ..Default::default()
+    };
+    let todos = repository::query_todos(&db, &this_week, None, &UTC).await.unwrap();
+    let ids: Vec<_> = todos.iter().map(|t| t.id.as_str()).collect();
+    assert_eq!(ids, vec![project.id.as_str(), exam.id.as_str()], "the project is active until its end");
+
+    let last_day = TodoListQuery { due_from: Some("2026-10-21T15:00:00Z".to_string()), ..Default::default() };
+    let todos = repository::query_todos(&db, &last_day, None, &UTC).await.unwrap();
+    assert_eq!(todos.len(), 2, "an all-day end covers the whole day");
+    let todos = repository::query_todos(&db, &last_day, None, &Tokyo).await.unwrap();
+    assert_eq!(todos.len(), 1, "in Tokyo that instant is already the 22nd");
+
+    repository::update_todo(
+        &db,
+        &project.id,
+        UpdateTodoRequest { title: None, due_date: None, due_end: Some("".to_string()), due_timezone: None, status: None, priority: None, tags: None, notes: None },
+        &StatusMapping::default(),
+    )
+    .await
+    .unwrap();
+    let todos = repository::query_todos(&db, &this_week, None, &UTC).await.unwrap();
+    assert_eq!(todos.len(), 1, "clearing the end leaves only the start date");
+}
+
+#[tokio::test]
+async fn test_query_todos_cursor_pagination() {
+    let db = setup_db().await;
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&db).await.unwrap();
+
+    for day in 1..=5 {
+        insert_todo(&db, "course-a", &format!("Todo {}", day), &format!("2026-11-0{}", day), TodoStatus::NotStarted).await;
+    }
+
+    let mut query = TodoListQuery {
+        sort: TodoSortField::DueDate,
+        order: SortOrder::Asc,
+        ..Default::default()
+    };
+
+    let first = repository::query_todos(&db, &query, Some(2), &UTC).await.unwrap();
+    assert_eq!(first.iter().map(|t| t.title.as_str()).collect::<Vec<_>>(), vec!["Todo 1", "Todo 2"]);
+
+    query.cursor = Some(first[1].id.clone());
+    let second = repository::query_todos(&db, &query, Some(2), &UTC).await.unwrap();
+    assert_eq!(second.iter().map(|t| t.title.as_str()).collect::<Vec<_>>(), vec!["Todo 3", "Todo 4"]);
+
+    query.cursor = Some(second[1].id.clone());
+    let last = repository::query_todos(&db, &query, Some(2), &UTC).await.unwrap();
+    assert_eq!(last.iter().map(|t| t.title.as_str()).collect::<Vec<_>>(), vec!["Todo 5"]);
+}
+
+#[tokio::test]
+async fn test_search_todos_and_courses() {
+    let db = setup_db().await;
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&db).await.unwrap();
+
+    let course = repository::insert_course(&db, new_course("電磁気学")).await.unwrap();
+    let report = insert_todo(&db, &course.id, "電磁気レポート", "2026-10-20", TodoStatus::NotStarted).await;
+    let archived = insert_todo(&db, &course.id, "電磁気 old", "2026-10-20", TodoStatus::NotStarted).await;
+    repository::archive_todo(&db, &archived).await.unwrap();
+
+    // trigram MATCH path
+    let hits = repository::search(&db, "maxwell", 10).await.unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].kind, "course");
+    assert!(hits[0].snippet.contains("<mark>Maxwell</mark>"));
+
+    let hits = repository::search(&db, "電磁気", 10).await.unwrap();
+    assert_eq!(hits.len(), 2, "archived todos are not indexed");
+    assert!(hits.iter().any(|h| h.kind == "todo" && h.id == report));
+
+    // short terms fall back to a substring scan
+    let hits = repository::search(&db, "レポ", 10).await.unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].snippet, "電磁気<mark>レポ</mark>ート");
+
+    // index follows updates
+    repository::update_todo(
+        &db,
+        &report,
+        UpdateTodoRequest { title: Some("Lab write-up".to_string()), due_date: None, due_end: None, due_timezone: None, status: None, priority: None, tags: None, notes: None },
+        &StatusMapping::default(),
+    )
+    .await
+    .unwrap();
+    assert!(repository::search(&db, "レポート", 10).await.unwrap().is_empty());
+    assert_eq!(repository::search(&db, "write", 10).await.unwrap().len(), 1);
+
+    // the index is keyed through search_ids, so hits survive VACUUM renumbering rowids
+    let essay = insert_todo(&db, &course.id, "Late essay", "2026-10-21", TodoStatus::NotStarted).await;
+    sqlx::query("DELETE FROM todos WHERE id = ?").bind(&report).execute(&db).await.unwrap();
+    sqlx::query("VACUUM").execute(&db).await.unwrap();
+    let hits = repository::search(&db, "essay", 10).await.unwrap();
+    assert_eq!(hits.iter().map(|h| h.id.as_str()).collect::<Vec<_>>(), vec![essay.as_str()]);
+}
+
+#[tokio::test]
+async fn test_update_and_archive_course() {
+    let db = setup_db().await;
+    let course = repository::insert_course(&db, new_course("Linear Algebra")).await.unwrap();
+    sqlx::query("UPDATE courses SET sync_state = 'synced' WHERE id = ?")
+        .bind(&course.id)
+        .execute(&db)
+        .await
+        .unwrap();
+
+    let updated = repository::update_course(
+        &db,
+        &course.id,
+        UpdateCourseRequest {
+            title: None,
+            semesters: None,
+            meetings: Some(vec![
+                CourseMeeting::period(Weekday::Mon, 3),
+                CourseMeeting::period(Weekday::Mon, 4),
+                CourseMeeting {
+                    period: None,
+                    start_time: NaiveTime::from_hms_opt(13, 0, 0),
+                    end_time: NaiveTime::from_hms_opt(14, 30, 0),
+                    room: Some("Lab 2".to_string()),
+                    ..CourseMeeting::period(Weekday::Thu, 1)
+                },
+            ]),
+            room: Some("K301".to_string()),
+            instructors: None,
+        },
+    )
+    .await
+    .unwrap()
+    .expect("Course not found");
+    assert_eq!(updated.title, "Linear Algebra");
+    let stored = repository::find_course_by_id(&db, &course.id).await.unwrap().unwrap();
+    assert_eq!(stored.meetings, updated.meetings, "meetings are replaced and keep their order");
+    assert_eq!(stored.meetings[2].room.as_deref(), Some("Lab 2"));
+    assert_eq!(updated.room.as_deref(), Some("K301"));
+    assert_eq!(updated.sync_state, "pending");
+
+    assert!(repository::archive_course(&db, &course.id).await.unwrap());
+    assert!(repository::fetch_courses(&db).await.unwrap().is_empty());
+    let pending = repository::fetch_pending_courses(&db).await.unwrap();
+    assert_eq!(pending.len(), 1, "archived course must still be pushed");
+    assert!(pending[0].is_archived);
+}
+
+#[tokio::test]
+async fn test_delete_only_never_synced_drafts() {
+    let db = setup_db().await;
+    let course = repository::insert_course(&db, new_course("Draft course")).await.unwrap();
+    let draft = insert_todo(&db, &course.id, "Draft todo", "2026-10-20", TodoStatus::NotStarted).await;
+    let synced = insert_todo(&db, &course.id, "Synced todo", "2026-10-20", TodoStatus::NotStarted).await;
+    sqlx::query("UPDATE todos SET sync_state = 'synced', last_synced_at = '2026-10-18T00:00:00Z' WHERE id = ?")
+        .bind(&synced)
+        .execute(&db)
+        .await
+        .unwrap();
+
+    assert!(repository::delete_todo_draft(&db, &draft).await.unwrap());
+    assert!(repository::find_todo_by_id(&db, &draft).await.unwrap().is_none());
+    assert!(!repository::delete_todo_draft(&db, &synced).await.unwrap());
+
+    assert!(
+        !repository::delete_course_draft(&db, &course.id).await.unwrap(),
+        "course with synced todos must not be deleted"
+    );
+    sqlx::query("DELETE FROM todos WHERE id = ?").bind(&synced).execute(&db).await.unwrap();
+    assert!(repository::delete_course_draft(&db, &course.id).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_completion_maintains_completed_at() {
+    let db = setup_db().await;
+    let statuses = StatusMapping::default();
+    let course = repository::insert_course(&db, new_course("Physics")).await.unwrap();
+    let id = insert_todo(&db, &course.id, "Problem set", "2026-10-20", TodoStatus::NotStarted).await;
+
+    let done = repository::set_todo_completed(&db, &id, true, &statuses).await.unwrap().unwrap();
+    assert_eq!(done.status, TodoStatus::Done);
+    let completed_at = done.completed_at.expect("completed_at should be stamped");
+
+    // completing again keeps the original timestamp
+    let again = repository::set_todo_completed(&db, &id, true, &statuses).await.unwrap().unwrap();
+    assert_eq!(again.completed_at, Some(completed_at));
+
+    let reopened = repository::set_todo_completed(&db, &id, false, &statuses).await.unwrap().unwrap();
+    assert_eq!(reopened.status, TodoStatus::NotStarted);
+    assert!(reopened.completed_at.is_none());
+
+    // PATCH with a status follows the same rule
+    let patched = repository::update_todo(
+        &db,
+        &id,
+        UpdateTodoRequest { title: None, due_date: None, due_end: None, due_timezone: None, status: Some(TodoStatus::Custom("最終確認".to_string())), priority: None, tags: None, notes: None },
+        &statuses,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(patched.completed_at.is_none(), "最終確認 is in the in-progress group");
+    let stored = repository::find_todo_by_id(&db, &id).await.unwrap().unwrap();
+    assert_eq!(stored.completed_at, patched.completed_at);
+    assert_eq!(stored.sync_state, "pending");
+}
+
+#[tokio::test]
+async fn test_statuses_stored_as_notion_names_are_normalized() {
+    let db = setup_db().await;
+    let statuses = StatusMapping::default();
+    let course = repository::insert_course(&db, new_course("Physics")).await.unwrap();
+    let legacy = insert_todo(&db, &course.id, "Old report", "2026-10-20", TodoStatus::Custom("完了".to_string())).await;
+    let review = insert_todo(&db, &course.id, "Draft", "2026-10-20", TodoStatus::Custom("最終確認".to_string())).await;
+
+    assert_eq!(repository::normalize_todo_statuses(&db, &statuses).await.unwrap(), 1);
+    assert_eq!(repository::find_todo_by_id(&db, &legacy).await.unwrap().unwrap().status, TodoStatus::Done);
+    assert_eq!(repository::find_todo_by_id(&db, &review).await.unwrap().unwrap().status, TodoStatus::Custom("最終確認".to_string()));
+
+    // a PATCH with the Notion name of a representative stores the key
+    let rename = UpdateTodoRequest { title: None, due_date: None, due_end: None, due_timezone: None, status: Some(TodoStatus::Custom("進行中".to_string())), priority: None, tags: None, notes: None };
+    let patched = repository::update_todo(&db, &review, rename, &statuses).await.unwrap().unwrap();
+    assert_eq!(patched.status, TodoStatus::InProgress);
+}
+
+#[tokio::test]
+async fn test_agenda_overdue_and_window() {
+    let db = setup_db().await;
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&db).await.unwrap();
+    let statuses = StatusMapping::default();
+    let done = statuses.done_statuses();
+
+    let now = "2026-10-19T03:00:00Z".parse::<DateTime<Utc>>().unwrap();
+    let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+
+    let late = insert_todo(&db, "course-a", "Late report", "2026-10-18", TodoStatus::NotStarted).await;
+    let this_morning = insert_todo(&db, "course-a", "Morning quiz", "2026-10-19T01:00:00Z", TodoStatus::InProgress).await;
+    let due_today = insert_todo(&db, "course-a", "Reading", "2026-10-19", TodoStatus::NotStarted).await;
+    insert_todo(&db, "course-a", "Finished", "2026-10-18", TodoStatus::Done).await;
+    insert_todo(&db, "course-a", "Next month", "2026-11-19", TodoStatus::NotStarted).await;
+
+    let overdue = repository::fetch_overdue_todos(&db, &done, None, now, today).await.unwrap();
+    let ids: Vec<_> = overdue.iter().map(|t| t.id.as_str()).collect();
+    assert_eq!(ids, vec![late.as_str(), this_morning.as_str()], "done todos are never overdue");
+
+    // 2026-10-19 00:00 to 2026-10-20 00:00 in Tokyo
+    let window = (
+        "2026-10-18T15:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+        "2026-10-19T15:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+    );
+    let todos = repository::fetch_agenda_todos(&db, &done, None, window, now, today, &Tokyo).await.unwrap();
+    let ids: Vec<_> = todos.iter().map(|t| t.id.as_str()).collect();
+    assert_eq!(ids, vec![due_today.as_str()], "overdue todos are not repeated in the window");
+}
+
+#[tokio::test]
+async fn test_timetable_entries_count_open_todos() {
+    let db = setup_db().await;
+    let done = StatusMapping::default().done_statuses();
+
+    let physics = repository::insert_course(&db, new_course("Physics")).await.unwrap();
+    let mut other_term = new_course("History");
+    other_term.semesters = vec!["2A2".to_string()];
+    repository::insert_course(&db, other_term).await.unwrap();
+
+    insert_todo(&db, &physics.id, "Problem set", "2026-10-20", TodoStatus::NotStarted).await;
+    insert_todo(&db, &physics.id, "Lab", "2026-10-21", TodoStatus::InProgress).await;
+    insert_todo(&db, &physics.id, "Quiz", "2026-10-14", TodoStatus::Done).await;
+    let archived = insert_todo(&db, &physics.id, "Old", "2026-10-01", TodoStatus::NotStarted).await;
+    repository::archive_todo(&db, &archived).await.unwrap();
+
+    let entries = repository::fetch_timetable_entries(&db, Some("2A1"), &done).await.unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].course.id, physics.id);
+    assert_eq!(entries[0].open_todos, 2, "done and archived todos are not counted");
+
+    let all = repository::fetch_timetable_entries(&db, None, &done).await.unwrap();
+    assert_eq!(all.len(), 2);
+}
+
+#[tokio::test]
+async fn test_semesters_link_courses_and_filter_lists() {
+    let db = setup_db().await;
+
+    let mut full_year = new_course("Seminar");
+    full_year.semesters = vec!["2A1".to_string(), "2A2".to_string()];
+    let seminar = repository::insert_course(&db, full_year).await.unwrap();
+    let physics = repository::insert_course(&db, new_course("Physics")).await.unwrap();
+    insert_todo(&db, &seminar.id, "Presentation", "2026-10-20", TodoStatus::NotStarted).await;
+    insert_todo(&db, &physics.id, "Problem set", "2026-10-21", TodoStatus::NotStarted).await;
+
+    let fetched = repository::find_course_by_id(&db, &seminar.id).await.unwrap().unwrap();
+    assert_eq!(fetched.semesters, vec!["2A1", "2A2"], "order of the multi-select is kept");
+
+    let semesters = repository::fetch_semesters(&db).await.unwrap();
+    let counts: Vec<_> = semesters.iter().map(|s| (s.name.as_str(), s.course_count)).collect();
+    assert_eq!(counts, vec![("2A1", 2), ("2A2", 1)]);
+
+    let second_term = CourseListQuery { semester: Some("2A2".to_string()), ..Default::default() };
+    let courses = repository::query_courses(&db, &second_term).await.unwrap();
+    assert_eq!(courses.len(), 1);
+    assert_eq!(courses[0].id, seminar.id);
+
+    // nothing is current until a semester is marked
+    let current = TodoListQuery { semester: Some("current".to_string()), ..Default::default() };
+    assert!(repository::query_todos(&db, &current, None, &UTC).await.unwrap().is_empty());
+
+    let req = UpdateSemesterRequest { start_date: Some("2026-10-01".to_string()), is_current: Some(true), ..Default::default() };
+    repository::update_semester(&db, "2A1", req).await.unwrap().unwrap();
+    let req = UpdateSemesterRequest { is_current: Some(true), ..Default::default() };
+    let marked = repository::update_semester(&db, "2A2", req).await.unwrap().unwrap();
+    assert!(marked.is_current);
+    let first = repository::find_semester(&db, "2A1").await.unwrap().unwrap();
+    assert!(!first.is_current, "only one semester is current at a time");
+    assert_eq!(first.start_date.unwrap().to_string(), "2026-10-01");
+
+    let todos = repository::query_todos(&db, &current, None, &UTC).await.unwrap();
+    assert_eq!(todos.iter().map(|t| t.title.as_str()).collect::<Vec<_>>(), vec!["Presentation"]);
+
+    // replacing the list drops the old links
+    let update = UpdateCourseRequest {
+        title: None,
+        semesters: Some(vec!["2A1".to_string()]),
+        meetings: None,
+        room: None,
+        instructors: None,
+    };
+    repository::update_course(&db, &seminar.id, update).await.unwrap();
+    assert!(repository::query_courses(&db, &second_term).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_instructors_list_filter_and_search() {
+    let db = setup_db().await;
+
+    let mut team_taught = new_course("Quantum Mechanics");
+    team_taught.instructors = vec!["Prof. Maxwell".to_string(), "Dr. Noether".to_string()];
+    let quantum = repository::insert_course(&db, team_taught).await.unwrap();
+    let optics = repository::insert_course(&db, new_course("Optics")).await.unwrap();
+
+    let stored = repository::find_course_by_id(&db, &quantum.id).await.unwrap().unwrap();
+    assert_eq!(stored.instructors, vec!["Prof. Maxwell", "Dr. Noether"]);
+
+    let query = CourseListQuery { instructor: Some("Dr. Noether".to_string()), ..Default::default() };
+    let courses = repository::query_courses(&db, &query).await.unwrap();
+    assert_eq!(courses.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), vec![quantum.id.as_str()]);
+
+    let instructors = repository::fetch_instructors(&db, &InstructorQuery::default()).await.unwrap();
+    let names: Vec<_> = instructors.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, vec!["Dr. Noether", "Prof. Maxwell"]);
+    let maxwell: Vec<_> = instructors[1].courses.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(maxwell, vec!["Optics", "Quantum Mechanics"]);
+
+    // the search index follows the instructor list
+    let update = UpdateCourseRequest {
+        title: None,
+        semesters: None,
+        meetings: None,
+        room: None,
+        instructors: Some(vec!["Dr. Curie".to_string()]),
+    };
+    repository::update_course(&db, &optics.id, update).await.unwrap();
+    let hits = repository::search(&db, "Curie", 10).await.unwrap();
+    assert_eq!(hits.iter().map(|h| h.id.as_str()).collect::<Vec<_>>(), vec![optics.id.as_str()]);
+    let hits = repository::search(&db, "Noether", 10).await.unwrap();
+    assert_eq!(hits.len(), 1);
+}
+
+#[tokio::test]
+async fn test_todo_notes_are_stored_and_searchable() {
+    let db = setup_db().await;
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&db).await.unwrap();
+
+    let essay = repository::insert_todo(
+        &db,
+        NewTodoRequest {
+            course_id: "course-a".to_string(),
+            title: "Essay".to_string(),
+            due_date: "2026-10-20".to_string(),
+            due_end: None,
+            due_timezone: None,
+            status: TodoStatus::NotStarted,
+            priority: None,
+            tags: Vec::new(),
+            notes: format!("{}\n\n- cite the Feynman lectures", "Outline first. ".repeat(20)),
+        },
+    )
+    .await
+    .unwrap();
+    insert_todo(&db, "course-a", "Quiz", "2026-10-21", TodoStatus::NotStarted).await;
+
+    let query = TodoListQuery { q: Some("feynman".to_string()), ..Default::default() };
+    let todos = repository::query_todos(&db, &query, None, &UTC).await.unwrap();
+    assert_eq!(todos.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), vec![essay.id.as_str()]);
+
+    let hits = repository::search(&db, "Feynman", 10).await.unwrap();
+    assert_eq!(hits.len(), 1);
+    assert!(hits[0].snippet.contains("<mark>Feynman</mark>"));
+
+    // the substring fallback only shows the text around the match
+    let hits = repository::search(&db, "ci", 10).await.unwrap();
+    assert!(hits[0].snippet.starts_with('…'));
+    assert!(hits[0].snippet.contains("<mark>ci</mark>te the Feynman lectures"));
+
+    let update = UpdateTodoRequest {
+        title: None,
+        due_date: None,
+        due_end: None,
+        due_timezone: None,
+        status: None,
+        priority: None,
+        tags: None,
+        notes: Some(String::new()),
+    };
+    let updated = repository::update_todo(&db, &essay.id, update, &StatusMapping::default()).await.unwrap().unwrap();
+    assert_eq!(updated.notes, "");
+    assert!(repository::search(&db, "Feynman", 10).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_subtasks_order_progress_and_pull() {
+    let db = setup_db().await;
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&db).await.unwrap();
+
+    let todo = insert_todo(&db, "course-a", "Thesis", "2026-12-20", TodoStatus::NotStarted).await;
+    sqlx::query("UPDATE todos SET sync_state = 'synced'").execute(&db).await.unwrap();
+
+    let mut ids = Vec::new();
+    for (title, position) in [("Outline", None), ("Draft", None), ("Topic", Some(0))] {
+        let req = NewSubtaskRequest { title: title.to_string(), done: false, position };
+        ids.push(repository::insert_subtask(&db, &todo, req).await.unwrap().id);
+    }
+    let titles = |subtasks: &[backend::models::Subtask]| subtasks.iter().map(|s| s.title.clone()).collect::<Vec<_>>();
+    let subtasks = repository::fetch_subtasks(&db, &todo).await.unwrap();
+    assert_eq!(titles(&subtasks), vec!["Topic", "Outline", "Draft"]);
+
+    let update = UpdateSubtaskRequest { done: Some(true), position: Some(99), ..Default::default() };
+    let moved = repository::update_subtask(&db, &todo, &ids[2], update).await.unwrap().unwrap();
+    assert_eq!(moved.position, 2);
+    assert!(repository::update_subtask(&db, "other-todo", &ids[2], UpdateSubtaskRequest::default()).await.unwrap().is_none());
+
+    let stored = repository::find_todo_by_id(&db, &todo).await.unwrap().unwrap();
+    assert_eq!((stored.progress.done, stored.progress.total), (1, 3));
+    assert_eq!(stored.sync_state, "pending", "checklist edits are pushed with the todo");
+
+    assert!(repository::delete_subtask(&db, &todo, &ids[0]).await.unwrap());
+    assert!(!repository::delete_subtask(&db, &todo, &ids[0]).await.unwrap());
+
+    // pulling from Notion keeps the ids of unchanged titles
+    let items = vec![
+        SubtaskItem { title: "Draft".to_string(), done: true },
+        SubtaskItem { title: "Proofread".to_string(), done: false },
+    ];
+    repository::replace_subtasks(&db, &todo, &items).await.unwrap();
+    let subtasks = repository::fetch_subtasks(&db, &todo).await.unwrap();
+    assert_eq!(titles(&subtasks), vec!["Draft", "Proofread"]);
+    assert_eq!(subtasks[0].id, ids[1]);
+    assert!(subtasks[0].done);
+}
+
+#[tokio::test]
+async fn test_todo_priority_and_tags() {
+    let db = setup_db().await;
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&db).await.unwrap();
+
+    let new_todo = |title: &str, priority: Option<&str>, tags: &[&str]| NewTodoRequest {
+        course_id: "course-a".to_string(),
+        title: title.to_string(),
+        due_date: "2026-10-20".to_string(),
+        due_end: None,
+        due_timezone: None,
+        status: TodoStatus::NotStarted,
+        priority: priority.map(str::to_string),
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+        notes: String::new(),
+    };
+    let midterm = repository::insert_todo(&db, new_todo("Midterm", Some("high"), &["exam", "group"])).await.unwrap();
+    let reading = repository::insert_todo(&db, new_todo("Chapter 4", None, &["reading"])).await.unwrap();
+    let archived = repository::insert_todo(&db, new_todo("Old quiz", None, &["exam"])).await.unwrap();
+    repository::archive_todo(&db, &archived.id).await.unwrap();
+
+    let stored = repository::find_todo_by_id(&db, &midterm.id).await.unwrap().unwrap();
+    assert_eq!(stored.priority, Some(Priority::High));
+    assert_eq!(stored.tags, vec!["exam", "group"]);
+
+    let query = TodoListQuery { tag: Some("group, reading".to_string()), ..Default::default() };
+    let todos = repository::query_todos(&db, &query, None, &UTC).await.unwrap();
+    let mut ids: Vec<_> = todos.iter().map(|t| t.id.as_str()).collect();
+    ids.sort();
+    let mut expected = vec![midterm.id.as_str(), reading.id.as_str()];
+    expected.sort();
+    assert_eq!(ids, expected);
+
+    let tags = repository::fetch_tags(&db).await.unwrap();
+    let counts: Vec<_> = tags.iter().map(|t| (t.name.as_str(), t.todo_count)).collect();
+    assert_eq!(counts, vec![("exam", 1), ("group", 1), ("reading", 1)]);
+
+    let update = UpdateTodoRequest {
+        title: None,
+        due_date: None,
+        due_end: None,
+        due_timezone: None,
+        status: None,
+        priority: Some(String::new()),
+        tags: Some(vec!["exam".to_string()]),
+        notes: None,
+    };
+    let updated = repository::update_todo(&db, &midterm.id, update, &StatusMapping::default()).await.unwrap().unwrap();
+    assert_eq!(updated.priority, None);
+    let stored = repository::find_todo_by_id(&db, &midterm.id).await.unwrap().unwrap();
+    assert_eq!(stored.tags, vec!["exam"]);
+    assert_eq!(repository::fetch_tags(&db).await.unwrap().len(), 2, "unused tags disappear");
+}
+
+#[tokio::test]
+async fn test_reminders_follow_offsets_and_are_acknowledged_once() {
+    let db = setup_db().await;
+    let statuses = std::sync::Arc::new(StatusMapping::default());
+    let service = ReminderService::new(db.clone(), statuses, chrono_tz::Asia::Tokyo);
+    let course = repository::insert_course(&db, new_course("Optics")).await.unwrap();
+    repository::set_course_reminder_offsets(&db, &course.id, &[1440]).await.unwrap();
+
+    let quiz = insert_todo(&db, &course.id, "Quiz", "2026-10-20T10:00:00+09:00", TodoStatus::NotStarted).await;
+    let report = insert_todo(&db, &course.id, "Report", "2026-10-21", TodoStatus::NotStarted).await;
+    insert_todo(&db, &course.id, "Done already", "2026-10-22", TodoStatus::Done).await;
+    repository::set_todo_reminder_offsets(&db, &report, Some(&[60])).await.unwrap();
+
+    let now: DateTime<Utc> = "2026-10-19T02:00:00Z".parse().unwrap();
+    assert_eq!(service.refresh(now).await.unwrap(), 2);
+    assert_eq!(service.refresh(now).await.unwrap(), 0);
+
+    let due = service.due(now).await.unwrap();
+    assert_eq!(due.len(), 1);
+    assert_eq!((due[0].todo_id.as_str(), due[0].offset_minutes), (quiz.as_str(), 1440));
+    assert_eq!(due[0].remind_at.to_rfc3339(), "2026-10-19T01:00:00+00:00");
+    assert_eq!(repository::take_unnotified_reminders(&db, now).await.unwrap().len(), 1);
+    assert!(repository::take_unnotified_reminders(&db, now).await.unwrap().is_empty());
+
+    // the first device claims it, the others see it gone
+    assert!(repository::acknowledge_reminder(&db, &due[0].id).await.unwrap());
+    assert!(!repository::acknowledge_reminder(&db, &due[0].id).await.unwrap());
+    assert!(service.due(now).await.unwrap().is_empty());
+    assert_eq!(service.refresh(now).await.unwrap(), 0, "acknowledged reminders are not recreated");
+
+    // back to the course default: the 60-minute reminder is replaced
+    repository::set_todo_reminder_offsets(&db, &report, None).await.unwrap();
+    let settings = repository::fetch_todo_reminder_settings(&db, &report).await.unwrap().unwrap();
+    assert_eq!((settings.offsets, settings.effective), (None, vec![1440]));
+    assert_eq!(service.refresh(now).await.unwrap(), 1);
+    let later: DateTime<Utc> = "2026-10-21T00:00:00Z".parse().unwrap();
+    assert_eq!(service.due(later).await.unwrap()[0].todo_id, report);
+
+    repository::set_course_reminder_offsets(&db, &course.id, &[]).await.unwrap();
+    service.refresh(now).await.unwrap();
+    assert!(service.due(later).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_changes_since_cursor() {
+    let db = setup_db().await;
+    let course = repository::insert_course(&db, new_course("Acoustics")).await.unwrap();
+    let first = insert_todo(&db, &course.id, "Lab 1", "2026-10-20", TodoStatus::NotStarted).await;
+    let second = insert_todo(&db, &course.id, "Lab 2", "2026-10-27", TodoStatus::NotStarted).await;
+
+    let all = repository::fetch_changes(&db, 0, 500).await.unwrap();
+    assert_eq!(all.courses.len(), 1);
+    assert_eq!(all.todos.iter().map(|t| t.id.as_str()).collect::<Vec<_>>(), vec![first.as_str(), second.as_str()]);
+    assert!(!all.has_more);
+    assert!(repository::fetch_changes(&db, all.cursor, 500).await.unwrap().todos.is_empty());
+
+    // a subtask changes the todo; archive and delete are reported as removed
+    let step = NewSubtaskRequest { title: "Calibrate".to_string(), done: false, position: None };
+    repository::insert_subtask(&db, &first, step).await.unwrap();
+    repository::archive_todo(&db, &second).await.unwrap();
+    let third = insert_todo(&db, &course.id, "Lab 3", "2026-11-03", TodoStatus::NotStarted).await;
+    repository::delete_todo_draft(&db, &third).await.unwrap();
+
+    let page = repository::fetch_changes(&db, all.cursor, 2).await.unwrap();
+    assert!(page.has_more);
+    assert_eq!(page.todos.len(), 1);
+    assert_eq!(page.todos[0].progress.total, 1);
+    assert_eq!(page.removed.len(), 1);
+    assert_eq!((page.removed[0].id.as_str(), page.removed[0].reason), (second.as_str(), RemovalReason::Archived));
+
+    let rest = repository::fetch_changes(&db, page.cursor, 2).await.unwrap();
+    assert!(!rest.has_more);
+    assert_eq!(rest.removed.len(), 1);
+    assert_eq!((rest.removed[0].id.as_str(), rest.removed[0].reason), (third.as_str(), RemovalReason::Deleted));
+    assert!(rest.cursor > page.cursor);
+}
+
+#[tokio::test]
+async fn test_idempotency_key_replays_the_first_response() {
+    let db = setup_db().await;
+    let body = br#"{"title":"Lab 1"}"#;
+    let begin = |body: &'static [u8]| repository::begin_idempotent_request(&db, "mac", "k1", "POST", "/todos", body, 3600);
+
+    assert_eq!(begin(body).await.unwrap(), IdempotencyState::New);
+    assert_eq!(begin(body).await.unwrap(), IdempotencyState::InProgress);
+
+    let response = StoredResponse {
+        status: 201,
+        content_type: Some("application/json".to_string()),
+        etag: Some("\"1\"".to_string()),
+        body: b"{}".to_vec(),
+    };
+    repository::complete_idempotent_request(&db, "mac", "k1", &response).await.unwrap();
+    assert_eq!(begin(body).await.unwrap(), IdempotencyState::Replay(response));
+    assert_eq!(begin(br#"{"title":"Lab 2"}"#).await.unwrap(), IdempotencyState::Mismatch);
+
+    // keys are per client
+    let other = repository::begin_idempotent_request(&db, "watch", "k1", "POST", "/todos", br#"{"title":"Lab 2"}"#, 3600);
+    assert_eq!(other.await.unwrap(), IdempotencyState::New);
+
+    // a failed request frees the key for a retry
+    assert_eq!(repository::begin_idempotent_request(&db, "mac", "k2", "PATCH", "/todos/t1", b"{}", 3600).await.unwrap(), IdempotencyState::New);
+    repository::abandon_idempotent_request(&db, "mac", "k2").await.unwrap();
+    assert_eq!(repository::begin_idempotent_request(&db, "mac", "k2", "PATCH", "/todos/t1", b"{}", 3600).await.unwrap(), IdempotencyState::New);
+}
+
+#[tokio::test]
+async fn test_versions_increase_with_every_change() {
+    let db = setup_db().await;
+    let statuses = StatusMapping::default();
+    let course = repository::insert_course(&db, new_course("Optics")).await.unwrap();
+    let todo_id = insert_todo(&db, &course.id, "Lab 1", "2026-10-20", TodoStatus::NotStarted).await;
+    let created = repository::find_todo_by_id(&db, &todo_id).await.unwrap().unwrap();
+
+    let rename = UpdateTodoRequest { title: Some("Lab 1 report".to_string()), due_date: None, due_end: None, due_timezone: None, status: None, priority: None, tags: None, notes: None };
+    let renamed = repository::update_todo(&db, &todo_id, rename, &statuses).await.unwrap().unwrap();
+    assert!(renamed.version > created.version);
+    assert_eq!(repository::find_todo_by_id(&db, &todo_id).await.unwrap().unwrap().version, renamed.version, "returned version is the stored one");
+
+    // rows aggregated into the todo count as changes to it
+    let step = NewSubtaskRequest { title: "Measure".to_string(), done: false, position: None };
+    repository::insert_subtask(&db, &todo_id, step).await.unwrap();
+    assert!(repository::find_todo_by_id(&db, &todo_id).await.unwrap().unwrap().version > renamed.version);
+
+    let stored = repository::find_course_by_id(&db, &course.id).await.unwrap().unwrap();
+    assert_eq!(course.version, stored.version);
+
+    // rewriting the same content leaves the version alone
+    let current = repository::find_todo_by_id(&db, &todo_id).await.unwrap().unwrap();
+    let upserted = repository::upsert_todo(&db, &current).await.unwrap();
+    assert_eq!(upserted.version, current.version);
+    let upserted = repository::upsert_course(&db, &stored).await.unwrap();
+    assert_eq!(upserted.version, stored.version);
+
+    // sync bookkeeping is not versioned
+    sqlx::query("UPDATE todos SET sync_state = 'synced', last_synced_at = ? WHERE id = ?")
+        .bind(repository::timestamp(chrono::Utc::now()))
+        .bind(&todo_id)
+        .execute(&db)
+        .await
+        .unwrap();
+    repository::mark_pulled_unchanged(&db, ChangeEntity::Todo, &todo_id, chrono::Utc::now()).await.unwrap();
+    let synced = repository::find_todo_by_id(&db, &todo_id).await.unwrap().unwrap();
+    assert_eq!(synced.sync_state, "synced");
+    assert_eq!(synced.version, current.version);
+}
+
+#[tokio::test]
+async fn test_history_records_changed_fields() {
+    let db = setup_db().await;
+    let statuses = StatusMapping::default();
+    let course = repository::insert_course(&db, new_course("Optics")).await.unwrap();
+    let todo_id = insert_todo(&db, &course.id, "Lab 1", "2026-10-20", TodoStatus::NotStarted).await;
+    repository::record_operation(&db, "phone", "create_todo", ChangeEntity::Todo, &todo_id, None).await.unwrap();
+
+    let before = repository::snapshot(&db, ChangeEntity::Todo, &todo_id).await.unwrap();
+    let rename = UpdateTodoRequest { title: Some("Lab 1 report".to_string()), due_date: None, due_end: None, due_timezone: None, status: None, priority: None, tags: None, notes: None };
+    repository::update_todo(&db, &todo_id, rename, &statuses).await.unwrap().unwrap();
+    repository::record_operation(&db, "watch", "update_todo", ChangeEntity::Todo, &todo_id, before).await.unwrap();
+
+    // writes that change nothing are left out
+    let before = repository::snapshot(&db, ChangeEntity::Todo, &todo_id).await.unwrap();
+    repository::record_operation(&db, "watch", "update_todo", ChangeEntity::Todo, &todo_id, before).await.unwrap();
+
+    let history = repository::fetch_history(&db, ChangeEntity::Todo, &todo_id, 10).await.unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!((history[0].source, history[0].client_id.as_deref()), (HistorySource::Api, Some("watch")));
+    assert_eq!(history[0].changes.keys().collect::<Vec<_>>(), vec!["title"]);
+    assert_eq!(history[0].changes["title"].from, "Lab 1");
+    assert_eq!(history[0].changes["title"].to, "Lab 1 report");
+    assert_eq!(history[1].action, "create_todo");
+    assert_eq!(history[1].changes["title"].from, serde_json::Value::Null);
+
+    assert_eq!(repository::fetch_history(&db, ChangeEntity::Todo, &todo_id, 1).await.unwrap().len(), 1);
+    assert!(repository::fetch_history(&db, ChangeEntity::Course, &todo_id, 10).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_unreadable_legacy_values_are_quarantined() {
+    let db = setup_db().await;
+    let course = repository::insert_course(&db, new_course("Physics")).await.unwrap();
+    let current = insert_todo(&db, &course.id, "Current", "2026-10-20", TodoStatus::NotStarted).await;
+    sqlx::query(
+        "INSERT INTO todos (id, course_id, title, due_date, status, is_archived, updated_at, completed_at, sync_state) \
+         VALUES ('legacy', ?, 'Legacy', 'tomorrow', 'not_started', 0, '2026-10-18 21:00:00', 'yesterday', 'synced')",
+    )
+    .bind(&course.id)
+    .execute(&db)
+    .await
+    .unwrap();
+    assert!(repository::fetch_todos(&db).await.is_err(), "'tomorrow' cannot be decoded");
+
+    sqlx::raw_sql(include_str!("../migrations/0005_normalize_timestamps.sql")).execute(&db).await.unwrap();
+
+    let todos = repository::fetch_todos(&db).await.unwrap();
+    let legacy = todos.iter().find(|t| t.id == "legacy").unwrap();
+    assert_eq!(legacy.due_date.to_string(), "2026-10-18");
+    assert!(legacy.completed_at.is_none());
+    let quarantined = repository::fetch_quarantined_values(&db).await.unwrap();
+    assert_eq!(
+        quarantined.iter().map(|(_, id, field, value)| (id.as_str(), field.as_str(), value.as_str())).collect::<Vec<_>>(),
+        vec![("legacy", "due_date", "tomorrow"), ("legacy", "completed_at", "yesterday")]
+    );
+
+    // every updated_at has the same fixed-width form, so text order is time order
+    let stamps: Vec<String> = sqlx::query_scalar("SELECT updated_at FROM todos ORDER BY id").fetch_all(&db).await.unwrap();
+    assert!(stamps.iter().all(|s| s.len() == "2026-10-18T21:00:00.000+00:00".len() && s.ends_with("+00:00")), "{:?}", stamps);
+    assert!(todos.iter().any(|t| t.id == current));
+
+    // so do the subtask, operation log and history writers
+    let step = NewSubtaskRequest { title: "Outline".to_string(), done: false, position: None };
+    repository::insert_subtask(&db, &current, step).await.unwrap();
+    repository::record_operation(&db, "mac", "create_todo", ChangeEntity::Todo, &current, None).await.unwrap();
+    let stamps: Vec<String> = sqlx::query_scalar(
+        "SELECT updated_at FROM subtasks UNION ALL SELECT created_at FROM operation_log \
+         UNION ALL SELECT changed_at FROM entity_history",
+    )
+    .fetch_all(&db)
+    .await
+    .unwrap();
+    assert!(stamps.iter().all(|s| s.len() == "2026-10-18T21:00:00.000+00:00".len() && s.ends_with("+00:00")), "{:?}", stamps);
+}
@@ -1,9 +1,9 @@
 use std::sync::Arc;
 use std::time::Duration;
-use std::sync::atomic::{AtomicUsize, Ordering};
 use backend::services::SyncScheduler;
 use backend::notion::NoopNotionClient;
 use sqlx::SqlitePool;
+use sqlx::sqlite::SqlitePoolOptions;
 
 #[tokio::test]
 async fn test_scheduler_initialization() {
@@ -14,7 +14,7 @@ async fn test_scheduler_initialization() {
     let notion = Arc::new(NoopNotionClient);
     
     // 10 秒の間隔で scheduler を作成
-    let scheduler = SyncScheduler::new(pool, notion, 10);
+    let _scheduler = SyncScheduler::new(pool, notion, 10);
     
     // 構造体が正常に作成されたことを確認（実行はしない）
     println!("Scheduler created successfully");
@@ -22,56 +22,18 @@ async fn test_scheduler_initialization() {
 
 #[tokio::test]
 async fn test_scheduler_short_interval() {
-    let pool = SqlitePool::connect("sqlite::memory:")
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
         .await
         .expect("Failed to create database");
 
-    // Create schema
-    sqlx::query(
-        r#"
-        CREATE TABLE courses (
-            id TEXT PRIMARY KEY,
-            title TEXT NOT NULL,
-            semester TEXT NOT NULL,
-            day_of_week TEXT NOT NULL,
-            period INTEGER NOT NULL,
-            room TEXT,
-            instructor TEXT,
-            is_archived INTEGER NOT NULL DEFAULT 0,
-            updated_at TEXT NOT NULL,
-            sync_state TEXT NOT NULL CHECK(sync_state IN ('pending', 'synced')) DEFAULT 'pending',
-            last_synced_at TEXT
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await
-    .expect("Failed to create courses table");
-
-    sqlx::query(
-        r#"
-        CREATE TABLE todos (
-            id TEXT PRIMARY KEY,
-            course_id TEXT NOT NULL,
-            title TEXT NOT NULL,
-            due_date TEXT NOT NULL,
-            status TEXT NOT NULL,
-            completed_at TEXT,
-            is_archived INTEGER NOT NULL DEFAULT 0,
-            updated_at TEXT NOT NULL,
-            sync_state TEXT NOT NULL CHECK(sync_state IN ('pending', 'synced')) DEFAULT 'pending',
-            last_synced_at TEXT,
-            FOREIGN KEY (course_id) REFERENCES courses(id) ON DELETE CASCADE
-        )
-        "#,
-    )
-    .execute(&pool)
-    .await
-    .expect("Failed to create todos table");
+    sqlx::migrate!("./migrations")
+        .run(&pool)
+        .await
+        .expect("Failed to run migrations");
 
     let notion = Arc::new(NoopNotionClient);
-    let sync_counter = Arc::new(AtomicUsize::new(0));
-    let counter_clone = sync_counter.clone();
 
     // 1 秒の間隔で scheduler を作成
     let scheduler = SyncScheduler::new(pool, notion, 1);